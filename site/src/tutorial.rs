@@ -422,6 +422,8 @@ fn TutorialTypes() -> impl IntoView {
         <h2 id="numbers">"Numbers"</h2>
         <p>"Numbers are decimal numbers with floating precision. They use a 64-bit floating-point representation."</p>
         <Editor example="[5 0 3.2 ¯1.1 π ∞]"/>
+        <p>"Whole numbers can also be written in hexadecimal with a "<code>"0x"</code>" prefix or in binary with a "<code>"0b"</code>" prefix."</p>
+        <Editor example="[0xFF 0b1010 ¯0x10]"/>
         <p>"Most math operations can only be applied to numbers."</p>
         <p>"Even though numbers can have a fractional part, many built-in functions require whole numbers. These functions will return an error if given a non-whole number."</p>
         <p>"One such example is "<Prim prim=Pick/>"."</p>
@@ -445,6 +447,8 @@ fn TutorialTypes() -> impl IntoView {
         <Editor example="&p $ Hello, \n   $ World!"/>
         <p>"This style of string is also useful when your string contains a lot of quotes that you don't want to escape."</p>
         <Editor example="$ An then she was like, \"No way!\"\n$ And I was like, \"Way...\""/>
+        <p>"Lines that start with "<code>"$$"</code>" followed by a space are raw. Escape sequences in them are not processed."</p>
+        <Editor example="&p $$ \\d+\\.\\d*\n   $$ C:\\Users"/>
         <br/>
 
         <h2 id="character-arithmetic">"Character Arithmetic"</h2>
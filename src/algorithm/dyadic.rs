@@ -124,8 +124,10 @@ impl Value {
         self.join_impl(other, ()).unwrap()
     }
     fn join_impl<C: FillContext>(self, other: Self, ctx: C) -> Result<Self, C::Error> {
-        Ok(match (self, other) {
+        let (a, b) = Value::unify_ints(self, other);
+        Ok(match (a, b) {
             (Value::Num(a), Value::Num(b)) => a.join_impl(b, ctx)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.join_impl(b, ctx)?.into(),
             (Value::Byte(a), Value::Byte(b)) => op2_bytes_retry_fill::<_, C>(
                 a,
                 b,
@@ -144,8 +146,10 @@ impl Value {
         })
     }
     pub(crate) fn append<C: FillContext>(&mut self, other: Self, ctx: C) -> Result<(), C::Error> {
+        let other = self.unify_ints_mut(other);
         match (&mut *self, other) {
             (Value::Num(a), Value::Num(b)) => a.append(b, ctx)?,
+            (Value::Int(a), Value::Int(b)) => a.append(b, ctx)?,
             (Value::Byte(a), Value::Byte(b)) => {
                 *self = op2_bytes_retry_fill::<_, C>(
                     a.clone(),
@@ -276,9 +280,9 @@ impl<T: ArrayValue> Array<T> {
             }
             if &self.shape()[1..] != other.shape() {
                 return Err(C::fill_error(ctx.error(format!(
-                    "Cannot append arrays of shapes {} and {}",
-                    self.format_shape(),
-                    other.format_shape()
+                    "Cannot append row of shape {} to array with row shape {}",
+                    other.format_shape(),
+                    FormatShape(&self.shape()[1..])
                 ))));
             }
             take(&mut self.shape)
@@ -304,8 +308,10 @@ impl Value {
         other: Self,
         ctx: C,
     ) -> Result<(), C::Error> {
+        let other = self.unify_ints_mut(other);
         match (&mut *self, other) {
             (Value::Num(a), Value::Num(b)) => a.couple_impl(b, ctx)?,
+            (Value::Int(a), Value::Int(b)) => a.couple_impl(b, ctx)?,
             (Value::Byte(a), Value::Byte(b)) => {
                 *self = op2_bytes_retry_fill::<_, C>(
                     a.clone(),
@@ -345,7 +351,9 @@ impl Value {
     pub fn uncouple(self, env: &Uiua) -> UiuaResult<(Self, Self)> {
         match self {
             Value::Num(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
+            Value::Int(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Byte(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
+            Value::Complex(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Char(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
             Value::Func(a) => a.uncouple(env).map(|(a, b)| (a.into(), b.into())),
         }
@@ -478,28 +486,39 @@ impl<T: ArrayValue> Array<T> {
 }
 
 impl Value {
-    pub fn reshape(&mut self, shape: &Self, env: &Uiua) -> UiuaResult {
+    /// Reshape the value using another value as the shape, as the `reshape` primitive does
+    pub fn reshape_by(&mut self, shape: &Self, env: &Uiua) -> UiuaResult {
         if let Ok(n) = shape.as_nat(env, "") {
-            match self {
-                Value::Num(a) => a.reshape_scalar(n),
-                Value::Byte(a) => a.reshape_scalar(n),
-                Value::Char(a) => a.reshape_scalar(n),
-                Value::Func(a) => a.reshape_scalar(n),
-            }
+            self.generic_mut_shallow(
+                |a| a.reshape_scalar(n),
+                |a| a.reshape_scalar(n),
+                |a| a.reshape_scalar(n),
+                |a| a.reshape_scalar(n),
+                |a| a.reshape_scalar(n),
+                |a| a.reshape_scalar(n),
+            );
+            Ok(())
         } else {
             let target_shape = shape.as_integers(
                 env,
                 "Shape should be a single natural number \
                 or a list of integers",
             )?;
-            match self {
-                Value::Num(a) => a.reshape(&target_shape, env),
-                Value::Byte(a) => a.reshape(&target_shape, env),
-                Value::Char(a) => a.reshape(&target_shape, env),
-                Value::Func(a) => a.reshape(&target_shape, env),
-            }?
+            self.reshape(&target_shape, env)
         }
-        Ok(())
+    }
+    /// Reshape the value to the given dimensions
+    ///
+    /// A single negative dimension is inferred from the number of elements.
+    pub fn reshape(&mut self, shape: &[isize], env: &Uiua) -> UiuaResult {
+        self.generic_mut_shallow(
+            |a| a.reshape(shape, env),
+            |a| a.reshape(shape, env),
+            |a| a.reshape(shape, env),
+            |a| a.reshape(shape, env),
+            |a| a.reshape(shape, env),
+            |a| a.reshape(shape, env),
+        )
     }
 }
 
@@ -629,19 +648,82 @@ impl Value {
         Ok(if self.rank() == 0 {
             match kept {
                 Value::Num(a) => a.scalar_keep(counts[0]).into(),
+                Value::Int(a) => a.scalar_keep(counts[0]).into(),
                 Value::Byte(a) => a.scalar_keep(counts[0]).into(),
+                Value::Complex(a) => a.scalar_keep(counts[0]).into(),
                 Value::Char(a) => a.scalar_keep(counts[0]).into(),
                 Value::Func(a) => a.scalar_keep(counts[0]).into(),
             }
         } else {
             match kept {
                 Value::Num(a) => a.list_keep(&counts, env)?.into(),
+                Value::Int(a) => a.list_keep(&counts, env)?.into(),
                 Value::Byte(a) => a.list_keep(&counts, env)?.into(),
+                Value::Complex(a) => a.list_keep(&counts, env)?.into(),
                 Value::Char(a) => a.list_keep(&counts, env)?.into(),
                 Value::Func(a) => a.list_keep(&counts, env)?.into(),
             }
         })
     }
+    /// Choose each element from one of two arrays based on a mask
+    ///
+    /// Where the mask is `1`, the element comes from `if_true`.
+    /// Where it is `0`, the element comes from `if_false`.
+    /// The arrays pervade like the arguments of a dyadic pervasive function.
+    pub fn if_else(&self, if_true: Self, if_false: Self, env: &Uiua) -> UiuaResult<Self> {
+        let mask = self.as_number_array(
+            env,
+            "Mask must be an array of 0s and 1s",
+            |_| true,
+            |n| n == 0.0 || n == 1.0,
+            |n| n as u8,
+        )?;
+        let (if_true, if_false) = Value::unify_ints(if_true, if_false);
+        Ok(match (if_true, if_false) {
+            (Value::Num(a), Value::Num(b)) => a.if_else(b, &mask, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.if_else(b, &mask, env)?.into(),
+            (Value::Byte(a), Value::Byte(b)) => a.if_else(b, &mask, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => a.if_else(b, &mask, env)?.into(),
+            (Value::Char(a), Value::Char(b)) => a.if_else(b, &mask, env)?.into(),
+            (Value::Byte(a), Value::Num(b)) => a.convert().if_else(b, &mask, env)?.into(),
+            (Value::Num(a), Value::Byte(b)) => a.if_else(b.convert(), &mask, env)?.into(),
+            (a, b) => a.coerce_to_functions(
+                b,
+                env,
+                |a, b, env| Ok(a.if_else(b, &mask, env)?.into()),
+                |a, b| format!("Cannot choose between {a} array and {b} array"),
+            )?,
+        })
+    }
+    /// Select the rows for which the mask is `1`
+    ///
+    /// The mask must be a list of `0`s and `1`s with one element per row.
+    pub fn mask_select(&self, mask: &Value, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot mask a scalar"));
+        }
+        let mask = mask.as_number_list(
+            env,
+            "Mask must be a list of 0s and 1s",
+            |n| n == 0.0 || n == 1.0,
+            |n| n as usize,
+        )?;
+        if mask.len() != self.row_count() {
+            return Err(env.error(format!(
+                "Cannot mask array with {} rows with a mask of length {}",
+                self.row_count(),
+                mask.len()
+            )));
+        }
+        Ok(match self.clone() {
+            Value::Num(a) => a.list_keep(&mask, env)?.into(),
+            Value::Int(a) => a.list_keep(&mask, env)?.into(),
+            Value::Byte(a) => a.list_keep(&mask, env)?.into(),
+            Value::Complex(a) => a.list_keep(&mask, env)?.into(),
+            Value::Char(a) => a.list_keep(&mask, env)?.into(),
+            Value::Func(a) => a.list_keep(&mask, env)?.into(),
+        })
+    }
     pub fn unkeep(self, kept: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let counts = self.as_naturals(
             env,
@@ -651,8 +733,9 @@ impl Value {
         if self.rank() == 0 {
             return Err(env.error("Cannot invert scalar keep"));
         }
-        Ok(match (kept, into) {
+        Ok(match Value::unify_ints(kept, into) {
             (Value::Num(a), Value::Num(b)) => a.unkeep(&counts, b, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.unkeep(&counts, b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.unkeep(&counts, b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.unkeep(&counts, b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.unkeep(&counts, b, env)?.into(),
@@ -668,6 +751,46 @@ impl Value {
     }
 }
 
+impl<T: ArrayValue> Array<T> {
+    /// Choose each element from this array where the mask is `1`
+    /// and from the other array where it is `0`
+    pub fn if_else(self, other: Self, mask: &Array<u8>, env: &Uiua) -> UiuaResult<Self> {
+        let shape = [mask.shape(), self.shape(), other.shape()]
+            .into_iter()
+            .max_by_key(|shape| shape.len())
+            .unwrap();
+        if [mask.shape(), self.shape(), other.shape()]
+            .iter()
+            .any(|sh| !shape.starts_with(sh))
+        {
+            return Err(env.error(format!(
+                "Shapes {}, {}, and {} do not match",
+                mask.format_shape(),
+                self.format_shape(),
+                other.format_shape()
+            )));
+        }
+        // Each element of a lower-rank array is used for a whole block of the result
+        let block_len = |sh: &[usize]| shape[sh.len()..].iter().product::<usize>();
+        let (mask_block, true_block, false_block) = (
+            block_len(mask.shape()),
+            block_len(self.shape()),
+            block_len(other.shape()),
+        );
+        let len: usize = shape.iter().product();
+        let data: CowSlice<T> = (0..len)
+            .map(|i| {
+                if mask.data[i / mask_block] != 0 {
+                    self.data[i / true_block].clone()
+                } else {
+                    other.data[i / false_block].clone()
+                }
+            })
+            .collect();
+        Ok(Array::new(shape, data))
+    }
+}
+
 impl<T: ArrayValue> Array<T> {
     pub fn scalar_keep(mut self, count: usize) -> Self {
         // Scalar kept
@@ -861,19 +984,22 @@ impl Value {
         let (index_shape, index_data) = self.into_shaped_indices(env)?;
         Ok(match from {
             Value::Num(a) => Value::Num(a.pick_shaped(&index_shape, &index_data, env)?),
+            Value::Int(a) => Value::Int(a.pick_shaped(&index_shape, &index_data, env)?),
             Value::Byte(a) => op_bytes_retry_fill(
                 a,
                 |a| Ok(a.pick_shaped(&index_shape, &index_data, env)?.into()),
                 |a| Ok(a.pick_shaped(&index_shape, &index_data, env)?.into()),
             )?,
+            Value::Complex(a) => Value::Complex(a.pick_shaped(&index_shape, &index_data, env)?),
             Value::Char(a) => Value::Char(a.pick_shaped(&index_shape, &index_data, env)?),
             Value::Func(a) => Value::Func(a.pick_shaped(&index_shape, &index_data, env)?),
         })
     }
     pub fn unpick(self, index: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let index = index.as_indices(env, "Index must be an array of integers")?;
-        Ok(match (self, into) {
+        Ok(match Value::unify_ints(self, into) {
             (Value::Num(a), Value::Num(b)) => a.unpick_impl(&index, b, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.unpick_impl(&index, b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.unpick_impl(&index, b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.unpick_impl(&index, b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.unpick_impl(&index, b, env)?.into(),
@@ -987,11 +1113,13 @@ impl Value {
         let index = self.as_indices(env, "Index must be a list of integers")?;
         Ok(match from {
             Value::Num(a) => Value::Num(a.take(&index, env)?),
+            Value::Int(a) => Value::Int(a.take(&index, env)?),
             Value::Byte(a) => op_bytes_retry_fill(
                 a,
                 |a| Ok(a.take(&index, env)?.into()),
                 |a| Ok(a.take(&index, env)?.into()),
             )?,
+            Value::Complex(a) => Value::Complex(a.take(&index, env)?),
             Value::Char(a) => Value::Char(a.take(&index, env)?),
             Value::Func(a) => Value::Func(a.take(&index, env)?),
         })
@@ -1003,15 +1131,18 @@ impl Value {
         let index = self.as_indices(env, "Index must be a list of integers")?;
         Ok(match from {
             Value::Num(a) => Value::Num(a.drop(&index, env)?),
+            Value::Int(a) => Value::Int(a.drop(&index, env)?),
             Value::Byte(a) => Value::Byte(a.drop(&index, env)?),
+            Value::Complex(a) => Value::Complex(a.drop(&index, env)?),
             Value::Char(a) => Value::Char(a.drop(&index, env)?),
             Value::Func(a) => Value::Func(a.drop(&index, env)?),
         })
     }
     pub(crate) fn untake(self, index: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let index = index.as_indices(env, "Index must be a list of integers")?;
-        Ok(match (self, into) {
+        Ok(match Value::unify_ints(self, into) {
             (Value::Num(a), Value::Num(b)) => Value::Num(a.untake(&index, b, env)?),
+            (Value::Int(a), Value::Int(b)) => Value::Int(a.untake(&index, b, env)?),
             (Value::Byte(a), Value::Byte(b)) => Value::Byte(a.untake(&index, b, env)?),
             (Value::Char(a), Value::Char(b)) => Value::Char(a.untake(&index, b, env)?),
             (Value::Func(a), Value::Func(b)) => Value::Func(a.untake(&index, b, env)?),
@@ -1028,8 +1159,9 @@ impl Value {
     }
     pub(crate) fn undrop(self, index: Self, into: Self, env: &Uiua) -> UiuaResult<Self> {
         let index = index.as_indices(env, "Index must be a list of integers")?;
-        Ok(match (self, into) {
+        Ok(match Value::unify_ints(self, into) {
             (Value::Num(a), Value::Num(b)) => Value::Num(a.undrop(&index, b, env)?),
+            (Value::Int(a), Value::Int(b)) => Value::Int(a.undrop(&index, b, env)?),
             (Value::Byte(a), Value::Byte(b)) => Value::Byte(a.undrop(&index, b, env)?),
             (Value::Char(a), Value::Char(b)) => Value::Char(a.undrop(&index, b, env)?),
             (Value::Func(a), Value::Func(b)) => Value::Func(a.undrop(&index, b, env)?),
@@ -1329,12 +1461,34 @@ impl Value {
         let by = self.as_indices(env, "Rotation amount must be a list of integers")?;
         match &mut rotated {
             Value::Num(a) => a.rotate(&by, env)?,
+            Value::Int(a) => a.rotate(&by, env)?,
             Value::Byte(a) => a.rotate(&by, env)?,
+            Value::Complex(a) => a.rotate(&by, env)?,
             Value::Char(a) => a.rotate(&by, env)?,
             Value::Func(a) => a.rotate(&by, env)?,
         }
         Ok(rotated)
     }
+    /// Cyclically rotate the array along the given axis
+    ///
+    /// Positive amounts move elements toward the start of the axis, like [`Value::rotate`].
+    pub fn rotate_axis(&mut self, amount: isize, axis: usize, env: &Uiua) -> UiuaResult {
+        if axis >= self.rank() {
+            return Err(env.error(format!(
+                "Cannot rotate axis {axis} of array with rank {}",
+                self.rank()
+            )));
+        }
+        self.generic_mut_shallow(
+            |a| a.rotate_axis(amount, axis),
+            |a| a.rotate_axis(amount, axis),
+            |a| a.rotate_axis(amount, axis),
+            |a| a.rotate_axis(amount, axis),
+            |a| a.rotate_axis(amount, axis),
+            |a| a.rotate_axis(amount, axis),
+        );
+        Ok(())
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -1349,6 +1503,25 @@ impl<T: ArrayValue> Array<T> {
         rotate(by, &self.shape, self.data.as_mut_slice());
         Ok(())
     }
+    /// Cyclically rotate the array along the given axis
+    ///
+    /// # Panics
+    /// Panics if the axis is out of bounds
+    pub fn rotate_axis(&mut self, amount: isize, axis: usize) {
+        let axis_len = self.shape[axis];
+        let inner_len: usize = self.shape[axis + 1..].iter().product();
+        let block_len = axis_len * inner_len;
+        if block_len == 0 {
+            return;
+        }
+        let mid = amount.rem_euclid(axis_len as isize) as usize;
+        if mid == 0 {
+            return;
+        }
+        for block in self.data.as_mut_slice().chunks_exact_mut(block_len) {
+            block.rotate_left(mid * inner_len);
+        }
+    }
 }
 
 fn rotate<T>(by: &[isize], shape: &[usize], data: &mut [T]) {
@@ -1390,6 +1563,11 @@ impl Value {
                     indices.push(i as isize);
                 }
             }
+            Value::Int(arr) => {
+                for &i in arr.data.iter() {
+                    indices.push(i as isize);
+                }
+            }
             Value::Byte(arr) => {
                 for &i in arr.data.iter() {
                     indices.push(i as isize);
@@ -1408,11 +1586,13 @@ impl Value {
         let (indices_shape, indices) = self.as_index_array(env)?;
         Ok(match from {
             Value::Num(a) => a.select_impl(indices_shape, &indices, env)?.into(),
+            Value::Int(a) => a.select_impl(indices_shape, &indices, env)?.into(),
             Value::Byte(a) => op_bytes_ref_retry_fill(
                 a,
                 |a| Ok(a.select_impl(indices_shape, &indices, env)?.into()),
                 |a| Ok(a.select_impl(indices_shape, &indices, env)?.into()),
             )?,
+            Value::Complex(a) => a.select_impl(indices_shape, &indices, env)?.into(),
             Value::Char(a) => a.select_impl(indices_shape, &indices, env)?.into(),
             Value::Func(a) => a.select_impl(indices_shape, &indices, env)?.into(),
         })
@@ -1424,8 +1604,9 @@ impl Value {
         if sorted_indices.windows(2).any(|win| win[0] == win[1]) {
             return Err(env.error("Cannot undo selection with duplicate indices"));
         }
-        Ok(match (self, into) {
+        Ok(match Value::unify_ints(self, into) {
             (Value::Num(a), Value::Num(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.unselect_impl(ind_shape, &ind, b, env)?.into(),
@@ -1581,11 +1762,227 @@ impl Value {
         let size_spec = self.as_naturals(env, "Window size must be a list of natural numbers")?;
         Ok(match from {
             Value::Num(a) => a.windows(&size_spec, env)?.into(),
+            Value::Int(a) => a.windows(&size_spec, env)?.into(),
             Value::Byte(a) => a.windows(&size_spec, env)?.into(),
+            Value::Complex(a) => a.windows(&size_spec, env)?.into(),
             Value::Char(a) => a.windows(&size_spec, env)?.into(),
             Value::Func(a) => a.windows(&size_spec, env)?.into(),
         })
     }
+    /// Get the dot product of two numeric lists
+    pub fn dot(&self, other: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let a = self.as_number_list(
+            env,
+            "Dot product arguments must be lists of numbers",
+            |_| true,
+            |n| n,
+        )?;
+        let b = other.as_number_list(
+            env,
+            "Dot product arguments must be lists of numbers",
+            |_| true,
+            |n| n,
+        )?;
+        if a.len() != b.len() {
+            return Err(env.error(format!(
+                "Cannot get the dot product of lists of lengths {} and {}",
+                a.len(),
+                b.len()
+            )));
+        }
+        Ok(a.iter().zip(&b).map(|(a, b)| a * b).sum::<f64>().into())
+    }
+    /// Get the `q` quantile of the rows of a numeric array
+    ///
+    /// Each column is sorted, and the result is linearly interpolated between
+    /// the two rows closest to the rank `q × (row count - 1)`.
+    pub fn quantile(&self, data: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let q = self.as_num(env, "Quantile must be a single number")?;
+        if !(0.0..=1.0).contains(&q) {
+            return Err(env.error(format!("Quantile must be between 0 and 1, but it is {q}")));
+        }
+        let nums = match data {
+            Value::Num(arr) => arr.clone(),
+            Value::Byte(arr) => arr.clone().convert(),
+            Value::Int(arr) => arr.clone().convert_with(|i| i as f64),
+            value => {
+                return Err(env.error(format!(
+                    "Cannot get the quantile of {} array",
+                    value.type_name()
+                )))
+            }
+        };
+        if nums.rank() == 0 {
+            return Err(env.error("Cannot get the quantile of a scalar"));
+        }
+        let row_count = nums.row_count();
+        let row_len = nums.row_len();
+        let shape = Shape::from(&nums.shape[1..]);
+        if row_count == 0 {
+            let fill = env
+                .num_fill()
+                .ok_or_else(|| env.error("Cannot get the quantile of an empty array"))?;
+            return Ok(Array::new(shape, EcoVec::from(vec![fill; row_len])).into());
+        }
+        let rank = q * (row_count - 1) as f64;
+        let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+        let frac = rank - lo as f64;
+        let mut column = Vec::with_capacity(row_count);
+        let mut data = EcoVec::with_capacity(row_len);
+        for j in 0..row_len {
+            column.clear();
+            column.extend((0..row_count).map(|i| nums.data[i * row_len + j]));
+            column.sort_unstable_by(f64::total_cmp);
+            data.push(if lo == hi {
+                column[lo]
+            } else {
+                column[lo] * (1.0 - frac) + column[hi] * frac
+            });
+        }
+        Ok(Array::new(shape, data).into())
+    }
+    /// Split the rows into consecutive chunks of `size` rows
+    ///
+    /// The result is a list of boxed chunks. If the row count is not a multiple of `size`,
+    /// the final chunk is shorter when `keep_partial` is set and dropped otherwise.
+    pub fn chunks(&self, size: usize, keep_partial: bool, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot get chunks of a scalar"));
+        }
+        if size == 0 {
+            return Err(env.error("Chunk size must be positive"));
+        }
+        let rows: Vec<Value> = self.rows().collect();
+        let chunks: EcoVec<Arc<Function>> = (rows.chunks(size))
+            .filter(|chunk| keep_partial || chunk.len() == size)
+            .map(|chunk| {
+                Arc::new(Function::boxed(Value::from_row_values_infallible(
+                    chunk.to_vec(),
+                )))
+            })
+            .collect();
+        Ok(chunks.into())
+    }
+    /// Pair every row of this array with every row of another
+    ///
+    /// The result has one row per pair, and each row is a box of a row of this array
+    /// followed by a box of a row of the other. This is like [cross](crate::primitive::Primitive::Cross)
+    /// with a function that boxes and joins its arguments.
+    pub fn cross(&self, other: &Self) -> Self {
+        let pair_count = self.row_count() * other.row_count();
+        let other_rows: Vec<Arc<Function>> = (other.rows())
+            .map(|row| Arc::new(Function::boxed(row)))
+            .collect();
+        let mut data = EcoVec::with_capacity(pair_count * 2);
+        for row in self.rows() {
+            let row = Arc::new(Function::boxed(row));
+            for other_row in &other_rows {
+                data.push(row.clone());
+                data.push(other_row.clone());
+            }
+        }
+        Array::new(tiny_vec![pair_count, 2], data).into()
+    }
+    /// Get the sliding windows of `size` consecutive rows
+    ///
+    /// Unlike [`Value::windows`], a size that does not fit is an error.
+    pub fn window(&self, size: usize, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot get windows of a scalar"));
+        }
+        if size == 0 || size > self.row_count() {
+            return Err(env.error(format!(
+                "Window size {size} is invalid for an array with {} rows",
+                self.row_count()
+            )));
+        }
+        let size_spec = [size];
+        Ok(match self {
+            Value::Num(a) => a.windows(&size_spec, env)?.into(),
+            Value::Int(a) => a.windows(&size_spec, env)?.into(),
+            Value::Byte(a) => a.windows(&size_spec, env)?.into(),
+            Value::Complex(a) => a.windows(&size_spec, env)?.into(),
+            Value::Char(a) => a.windows(&size_spec, env)?.into(),
+            Value::Func(a) => a.windows(&size_spec, env)?.into(),
+        })
+    }
+    /// Find the index at which each query would be inserted into this sorted list
+    ///
+    /// This uses a binary search, so the list is assumed to be sorted in ascending order
+    /// and is not checked. If a query is equal to some elements, the index is the leftmost
+    /// position among them, or the rightmost if `right` is set.
+    pub fn bisect(&self, queries: &Self, right: bool, env: &Uiua) -> UiuaResult<Array<f64>> {
+        let sorted = self.as_number_list(
+            env,
+            "Sorted array must be a list of numbers",
+            |_| true,
+            |n| n,
+        )?;
+        let queries =
+            queries.as_number_array(env, "Queries must be numbers", |_| true, |_| true, |n| n)?;
+        let data: EcoVec<f64> = (queries.data.iter())
+            .map(|&q| {
+                let index = if right {
+                    sorted.partition_point(|&n| n <= q)
+                } else {
+                    sorted.partition_point(|&n| n < q)
+                };
+                index as f64
+            })
+            .collect();
+        Ok(Array::new(queries.shape, data))
+    }
+    /// Alternate the rows of this array with the rows of another
+    ///
+    /// If one array has more rows, its extra rows are added at the end.
+    pub fn interleave(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+        let (a, b) = Value::unify_ints(self, other);
+        Ok(match (a, b) {
+            (Value::Num(a), Value::Num(b)) => a.interleave(b, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.interleave(b, env)?.into(),
+            (Value::Byte(a), Value::Byte(b)) => a.interleave(b, env)?.into(),
+            (Value::Complex(a), Value::Complex(b)) => a.interleave(b, env)?.into(),
+            (Value::Char(a), Value::Char(b)) => a.interleave(b, env)?.into(),
+            (Value::Byte(a), Value::Num(b)) => a.convert().interleave(b, env)?.into(),
+            (Value::Num(a), Value::Byte(b)) => a.interleave(b.convert(), env)?.into(),
+            (a, b) => a.coerce_to_functions(
+                b,
+                env,
+                |a, b, env| Ok(a.interleave(b, env)?.into()),
+                |a, b| format!("Cannot interleave {a} array and {b} array"),
+            )?,
+        })
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// Alternate the rows of this array with the rows of another
+    pub fn interleave(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() == 0 || other.rank() == 0 {
+            return Err(env.error("Cannot interleave scalars"));
+        }
+        if self.shape[1..] != other.shape[1..] {
+            return Err(env.error(format!(
+                "Cannot interleave arrays of shapes {} and {} \
+                because their rows have different shapes",
+                self.format_shape(),
+                other.format_shape()
+            )));
+        }
+        let row_len = self.row_len();
+        let row_count = self.row_count() + other.row_count();
+        let mut data = EcoVec::with_capacity(row_count * row_len);
+        for i in 0..self.row_count().max(other.row_count()) {
+            for arr in [&self, &other] {
+                if i < arr.row_count() {
+                    data.extend_from_slice(&arr.data[i * row_len..][..row_len]);
+                }
+            }
+        }
+        let mut shape = self.shape;
+        shape[0] = row_count;
+        Ok(Array::new(shape, data))
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -1664,8 +2061,10 @@ impl<T: ArrayValue> Array<T> {
 
 impl Value {
     pub fn find(&self, searched: &Self, env: &Uiua) -> UiuaResult<Self> {
-        Ok(match (self, searched) {
+        let (a, b) = Value::unify_ints_ref(self, searched);
+        Ok(match (&*a, &*b) {
             (Value::Num(a), Value::Num(b)) => a.find(b, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.find(b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.find(b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.find(b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.find(b, env)?.into(),
@@ -1789,8 +2188,10 @@ impl<'a, T: ArrayValue> Hash for ArrayCmpSlice<'a, T> {
 
 impl Value {
     pub fn member(&self, of: &Self, env: &Uiua) -> UiuaResult<Self> {
-        Ok(match (self, of) {
+        let (a, b) = Value::unify_ints_ref(self, of);
+        Ok(match (&*a, &*b) {
             (Value::Num(a), Value::Num(b)) => a.member(b, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.member(b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.member(b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.member(b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.member(b, env)?.into(),
@@ -1854,8 +2255,10 @@ impl<T: ArrayValue> Array<T> {
 
 impl Value {
     pub fn index_of(&self, searched_in: &Value, env: &Uiua) -> UiuaResult<Value> {
-        Ok(match (self, searched_in) {
+        let (a, b) = Value::unify_ints_ref(self, searched_in);
+        Ok(match (&*a, &*b) {
             (Value::Num(a), Value::Num(b)) => a.index_of(b, env)?.into(),
+            (Value::Int(a), Value::Int(b)) => a.index_of(b, env)?.into(),
             (Value::Byte(a), Value::Byte(b)) => a.index_of(b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.index_of(b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.index_of(b, env)?.into(),
@@ -1935,3 +2338,108 @@ impl<T: ArrayValue> Array<T> {
         })
     }
 }
+
+impl Value {
+    /// Multiply two matrices
+    ///
+    /// The last axis of the first value is contracted with the first axis of the second.
+    /// Rank 2 arrays of non-integer numbers or bytes use a fast blocked, parallel algorithm
+    /// with `f64` accumulation. Other values are multiplied and summed pervasively.
+    pub fn matmul(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+        crate::profile_function!();
+        let (a_shape, b_shape) = (self.shape(), other.shape());
+        if a_shape.is_empty() || b_shape.is_empty() {
+            return Err(env.error(format!(
+                "Cannot matrix multiply arrays of shape {} and {}, \
+                because both must have at least one axis",
+                self.format_shape(),
+                other.format_shape()
+            )));
+        }
+        let n = *a_shape.last().unwrap();
+        if n != b_shape[0] {
+            return Err(env.error(format!(
+                "Cannot matrix multiply arrays of shape {} and {}, \
+                because the first has {n} columns but the second has {} rows",
+                self.format_shape(),
+                other.format_shape(),
+                b_shape[0]
+            )));
+        }
+        let m: usize = a_shape[..a_shape.len() - 1].iter().product();
+        let shape: Shape = (a_shape[..a_shape.len() - 1].iter())
+            .chain(&b_shape[1..])
+            .copied()
+            .collect();
+        let p: usize = b_shape[1..].iter().product();
+        if m == 0 || n == 0 || p == 0 {
+            let zeros = vec![0.0; shape.iter().product()];
+            return Ok(Array::new(shape, zeros.as_slice()).into());
+        }
+        if let (2, 2, Some(a), Some(b)) = (
+            a_shape.len(),
+            b_shape.len(),
+            self.as_matmul_floats(),
+            other.as_matmul_floats(),
+        ) {
+            let data = matmul_f64(&a, &b, m, n, p);
+            return Ok(Array::new(shape, data.as_slice()).into());
+        }
+        // Generic path
+        let mut a = self;
+        a.reshape(&[m as isize, n as isize], env)?;
+        let b_rows: Vec<Value> = other.into_rows().collect();
+        let mut rows = Vec::with_capacity(m);
+        for a_row in a.into_rows() {
+            let mut sum: Option<Value> = None;
+            for (x, b_row) in a_row.into_rows().zip(&b_rows) {
+                let product = x.mul(b_row.clone(), env)?;
+                sum = Some(match sum {
+                    Some(sum) => sum.add(product, env)?,
+                    None => product,
+                });
+            }
+            rows.push(sum.unwrap());
+        }
+        let mut result = Value::from_row_values(rows, env)?;
+        let shape: Vec<isize> = shape.iter().map(|&d| d as isize).collect();
+        result.reshape(&shape, env)?;
+        Ok(result)
+    }
+    fn as_matmul_floats(&self) -> Option<Cow<'_, [f64]>> {
+        match self {
+            Value::Num(arr) => Some(Cow::Borrowed(&arr.data)),
+            Value::Byte(arr) => Some(Cow::Owned(arr.data.iter().map(|&b| b as f64).collect())),
+            _ => None,
+        }
+    }
+}
+
+/// Multiply an `m`×`n` matrix by an `n`×`p` matrix
+fn matmul_f64(a: &[f64], b: &[f64], m: usize, n: usize, p: usize) -> Vec<f64> {
+    use rayon::prelude::*;
+    const BLOCK: usize = 64;
+    let mut result = vec![0.0; m * p];
+    result
+        .par_chunks_mut(BLOCK * p)
+        .enumerate()
+        .for_each(|(block, out)| {
+            let row_start = block * BLOCK;
+            // Accumulate over blocks of the shared axis so that
+            // the rows of `b` stay in cache
+            for k_start in (0..n).step_by(BLOCK) {
+                let k_end = (k_start + BLOCK).min(n);
+                for (r, out_row) in out.chunks_exact_mut(p).enumerate() {
+                    let a_row = &a[(row_start + r) * n..][..n];
+                    for k in k_start..k_end {
+                        let a_rk = a_row[k];
+                        let b_row = &b[k * p..][..p];
+                        for (o, &b_kj) in out_row.iter_mut().zip(b_row) {
+                            *o += a_rk * b_kj;
+                        }
+                    }
+                }
+            }
+        });
+    result
+}
@@ -0,0 +1,583 @@
+//! Algorithms for encoding and decoding interchange formats
+
+use std::{mem::take, sync::Arc};
+
+use ecow::EcoVec;
+use serde_json::{Map, Number, Value as Json};
+use tinyvec::tiny_vec;
+
+use crate::{
+    array::{Array, ArrayValue},
+    function::Function,
+    value::{Value, ValueBuilder},
+    Uiua, UiuaResult,
+};
+
+impl Value {
+    /// Decode a JSON string into a value
+    ///
+    /// - Numbers become scalar numbers
+    /// - Strings become character arrays
+    /// - `true` and `false` become the numbers `1` and `0`
+    /// - `null` becomes `NaN`
+    /// - Arrays whose items all decode to unboxed values with the same type and shape become
+    ///   normal arrays. All other arrays become arrays of boxes.
+    /// - Objects become rank `2` box arrays of key-value pairs
+    pub fn from_json_string(json: &str, env: &Uiua) -> UiuaResult<Self> {
+        let json: Json = serde_json::from_str(json).map_err(|e| {
+            let offset = json_error_offset(json, e.line(), e.column());
+            env.error(format!("Invalid JSON at byte {offset}: {e}"))
+        })?;
+        Value::from_json(json, env)
+    }
+    fn from_json(json: Json, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match json {
+            Json::Null => f64::NAN.into(),
+            Json::Bool(b) => f64::from(u8::from(b)).into(),
+            Json::Number(n) => n.as_f64().unwrap_or(f64::NAN).into(),
+            Json::String(s) => s.into(),
+            Json::Array(items) => {
+                let items = (items.into_iter())
+                    .map(|item| Value::from_json(item, env))
+                    .collect::<UiuaResult<Vec<_>>>()?;
+                collect_items(items, env)?
+            }
+            Json::Object(map) => {
+                let mut pairs = EcoVec::with_capacity(map.len() * 2);
+                for (key, value) in map {
+                    pairs.push(Arc::new(Function::boxed(key)));
+                    pairs.push(Arc::new(Function::boxed(Value::from_json(value, env)?)));
+                }
+                Array::new(tiny_vec![pairs.len() / 2, 2], pairs).into()
+            }
+        })
+    }
+    /// Encode a value as a JSON string
+    ///
+    /// This is the inverse of [`Value::from_json_string`].
+    /// Rank `2` box arrays with `2` columns whose first column is all strings are encoded as objects.
+    /// `NaN` is encoded as `null`.
+    pub fn to_json_string(&self, env: &Uiua) -> UiuaResult<String> {
+        let json = self.to_json(env)?;
+        serde_json::to_string(&json).map_err(|e| env.error(format!("Cannot encode JSON: {e}")))
+    }
+    fn to_json(&self, env: &Uiua) -> UiuaResult<Json> {
+        Ok(match self {
+            Value::Num(arr) => array_to_json(arr, env, |&n, env| {
+                if n.is_nan() {
+                    Ok(Json::Null)
+                } else if n.is_infinite() {
+                    Err(env.error("Cannot encode infinity as JSON"))
+                } else if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+                    Ok(Json::Number((n as i64).into()))
+                } else {
+                    Ok(Number::from_f64(n).map_or(Json::Null, Json::Number))
+                }
+            })?,
+            Value::Int(arr) => array_to_json(arr, env, |&i, _| Ok(Json::Number(i.into())))?,
+            Value::Byte(arr) => array_to_json(arr, env, |&b, _| Ok(Json::Number(b.into())))?,
+            Value::Complex(_) => return Err(env.error("Cannot encode complex numbers as JSON")),
+            Value::Char(arr) if arr.rank() == 0 => Json::String(arr.data[0].to_string()),
+            Value::Char(arr) if arr.rank() == 1 => Json::String(arr.data.iter().collect()),
+            Value::Char(arr) => Json::Array(
+                (arr.rows())
+                    .map(|row| Value::from(row).to_json(env))
+                    .collect::<UiuaResult<_>>()?,
+            ),
+            Value::Func(arr) if arr.rank() == 0 => {
+                let value = arr.data[0].as_boxed().ok_or_else(|| {
+                    env.error("Cannot encode a function as JSON. Only boxed values can be encoded.")
+                })?;
+                value.to_json(env)?
+            }
+            Value::Func(arr) if is_json_object(arr) => {
+                let mut map = Map::with_capacity(arr.row_count());
+                for pair in arr.data.chunks_exact(2) {
+                    let key = pair[0].as_boxed().unwrap().as_char_array().unwrap();
+                    let value = Value::from(Array::<Arc<Function>>::from(pair[1].clone()));
+                    map.insert(key.data.iter().collect(), value.to_json(env)?);
+                }
+                Json::Object(map)
+            }
+            Value::Func(arr) => Json::Array(
+                (arr.rows())
+                    .map(|row| Value::from(row).to_json(env))
+                    .collect::<UiuaResult<_>>()?,
+            ),
+        })
+    }
+    /// Decode a CSV string into a rank `2` array of boxed string fields
+    ///
+    /// Quoted fields may contain commas, newlines, and `""`-escaped quotes.
+    /// Empty lines are skipped, and a trailing newline does not produce an extra row.
+    /// Every row must have the same number of fields.
+    pub fn from_csv_string(csv: &str, env: &Uiua) -> UiuaResult<Self> {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let (mut line, mut col) = (1, 0);
+        let mut quote_start = (1, 1);
+        let mut chars = csv.chars().peekable();
+        while let Some(c) = chars.next() {
+            col += 1;
+            if in_quotes {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        col += 1;
+                        field.push('"');
+                    }
+                    '"' => in_quotes = false,
+                    '\n' => {
+                        (line, col) = (line + 1, 0);
+                        field.push(c);
+                    }
+                    c => field.push(c),
+                }
+                continue;
+            }
+            match c {
+                '"' if field.is_empty() && !quoted => {
+                    quoted = true;
+                    in_quotes = true;
+                    quote_start = (line, col);
+                }
+                '"' => {
+                    return Err(env.error(format!(
+                        "Invalid CSV at line {line}, column {col}: \
+                        unexpected quote in unquoted field"
+                    )))
+                }
+                ',' => {
+                    row.push(take(&mut field));
+                    quoted = false;
+                }
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\n' => {
+                    if !(row.is_empty() && field.is_empty() && !quoted) {
+                        row.push(take(&mut field));
+                        rows.push(take(&mut row));
+                        quoted = false;
+                    }
+                    (line, col) = (line + 1, 0);
+                }
+                _ if quoted => {
+                    return Err(env.error(format!(
+                        "Invalid CSV at line {line}, column {col}: \
+                        unexpected character after closing quote"
+                    )))
+                }
+                c => field.push(c),
+            }
+        }
+        if in_quotes {
+            let (line, col) = quote_start;
+            return Err(env.error(format!(
+                "Invalid CSV at line {line}, column {col}: unterminated quoted field"
+            )));
+        }
+        if !(row.is_empty() && field.is_empty() && !quoted) {
+            row.push(field);
+            rows.push(row);
+        }
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(i) = rows.iter().position(|row| row.len() != width) {
+            return Err(env.error(format!(
+                "Invalid CSV: record {} has {} fields, but the first record has {width}",
+                i + 1,
+                rows[i].len(),
+            )));
+        }
+        let data: EcoVec<_> = (rows.into_iter().flatten())
+            .map(|field| Arc::new(Function::boxed(field)))
+            .collect();
+        Ok(Array::new(tiny_vec![data.len() / width.max(1), width], data).into())
+    }
+    /// Encode a rank `1` or `2` array as a CSV string
+    ///
+    /// Each cell must be a string or a scalar number, possibly [box]ed.
+    /// Fields that contain commas, quotes, or newlines are quoted.
+    pub fn to_csv_string(&self, env: &Uiua) -> UiuaResult<String> {
+        let rows: Vec<Value> = match self.rank() {
+            1 => vec![self.clone()],
+            2 => self.rows().collect(),
+            rank => {
+                return Err(env.error(format!(
+                    "Only rank 1 and 2 arrays can be encoded as CSV, but the array is rank {rank}"
+                )))
+            }
+        };
+        let mut csv = String::new();
+        for row in rows {
+            for (i, cell) in row.rows().enumerate() {
+                if i > 0 {
+                    csv.push(',');
+                }
+                let field = csv_field(&cell, env)?;
+                if field.contains([',', '"', '\n', '\r']) {
+                    csv.push('"');
+                    csv.push_str(&field.replace('"', "\"\""));
+                    csv.push('"');
+                } else {
+                    csv.push_str(&field);
+                }
+            }
+            csv.push('\n');
+        }
+        Ok(csv)
+    }
+    /// Decode MessagePack bytes into a value
+    ///
+    /// Values are decoded like [`Value::from_json_string`], with some additions:
+    /// - Binary data becomes a list of bytes
+    /// - Map keys may be any value, not just strings
+    ///
+    /// Extension types are not supported.
+    pub fn from_msgpack(bytes: &[u8], env: &Uiua) -> UiuaResult<Self> {
+        let mut rest = bytes;
+        let value = msgpack::decode(&mut rest, env)?;
+        if !rest.is_empty() {
+            return Err(env.error(format!(
+                "Invalid MessagePack: {} unexpected bytes after the end of the data",
+                rest.len()
+            )));
+        }
+        Ok(value)
+    }
+    /// Encode a value as MessagePack bytes
+    ///
+    /// This is the inverse of [`Value::from_msgpack`].
+    /// Values are encoded like [`Value::to_json_string`], except that lists of bytes
+    /// are encoded as binary data and non-finite numbers are allowed.
+    pub fn to_msgpack(&self, env: &Uiua) -> UiuaResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        msgpack::encode(self, &mut bytes, env)?;
+        Ok(bytes)
+    }
+    /// Encode a value as a MessagePack byte array value
+    pub(crate) fn msgpack_encode(&self, env: &Uiua) -> UiuaResult<Self> {
+        Ok(Array::<u8>::from_iter(self.to_msgpack(env)?).into())
+    }
+    /// Decode a byte array value from MessagePack
+    pub(crate) fn msgpack_decode(&self, env: &Uiua) -> UiuaResult<Self> {
+        let bytes = self.as_bytes(env, "MessagePack data must be a list of bytes")?;
+        Value::from_msgpack(&bytes, env)
+    }
+}
+
+mod msgpack {
+    use super::*;
+
+    pub fn encode(value: &Value, out: &mut Vec<u8>, env: &Uiua) -> UiuaResult {
+        match value {
+            Value::Num(arr) => encode_array(arr, out, env, |&n, out, _| {
+                if n.fract() == 0.0 && n.abs() < 2f64.powi(63) {
+                    encode_int(n as i64, out);
+                } else {
+                    out.push(0xcb);
+                    out.extend(n.to_be_bytes());
+                }
+                Ok(())
+            })?,
+            Value::Int(arr) => encode_array(arr, out, env, |&i, out, _| {
+                encode_int(i, out);
+                Ok(())
+            })?,
+            Value::Byte(arr) if arr.rank() == 1 => {
+                encode_len(arr.data.len(), [0, 0xc4, 0xc5, 0xc6], 0, out, env)?;
+                out.extend(arr.data.iter());
+            }
+            Value::Byte(arr) => encode_array(arr, out, env, |&b, out, _| {
+                encode_int(b.into(), out);
+                Ok(())
+            })?,
+            Value::Complex(_) => {
+                return Err(env.error("Cannot encode complex numbers as MessagePack"))
+            }
+            Value::Char(arr) if arr.rank() <= 1 => {
+                let s: String = arr.data.iter().collect();
+                encode_len(s.len(), [0xa0, 0xd9, 0xda, 0xdb], 32, out, env)?;
+                out.extend(s.bytes());
+            }
+            Value::Char(arr) => {
+                encode_rows(arr.rows().map(Value::from), arr.row_count(), out, env)?
+            }
+            Value::Func(arr) if arr.rank() == 0 => {
+                let value = arr.data[0].as_boxed().ok_or_else(|| {
+                    env.error(
+                        "Cannot encode a function as MessagePack. \
+                        Only boxed values can be encoded.",
+                    )
+                })?;
+                encode(value, out, env)?
+            }
+            Value::Func(arr) if is_json_object(arr) => {
+                encode_len(arr.row_count(), [0x80, 0xde, 0xde, 0xdf], 16, out, env)?;
+                for pair in arr.data.chunks_exact(2) {
+                    for item in pair {
+                        encode(&Array::<Arc<Function>>::from(item.clone()).into(), out, env)?;
+                    }
+                }
+            }
+            Value::Func(arr) => {
+                encode_rows(arr.rows().map(Value::from), arr.row_count(), out, env)?
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_array<T: ArrayValue>(
+        arr: &Array<T>,
+        out: &mut Vec<u8>,
+        env: &Uiua,
+        scalar: impl Fn(&T, &mut Vec<u8>, &Uiua) -> UiuaResult + Copy,
+    ) -> UiuaResult {
+        if arr.rank() == 0 {
+            return scalar(&arr.data[0], out, env);
+        }
+        encode_len(arr.row_count(), [0x90, 0xdc, 0xdc, 0xdd], 16, out, env)?;
+        for row in arr.rows() {
+            encode_array(&row, out, env, scalar)?;
+        }
+        Ok(())
+    }
+
+    fn encode_rows(
+        rows: impl Iterator<Item = Value>,
+        len: usize,
+        out: &mut Vec<u8>,
+        env: &Uiua,
+    ) -> UiuaResult {
+        encode_len(len, [0x90, 0xdc, 0xdc, 0xdd], 16, out, env)?;
+        for row in rows {
+            encode(&row, out, env)?;
+        }
+        Ok(())
+    }
+
+    /// Encode a length with the smallest of a fixed, 8-bit, 16-bit, or 32-bit marker
+    ///
+    /// Fixed markers are used for lengths less than `fixed_max`.
+    fn encode_len(
+        len: usize,
+        [fixed, u8_marker, u16_marker, u32_marker]: [u8; 4],
+        fixed_max: usize,
+        out: &mut Vec<u8>,
+        env: &Uiua,
+    ) -> UiuaResult {
+        if len < fixed_max {
+            out.push(fixed | len as u8);
+        } else if len <= u8::MAX as usize && u8_marker != u16_marker {
+            out.extend([u8_marker, len as u8]);
+        } else if len <= u16::MAX as usize {
+            out.push(u16_marker);
+            out.extend((len as u16).to_be_bytes());
+        } else if len <= u32::MAX as usize {
+            out.push(u32_marker);
+            out.extend((len as u32).to_be_bytes());
+        } else {
+            return Err(env.error(format!(
+                "Cannot encode length {len} as MessagePack because it is too long"
+            )));
+        }
+        Ok(())
+    }
+
+    fn encode_int(i: i64, out: &mut Vec<u8>) {
+        if (0..128).contains(&i) || (-32..0).contains(&i) {
+            out.push(i as u8);
+        } else if let Ok(i) = u8::try_from(i) {
+            out.extend([0xcc, i]);
+        } else if let Ok(i) = u16::try_from(i) {
+            out.push(0xcd);
+            out.extend(i.to_be_bytes());
+        } else if let Ok(i) = u32::try_from(i) {
+            out.push(0xce);
+            out.extend(i.to_be_bytes());
+        } else if let Ok(i) = i8::try_from(i) {
+            out.push(0xd0);
+            out.extend(i.to_be_bytes());
+        } else if let Ok(i) = i16::try_from(i) {
+            out.push(0xd1);
+            out.extend(i.to_be_bytes());
+        } else if let Ok(i) = i32::try_from(i) {
+            out.push(0xd2);
+            out.extend(i.to_be_bytes());
+        } else {
+            out.push(0xd3);
+            out.extend(i.to_be_bytes());
+        }
+    }
+
+    pub fn decode(bytes: &mut &[u8], env: &Uiua) -> UiuaResult<Value> {
+        let marker = take::<1>(bytes, env)?[0];
+        Ok(match marker {
+            0x00..=0x7f => f64::from(marker).into(),
+            0x80..=0x8f => decode_map((marker & 0x0f) as usize, bytes, env)?,
+            0x90..=0x9f => decode_items((marker & 0x0f) as usize, bytes, env)?,
+            0xa0..=0xbf => decode_str((marker & 0x1f) as usize, bytes, env)?,
+            0xc0 => f64::NAN.into(),
+            0xc2 => 0.0.into(),
+            0xc3 => 1.0.into(),
+            0xc4..=0xc6 => {
+                let len = decode_len(marker - 0xc4, bytes, env)?;
+                let data = take_slice(len, bytes, env)?;
+                Array::<u8>::from_iter(data.iter().copied()).into()
+            }
+            0xca => (f32::from_be_bytes(take(bytes, env)?) as f64).into(),
+            0xcb => f64::from_be_bytes(take(bytes, env)?).into(),
+            0xcc => f64::from(take::<1>(bytes, env)?[0]).into(),
+            0xcd => f64::from(u16::from_be_bytes(take(bytes, env)?)).into(),
+            0xce => f64::from(u32::from_be_bytes(take(bytes, env)?)).into(),
+            0xcf => (u64::from_be_bytes(take(bytes, env)?) as f64).into(),
+            0xd0 => f64::from(i8::from_be_bytes(take(bytes, env)?)).into(),
+            0xd1 => f64::from(i16::from_be_bytes(take(bytes, env)?)).into(),
+            0xd2 => f64::from(i32::from_be_bytes(take(bytes, env)?)).into(),
+            0xd3 => (i64::from_be_bytes(take(bytes, env)?) as f64).into(),
+            0xd9..=0xdb => {
+                let len = decode_len(marker - 0xd9, bytes, env)?;
+                decode_str(len, bytes, env)?
+            }
+            0xdc | 0xdd => {
+                let len = decode_len(marker - 0xdc + 1, bytes, env)?;
+                decode_items(len, bytes, env)?
+            }
+            0xde | 0xdf => {
+                let len = decode_len(marker - 0xde + 1, bytes, env)?;
+                decode_map(len, bytes, env)?
+            }
+            0xc7..=0xc9 | 0xd4..=0xd8 => {
+                return Err(env.error("Cannot decode MessagePack extension types"))
+            }
+            0xe0..=0xff => f64::from(marker as i8).into(),
+            0xc1 => return Err(env.error("Invalid MessagePack: unused marker byte 0xc1")),
+        })
+    }
+
+    /// Decode a big-endian length of 1, 2, or 4 bytes for size classes 0, 1, or 2
+    fn decode_len(size_class: u8, bytes: &mut &[u8], env: &Uiua) -> UiuaResult<usize> {
+        Ok(match size_class {
+            0 => take::<1>(bytes, env)?[0] as usize,
+            1 => u16::from_be_bytes(take(bytes, env)?) as usize,
+            _ => u32::from_be_bytes(take(bytes, env)?) as usize,
+        })
+    }
+
+    fn decode_str(len: usize, bytes: &mut &[u8], env: &Uiua) -> UiuaResult<Value> {
+        let data = take_slice(len, bytes, env)?;
+        let s = std::str::from_utf8(data)
+            .map_err(|e| env.error(format!("Invalid MessagePack string: {e}")))?;
+        Ok(s.into())
+    }
+
+    fn decode_items(len: usize, bytes: &mut &[u8], env: &Uiua) -> UiuaResult<Value> {
+        let items = (0..len)
+            .map(|_| decode(bytes, env))
+            .collect::<UiuaResult<Vec<_>>>()?;
+        collect_items(items, env)
+    }
+
+    fn decode_map(len: usize, bytes: &mut &[u8], env: &Uiua) -> UiuaResult<Value> {
+        // Every key and value takes at least one byte, so a length read from
+        // the input cannot be trusted beyond the number of remaining bytes
+        let mut pairs = EcoVec::with_capacity((len * 2).min(bytes.len()));
+        for _ in 0..len * 2 {
+            pairs.push(Arc::new(Function::boxed(decode(bytes, env)?)));
+        }
+        Ok(Array::new(tiny_vec![len, 2], pairs).into())
+    }
+
+    fn take<const N: usize>(bytes: &mut &[u8], env: &Uiua) -> UiuaResult<[u8; N]> {
+        Ok(take_slice(N, bytes, env)?.try_into().unwrap())
+    }
+
+    fn take_slice<'a>(len: usize, bytes: &mut &'a [u8], env: &Uiua) -> UiuaResult<&'a [u8]> {
+        if bytes.len() < len {
+            return Err(env.error("Invalid MessagePack: unexpected end of data"));
+        }
+        let (taken, rest) = bytes.split_at(len);
+        *bytes = rest;
+        Ok(taken)
+    }
+}
+
+/// Combine decoded array items into a normal array if they all have the same type and shape,
+/// or into an array of boxes otherwise
+fn collect_items(items: Vec<Value>, env: &Uiua) -> UiuaResult<Value> {
+    let first = items.first().cloned().unwrap_or_default();
+    let homogeneous = items.iter().all(|item| {
+        !matches!(item, Value::Func(_))
+            && item.type_name() == first.type_name()
+            && item.shape() == first.shape()
+    });
+    Ok(if items.is_empty() {
+        Array::<f64>::default().into()
+    } else if homogeneous {
+        let mut builder = ValueBuilder::with_capacity(items.len());
+        for item in items {
+            builder.add_row(item, env)?;
+        }
+        builder.finish()
+    } else {
+        Array::<Arc<Function>>::from_iter(
+            items
+                .into_iter()
+                .map(|item| Arc::new(Function::boxed(item))),
+        )
+        .into()
+    })
+}
+
+fn csv_field(cell: &Value, env: &Uiua) -> UiuaResult<String> {
+    Ok(match cell {
+        Value::Char(arr) if arr.rank() <= 1 => arr.data.iter().collect(),
+        Value::Num(arr) if arr.rank() == 0 => arr.data[0].to_string(),
+        Value::Int(arr) if arr.rank() == 0 => arr.data[0].to_string(),
+        Value::Byte(arr) if arr.rank() == 0 => arr.data[0].to_string(),
+        Value::Func(arr) if arr.rank() == 0 && arr.data[0].as_boxed().is_some() => {
+            csv_field(arr.data[0].as_boxed().unwrap(), env)?
+        }
+        _ => {
+            return Err(env.error(format!(
+                "CSV cells must be strings or scalar numbers, but found a {} array of shape {}",
+                cell.type_name(),
+                cell.format_shape()
+            )))
+        }
+    })
+}
+
+fn array_to_json<T: ArrayValue>(
+    arr: &Array<T>,
+    env: &Uiua,
+    scalar: impl Fn(&T, &Uiua) -> UiuaResult<Json> + Copy,
+) -> UiuaResult<Json> {
+    if arr.rank() == 0 {
+        return scalar(&arr.data[0], env);
+    }
+    Ok(Json::Array(
+        (arr.rows())
+            .map(|row| array_to_json(&row, env, scalar))
+            .collect::<UiuaResult<_>>()?,
+    ))
+}
+
+fn is_json_object(arr: &Array<Arc<Function>>) -> bool {
+    arr.rank() == 2
+        && arr.shape()[1] == 2
+        && arr.data.chunks_exact(2).all(|pair| {
+            (pair[0].as_boxed())
+                .and_then(Value::as_char_array)
+                .is_some_and(|key| key.rank() <= 1)
+        })
+}
+
+/// Convert a 1-indexed line and column from a JSON error into a byte offset
+fn json_error_offset(json: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = (json.split_inclusive('\n').take(line.saturating_sub(1)))
+        .map(str::len)
+        .sum();
+    (line_start + column.saturating_sub(1)).min(json.len())
+}
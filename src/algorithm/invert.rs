@@ -19,7 +19,7 @@ impl Function {
         } else {
             let (befores, afters) = under_instrs(&self.instrs, g_sig)?;
             let before = Function::new_inferred(self.id.clone(), befores).ok()?;
-            let after = Function::new_inferred(self.id, afters).ok()?;
+            let after = Function::new_inferred(self.id.clone(), afters).ok()?;
             Some((before, after))
         }
     }
@@ -268,6 +268,11 @@ fn under_instrs_impl(instrs: &[Instr], g_sig: Signature) -> Option<(Vec<Instr>,
             [Now.i(), PushTempUnderN(1).i()],
             [PopTempUnderN(1).i(), Now.i(), Flip.i(), Sub.i()],
         ),
+        &(
+            [Monotonic],
+            [Monotonic.i(), PushTempUnderN(1).i()],
+            [PopTempUnderN(1).i(), Monotonic.i(), Flip.i(), Sub.i()],
+        ),
     ];
 
     let mut befores = Vec::new();
@@ -66,10 +66,18 @@ impl Value {
                 .partition_groups(markers, env)?
                 .map(Into::into)
                 .collect(),
+            Value::Int(arr) => arr
+                .partition_groups(markers, env)?
+                .map(Into::into)
+                .collect(),
             Value::Byte(arr) => arr
                 .partition_groups(markers, env)?
                 .map(Into::into)
                 .collect(),
+            Value::Complex(arr) => arr
+                .partition_groups(markers, env)?
+                .map(Into::into)
+                .collect(),
             Value::Char(arr) => arr
                 .partition_groups(markers, env)?
                 .map(Into::into)
@@ -80,6 +88,25 @@ impl Value {
                 .collect(),
         })
     }
+    /// Split the rows into groups of consecutive rows with equal keys
+    ///
+    /// Unlike a full grouping, rows with equal keys that are not adjacent go in separate groups.
+    pub fn partition_by(&self, key: impl Fn(&Value) -> UiuaResult<Value>) -> UiuaResult<Vec<Self>> {
+        let mut groups: Vec<Vec<Value>> = Vec::new();
+        let mut last_key = None;
+        for row in self.rows() {
+            let row_key = key(&row)?;
+            if last_key.as_ref() != Some(&row_key) {
+                groups.push(Vec::new());
+                last_key = Some(row_key);
+            }
+            groups.last_mut().unwrap().push(row);
+        }
+        Ok(groups
+            .into_iter()
+            .map(Value::from_row_values_infallible)
+            .collect())
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -124,7 +151,9 @@ impl Value {
     pub fn group_groups(&self, indices: &[isize], env: &Uiua) -> UiuaResult<Vec<Self>> {
         Ok(match self {
             Value::Num(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
+            Value::Int(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Byte(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
+            Value::Complex(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Char(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
             Value::Func(arr) => arr.group_groups(indices, env)?.map(Into::into).collect(),
         })
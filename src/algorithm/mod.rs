@@ -8,12 +8,14 @@ use crate::{
 };
 
 mod dyadic;
+mod encode;
 pub mod fork;
 pub(crate) mod invert;
 pub mod loops;
 mod monadic;
 pub mod pervade;
 pub mod reduce;
+pub mod simd;
 pub mod table;
 pub mod zip;
 
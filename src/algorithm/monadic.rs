@@ -29,6 +29,8 @@ impl Value {
             Array::deshape,
             Array::deshape,
             Array::deshape,
+            Array::deshape,
+            Array::deshape,
         )
     }
     pub fn parse_num(&self, env: &Uiua) -> UiuaResult<Self> {
@@ -112,6 +114,8 @@ impl Value {
             |a| a.first(env).map(Into::into),
             |a| a.first(env).map(Into::into),
             |a| a.first(env).map(Into::into),
+            |a| a.first(env).map(Into::into),
+            |a| a.first(env).map(Into::into),
         )
     }
     pub fn last(self, env: &Uiua) -> UiuaResult<Self> {
@@ -120,6 +124,8 @@ impl Value {
             |a| a.last(env).map(Into::into),
             |a| a.last(env).map(Into::into),
             |a| a.last(env).map(Into::into),
+            |a| a.last(env).map(Into::into),
+            |a| a.last(env).map(Into::into),
         )
     }
 }
@@ -175,8 +181,40 @@ impl Value {
             Array::reverse,
             Array::reverse,
             Array::reverse,
+            Array::reverse,
+            Array::reverse,
         )
     }
+    /// Get the Euclidean length of a numeric list
+    ///
+    /// Like [`Primitive::Hypot`](crate::primitive::Primitive::Hypot), this does not overflow for large elements.
+    pub fn norm(&self, env: &Uiua) -> UiuaResult<Self> {
+        let nums = self.as_number_list(
+            env,
+            "Argument to norm must be a list of numbers",
+            |_| true,
+            |n| n,
+        )?;
+        Ok(nums.into_iter().fold(0.0, f64::hypot).into())
+    }
+    /// Reverse the array along the given axis
+    pub fn reverse_axis(&mut self, axis: usize, env: &Uiua) -> UiuaResult {
+        if axis >= self.rank() {
+            return Err(env.error(format!(
+                "Cannot reverse axis {axis} of array with rank {}",
+                self.rank()
+            )));
+        }
+        self.generic_mut_shallow(
+            |a| a.reverse_axis(axis),
+            |a| a.reverse_axis(axis),
+            |a| a.reverse_axis(axis),
+            |a| a.reverse_axis(axis),
+            |a| a.reverse_axis(axis),
+            |a| a.reverse_axis(axis),
+        );
+        Ok(())
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -197,6 +235,24 @@ impl<T: ArrayValue> Array<T> {
             }
         }
     }
+    /// Reverse the array along the given axis
+    ///
+    /// # Panics
+    /// Panics if the axis is out of bounds
+    pub fn reverse_axis(&mut self, axis: usize) {
+        let axis_len = self.shape[axis];
+        let inner_len: usize = self.shape[axis + 1..].iter().product();
+        let block_len = axis_len * inner_len;
+        if block_len == 0 {
+            return;
+        }
+        for block in self.data.as_mut_slice().chunks_exact_mut(block_len) {
+            for i in 0..axis_len / 2 {
+                let (left, right) = block.split_at_mut((axis_len - i - 1) * inner_len);
+                left[i * inner_len..][..inner_len].swap_with_slice(&mut right[..inner_len]);
+            }
+        }
+    }
 }
 
 impl Value {
@@ -206,6 +262,8 @@ impl Value {
             Array::transpose,
             Array::transpose,
             Array::transpose,
+            Array::transpose,
+            Array::transpose,
         )
     }
     pub fn inv_transpose(&mut self) {
@@ -214,6 +272,8 @@ impl Value {
             Array::inv_transpose,
             Array::inv_transpose,
             Array::inv_transpose,
+            Array::inv_transpose,
+            Array::inv_transpose,
         )
     }
 }
@@ -261,14 +321,135 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Value {
+    /// Rearrange the axes of the value by a permutation
+    ///
+    /// Axis `i` of the result is axis `perm[i]` of the original value
+    pub fn transpose_axes(&mut self, perm: &[usize], env: &Uiua) -> UiuaResult {
+        self.generic_mut_shallow(
+            |a| a.transpose_axes(perm, env),
+            |a| a.transpose_axes(perm, env),
+            |a| a.transpose_axes(perm, env),
+            |a| a.transpose_axes(perm, env),
+            |a| a.transpose_axes(perm, env),
+            |a| a.transpose_axes(perm, env),
+        )
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// Rearrange the axes of the array by a permutation
+    ///
+    /// Axis `i` of the result is axis `perm[i]` of the original array
+    pub fn transpose_axes(&mut self, perm: &[usize], env: &Uiua) -> UiuaResult {
+        crate::profile_function!();
+        let rank = self.rank();
+        let mut seen = vec![false; rank];
+        let is_permutation = perm.len() == rank
+            && perm
+                .iter()
+                .all(|&axis| axis < rank && !std::mem::replace(&mut seen[axis], true));
+        if !is_permutation {
+            return Err(env.error(format!(
+                "Cannot transpose rank {rank} array by {perm:?}, \
+                which is not a permutation of its axes"
+            )));
+        }
+        if perm.iter().enumerate().all(|(i, &axis)| i == axis) {
+            return Ok(());
+        }
+        let new_shape: Shape = perm.iter().map(|&axis| self.shape[axis]).collect();
+        if self.data.is_empty() {
+            self.shape = new_shape;
+            return Ok(());
+        }
+        if rank == 2 {
+            // Swap axes in cache-sized blocks
+            const BLOCK: usize = 32;
+            let (rows, cols) = (self.shape[0], self.shape[1]);
+            let mut temp = self.data.clone();
+            let dest = temp.as_mut_slice();
+            for i_block in (0..rows).step_by(BLOCK) {
+                for j_block in (0..cols).step_by(BLOCK) {
+                    for i in i_block..(i_block + BLOCK).min(rows) {
+                        for j in j_block..(j_block + BLOCK).min(cols) {
+                            dest[j * rows + i] = self.data[i * cols + j].clone();
+                        }
+                    }
+                }
+            }
+            self.data = temp;
+            self.shape = new_shape;
+            return Ok(());
+        }
+        // Strides of the original axes, in the order of the new axes
+        let mut strides = vec![1; rank];
+        for axis in (0..rank - 1).rev() {
+            strides[axis] = strides[axis + 1] * self.shape[axis + 1];
+        }
+        let strides: Vec<usize> = perm.iter().map(|&axis| strides[axis]).collect();
+        let mut temp = EcoVec::with_capacity(self.data.len());
+        let mut index = vec![0; rank];
+        let mut offset = 0;
+        for _ in 0..self.data.len() {
+            temp.push(self.data[offset].clone());
+            // Increment the multi-dimensional index
+            for axis in (0..rank).rev() {
+                index[axis] += 1;
+                offset += strides[axis];
+                if index[axis] < new_shape[axis] {
+                    break;
+                }
+                offset -= strides[axis] * index[axis];
+                index[axis] = 0;
+            }
+        }
+        self.data = temp.into();
+        self.shape = new_shape;
+        Ok(())
+    }
+}
+
 impl Value {
     pub fn rise(&self, env: &Uiua) -> UiuaResult<Self> {
-        self.generic_ref_env_deep(Array::rise, Array::rise, Array::rise, Array::rise, env)
-            .map(Self::from_iter)
+        self.generic_ref_env_deep(
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            env,
+        )
+        .map(Self::from_iter)
     }
     pub fn fall(&self, env: &Uiua) -> UiuaResult<Self> {
-        self.generic_ref_env_deep(Array::fall, Array::fall, Array::fall, Array::fall, env)
-            .map(Self::from_iter)
+        self.generic_ref_env_deep(
+            Array::fall,
+            Array::fall,
+            Array::fall,
+            Array::fall,
+            Array::fall,
+            Array::fall,
+            env,
+        )
+        .map(Self::from_iter)
+    }
+    /// Get the indices that would stably sort the rows of the value
+    ///
+    /// This is the same permutation that [`Primitive::Rise`](crate::primitive::Primitive::Rise) produces,
+    /// but as exact integers. Equal rows keep their original relative order.
+    pub fn grade(&self, env: &Uiua) -> UiuaResult<Array<i64>> {
+        let indices = self.generic_ref_env_deep(
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            Array::rise,
+            env,
+        )?;
+        Ok(indices.into_iter().map(|i| i as i64).collect())
     }
     pub fn classify(&self, env: &Uiua) -> UiuaResult<Self> {
         self.generic_ref_env_deep(
@@ -276,16 +457,42 @@ impl Value {
             Array::classify,
             Array::classify,
             Array::classify,
+            Array::classify,
+            Array::classify,
             env,
         )
         .map(Self::from_iter)
     }
+    /// Get the unique rows in first-seen order along with how many times each occurs
+    pub fn unique_counts(&self, env: &Uiua) -> UiuaResult<(Self, Self)> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot get the unique counts of a rank-0 array"));
+        }
+        fn unique_counts<T: ArrayValue>(arr: &Array<T>) -> (Value, Vec<usize>)
+        where
+            Value: From<Array<T>>,
+        {
+            let (unique, counts) = arr.unique_counts();
+            (unique.into(), counts)
+        }
+        let (unique, counts) = self.generic_ref_deep(
+            unique_counts,
+            unique_counts,
+            unique_counts,
+            unique_counts,
+            unique_counts,
+            unique_counts,
+        );
+        Ok((unique, counts.into_iter().collect()))
+    }
     pub fn deduplicate(&mut self) {
         self.generic_mut_deep(
             Array::deduplicate,
             Array::deduplicate,
             Array::deduplicate,
             Array::deduplicate,
+            Array::deduplicate,
+            Array::deduplicate,
         )
     }
 }
@@ -299,6 +506,7 @@ impl<T: ArrayValue> Array<T> {
             return Ok(Vec::new());
         }
         let mut indices = (0..self.row_count()).collect::<Vec<_>>();
+        // This sort must be stable so that `grade` keeps equal rows in order
         indices.par_sort_by(|&a, &b| {
             self.row_slice(a)
                 .iter()
@@ -340,6 +548,24 @@ impl<T: ArrayValue> Array<T> {
         }
         Ok(classified)
     }
+    /// Get the unique rows in first-seen order along with how many times each occurs
+    pub fn unique_counts(&self) -> (Self, Vec<usize>) {
+        let mut unique = CowSlice::new();
+        let mut indices = HashMap::new();
+        let mut counts = Vec::new();
+        for row in self.rows() {
+            if let Some(&i) = indices.get(&row) {
+                counts[i] += 1;
+            } else {
+                unique.extend_from_slice(&row.data);
+                indices.insert(row, counts.len());
+                counts.push(1);
+            }
+        }
+        let mut shape = self.shape.clone();
+        shape[0] = counts.len();
+        (Array::new(shape, unique), counts)
+    }
     pub fn deduplicate(&mut self) {
         if self.rank() == 0 {
             return;
@@ -489,6 +715,62 @@ impl Array<u8> {
 }
 
 impl Value {
+    /// Pack the last axis of an array of `0`s and `1`s into bytes
+    ///
+    /// Each byte holds 8 bits, with the first bit in the most significant position.
+    /// If the last axis is not a multiple of 8 long, the last byte of each row is padded with `0`s.
+    /// Use [`Value::unpack_bits`] with the original length of the last axis to reverse this.
+    pub fn pack_bits(&self, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot pack the bits of a scalar"));
+        }
+        let bits = self.as_number_array(
+            env,
+            "Bits must be an array of 0s and 1s",
+            |_| true,
+            |n| n == 0.0 || n == 1.0,
+            |n| n as u8,
+        )?;
+        let mut shape = bits.shape.clone();
+        let bit_len = shape.pop().unwrap();
+        let byte_len = bit_len.div_ceil(8);
+        shape.push(byte_len);
+        let mut data = EcoVec::with_capacity(shape.iter().product());
+        if bit_len > 0 {
+            for row in bits.data.chunks_exact(bit_len) {
+                for chunk in row.chunks(8) {
+                    let byte =
+                        (chunk.iter().enumerate()).fold(0, |byte, (i, &b)| byte | b << (7 - i));
+                    data.push(byte);
+                }
+            }
+        }
+        Ok(Array::<u8>::new(shape, data).into())
+    }
+    /// Unpack bytes created by [`Value::pack_bits`]
+    ///
+    /// `len` is the original length of the last axis, so that padding bits are dropped.
+    pub fn unpack_bits(&self, len: usize, env: &Uiua) -> UiuaResult<Self> {
+        let bytes = match self {
+            Value::Byte(bytes) if bytes.rank() > 0 => bytes,
+            _ => return Err(env.error("Packed bits must be an array of bytes")),
+        };
+        let mut shape = bytes.shape.clone();
+        let byte_len = shape.pop().unwrap();
+        if byte_len != len.div_ceil(8) {
+            return Err(env.error(format!(
+                "Cannot unpack {len} bits from rows of {byte_len} bytes"
+            )));
+        }
+        shape.push(len);
+        let mut data = EcoVec::with_capacity(shape.iter().product());
+        if byte_len > 0 {
+            for row in bytes.data.chunks_exact(byte_len) {
+                data.extend((0..len).map(|i| (row[i / 8] >> (7 - i % 8)) & 1));
+            }
+        }
+        Ok(Array::<u8>::new(shape, data).into())
+    }
     pub fn wher(&self, env: &Uiua) -> UiuaResult<Self> {
         let counts = self.as_naturals(env, "Argument to where must be a list of naturals")?;
         let total: usize = counts.iter().fold(0, |acc, &b| acc.saturating_add(b));
@@ -542,9 +824,39 @@ impl Value {
         let s = self.as_string(env, "Argument to utf must be a string")?;
         Ok(Array::<u8>::from_iter(s.into_bytes()).into())
     }
+    /// Decode UTF-8 bytes
+    ///
+    /// Invalid sequences are an error unless there is a character fill value,
+    /// in which case each one is replaced with the fill character.
     pub fn inv_utf8(&self, env: &Uiua) -> UiuaResult<Self> {
         let bytes = self.as_bytes(env, "Argument to inverse utf must be a list of bytes")?;
-        let s = String::from_utf8(bytes).map_err(|e| env.error(e))?;
+        let replacement = env.char_fill();
+        let mut s = String::with_capacity(bytes.len());
+        let mut rest = bytes.as_slice();
+        let mut offset = 0;
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    s.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                    let Some(replacement) = replacement else {
+                        return Err(env.error(format!(
+                            "Invalid UTF-8 sequence at byte {}",
+                            offset + valid.len()
+                        )));
+                    };
+                    s.push_str(std::str::from_utf8(valid).unwrap());
+                    s.push(replacement);
+                    // A missing error length means the bytes end mid-sequence
+                    let invalid_len = e.error_len().unwrap_or(invalid.len());
+                    rest = &invalid[invalid_len..];
+                    offset += valid.len() + invalid_len;
+                }
+            }
+        }
         Ok(s.into())
     }
 }
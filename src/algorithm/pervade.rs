@@ -8,6 +8,10 @@ use std::{
     slice::{self, ChunksExact},
 };
 
+use ecow::EcoVec;
+use num_complex::Complex64;
+use rayon::prelude::*;
+
 use crate::{array::*, cowslice::CowSlice, Uiua, UiuaError, UiuaResult};
 
 use super::{max_shape, FillContext};
@@ -191,6 +195,44 @@ where
     Ok(())
 }
 
+impl<T: ArrayValue> Array<T> {
+    /// Repeat axes of length 1 to give the array the given shape
+    ///
+    /// The shape must be compatible as determined by [`ShapeExt::broadcast_with`].
+    pub fn broadcast_to(&self, shape: &[usize]) -> Self {
+        if self.shape() == shape {
+            return self.clone();
+        }
+        let rank = shape.len();
+        // Strides aligned to the target axes, with 0 for repeated axes
+        let mut strides = vec![0; rank];
+        let mut stride = 1;
+        for (i, &dim) in self.shape.iter().enumerate().rev() {
+            if dim != 1 {
+                strides[rank - self.rank() + i] = stride;
+            }
+            stride *= dim;
+        }
+        let len: usize = shape.iter().product();
+        let mut data = EcoVec::with_capacity(len);
+        let mut index = vec![0; rank];
+        let mut src = 0;
+        for _ in 0..len {
+            data.push(self.data[src].clone());
+            for axis in (0..rank).rev() {
+                index[axis] += 1;
+                src += strides[axis];
+                if index[axis] < shape[axis] {
+                    break;
+                }
+                src -= strides[axis] * shape[axis];
+                index[axis] = 0;
+            }
+        }
+        Array::new(shape, data)
+    }
+}
+
 pub fn bin_pervade<A, B, C, F>(
     mut a: Array<A>,
     mut b: Array<B>,
@@ -256,11 +298,46 @@ where
     Ok(())
 }
 
+/// Apply a function to each element of a slice
+///
+/// Runs in parallel if the slice has at least `min_len` elements
+pub(crate) fn par_for_each<T: Send>(
+    data: &mut [T],
+    min_len: usize,
+    f: impl Fn(&mut T) + Send + Sync,
+) {
+    if data.len() >= min_len {
+        data.par_iter_mut().with_min_len(min_len).for_each(f);
+    } else {
+        data.iter_mut().for_each(f);
+    }
+}
+
+/// Apply a function to each pair of elements of two slices, mutating the first
+///
+/// Runs in parallel if the slices have at least `min_len` elements
+fn par_for_each_zip<T: Copy + Send + Sync>(
+    dest: &mut [T],
+    src: &[T],
+    min_len: usize,
+    f: impl Fn(&mut T, T) + Send + Sync,
+) {
+    if dest.len() >= min_len {
+        (dest.par_iter_mut().zip(src))
+            .with_min_len(min_len)
+            .for_each(|(d, &s)| f(d, s));
+    } else {
+        for (d, &s) in dest.iter_mut().zip(src) {
+            f(d, s);
+        }
+    }
+}
+
 pub fn bin_pervade_mut<T>(
     a: &mut Array<T>,
     mut b: Array<T>,
     env: &Uiua,
-    f: impl Fn(T, T) -> T + Copy,
+    f: impl Fn(T, T) -> T + Copy + Send + Sync,
 ) -> UiuaResult
 where
     T: ArrayValue + Copy,
@@ -270,25 +347,19 @@ where
     let bsh = b.shape.as_slice();
     // Try to avoid copying when possible
     if ash == bsh {
+        let min_len = env.parallel_min_len();
         if a.data.is_copy_of(&b.data) {
             drop(b);
-            let a_data = a.data.as_mut_slice();
-            for a in a_data {
-                *a = f(*a, *a);
-            }
+            par_for_each(a.data.as_mut_slice(), min_len, |a| *a = f(*a, *a));
         } else if b.data.is_unique() {
             let a_data = a.data.as_slice();
             let b_data = b.data.as_mut_slice();
-            for (a, b) in a_data.iter().zip(b_data) {
-                *b = f(*a, *b);
-            }
+            par_for_each_zip(b_data, a_data, min_len, |b, a| *b = f(a, *b));
             *a = b;
         } else {
             let a_data = a.data.as_mut_slice();
             let b_data = b.data.as_slice();
-            for (a, b) in a_data.iter_mut().zip(b_data) {
-                *a = f(*a, *b);
-            }
+            par_for_each_zip(a_data, b_data, min_len, |a, b| *a = f(*a, b));
         }
     } else {
         match ash.len().cmp(&bsh.len()) {
@@ -482,6 +553,9 @@ pub mod sqrt {
     pub fn byte(a: u8) -> f64 {
         f64::from(a).sqrt()
     }
+    pub fn com(a: Complex64) -> Complex64 {
+        a.sqrt()
+    }
     pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot take the square root of {a}"))
     }
@@ -546,6 +620,143 @@ pub mod acos {
         env.error(format!("Cannot get the arccosine of {a}"))
     }
 }
+pub mod sinh {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        a.sinh()
+    }
+    pub fn byte(a: u8) -> f64 {
+        f64::from(a).sinh()
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the hyperbolic sine of {a}"))
+    }
+}
+pub mod cosh {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        a.cosh()
+    }
+    pub fn byte(a: u8) -> f64 {
+        f64::from(a).cosh()
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the hyperbolic cosine of {a}"))
+    }
+}
+pub mod tanh {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        a.tanh()
+    }
+    pub fn byte(a: u8) -> f64 {
+        f64::from(a).tanh()
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the hyperbolic tangent of {a}"))
+    }
+}
+pub mod cbrt {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        a.cbrt()
+    }
+    pub fn byte(a: u8) -> f64 {
+        f64::from(a).cbrt()
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the cube root of {a}"))
+    }
+}
+pub mod gamma {
+    use super::*;
+    use std::f64::consts::PI;
+    /// Coefficients for the Lanczos approximation with `g = 7`
+    const LANCZOS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    /// Above this, the gamma function is too large for a float
+    const MAX_ARG: f64 = 171.624_376_956_302_7;
+    pub fn num(a: f64) -> f64 {
+        if a == 0.0 {
+            // The sign of the pole matches the sign of the zero
+            return 1.0 / a;
+        }
+        if a < 0.0 && a.fract() == 0.0 || a == f64::NEG_INFINITY {
+            return f64::NAN;
+        }
+        if a > MAX_ARG {
+            return f64::INFINITY;
+        }
+        // Multiply exactly for integers whose factorial fits in a float
+        if a.fract() == 0.0 && a <= 171.0 {
+            return (2..a as u64).fold(1.0, |acc, i| acc * i as f64);
+        }
+        if a < 0.5 {
+            // Reflection formula
+            return PI / ((PI * a).sin() * num(1.0 - a));
+        }
+        let a = a - 1.0;
+        let mut sum = LANCZOS[0];
+        for (i, &c) in LANCZOS.iter().enumerate().skip(1) {
+            sum += c / (a + i as f64);
+        }
+        let t = a + 7.5;
+        // The power is split in two so that it doesn't overflow before being scaled down
+        let half_pow = t.powf((a + 0.5) / 2.0);
+        (2.0 * PI).sqrt() * half_pow * (-t).exp() * half_pow * sum
+    }
+    pub fn byte(a: u8) -> f64 {
+        num(a.into())
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the gamma function of {a}"))
+    }
+}
+pub mod factorial {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        gamma::num(a + 1.0)
+    }
+    pub fn byte(a: u8) -> f64 {
+        num(a.into())
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the factorial of {a}"))
+    }
+}
+pub mod log2 {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        a.log2()
+    }
+    pub fn byte(a: u8) -> f64 {
+        f64::from(a).log2()
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the base-2 logarithm of {a}"))
+    }
+}
+pub mod log10 {
+    use super::*;
+    pub fn num(a: f64) -> f64 {
+        a.log10()
+    }
+    pub fn byte(a: u8) -> f64 {
+        f64::from(a).log10()
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the base-10 logarithm of {a}"))
+    }
+}
 pub mod floor {
     use super::*;
     pub fn num(a: f64) -> f64 {
@@ -648,6 +859,24 @@ pub mod add {
     pub fn char_byte(a: char, b: u8) -> char {
         char::from_u32((b as i64 + a as i64) as u32).unwrap_or('\0')
     }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        b.checked_add(a)
+    }
+    pub fn com_com(a: Complex64, b: Complex64) -> Complex64 {
+        b + a
+    }
+    pub fn num_com(a: f64, b: Complex64) -> Complex64 {
+        b + a
+    }
+    pub fn com_num(a: Complex64, b: f64) -> Complex64 {
+        Complex64::from(b) + a
+    }
+    pub fn byte_com(a: u8, b: Complex64) -> Complex64 {
+        b + f64::from(a)
+    }
+    pub fn com_byte(a: Complex64, b: u8) -> Complex64 {
+        Complex64::from(f64::from(b)) + a
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot add {a} and {b}"))
     }
@@ -676,6 +905,24 @@ pub mod sub {
     pub fn byte_char(a: u8, b: char) -> char {
         char::from_u32(((b as i64) - (a as i64)) as u32).unwrap_or('\0')
     }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        b.checked_sub(a)
+    }
+    pub fn com_com(a: Complex64, b: Complex64) -> Complex64 {
+        b - a
+    }
+    pub fn num_com(a: f64, b: Complex64) -> Complex64 {
+        b - a
+    }
+    pub fn com_num(a: Complex64, b: f64) -> Complex64 {
+        Complex64::from(b) - a
+    }
+    pub fn byte_com(a: u8, b: Complex64) -> Complex64 {
+        b - f64::from(a)
+    }
+    pub fn com_byte(a: Complex64, b: u8) -> Complex64 {
+        Complex64::from(f64::from(b)) - a
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot subtract {a} from {b}"))
     }
@@ -695,6 +942,24 @@ pub mod mul {
     pub fn num_byte(a: f64, b: u8) -> f64 {
         f64::from(b) * a
     }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        b.checked_mul(a)
+    }
+    pub fn com_com(a: Complex64, b: Complex64) -> Complex64 {
+        b * a
+    }
+    pub fn num_com(a: f64, b: Complex64) -> Complex64 {
+        b * a
+    }
+    pub fn com_num(a: Complex64, b: f64) -> Complex64 {
+        Complex64::from(b) * a
+    }
+    pub fn byte_com(a: u8, b: Complex64) -> Complex64 {
+        b * f64::from(a)
+    }
+    pub fn com_byte(a: Complex64, b: u8) -> Complex64 {
+        Complex64::from(f64::from(b)) * a
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot multiply {a} and {b}"))
     }
@@ -714,6 +979,21 @@ pub mod div {
     pub fn num_byte(a: f64, b: u8) -> f64 {
         f64::from(b) / a
     }
+    pub fn com_com(a: Complex64, b: Complex64) -> Complex64 {
+        b / a
+    }
+    pub fn num_com(a: f64, b: Complex64) -> Complex64 {
+        b / a
+    }
+    pub fn com_num(a: Complex64, b: f64) -> Complex64 {
+        Complex64::from(b) / a
+    }
+    pub fn byte_com(a: u8, b: Complex64) -> Complex64 {
+        b / f64::from(a)
+    }
+    pub fn com_byte(a: Complex64, b: u8) -> Complex64 {
+        Complex64::from(f64::from(b)) / a
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot divide {a} by {b}"))
     }
@@ -739,6 +1019,235 @@ pub mod modulus {
     }
 }
 
+pub mod gcd {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        let (mut a, mut b) = (a.abs(), b.abs());
+        while b != 0.0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        i64::try_from(a).ok()
+    }
+    pub fn byte_byte(a: u8, b: u8) -> u8 {
+        let (mut a, mut b) = (a, b);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the gcd of {a} and {b}"))
+    }
+}
+
+pub mod lcm {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        if a == 0.0 || b == 0.0 {
+            return 0.0;
+        }
+        (a / gcd::num_num(a, b) * b).abs()
+    }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        if a == 0 || b == 0 {
+            return Some(0);
+        }
+        (a / gcd::int_int(a, b)?).checked_mul(b)?.checked_abs()
+    }
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        num_num(a.into(), b.into())
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the lcm of {a} and {b}"))
+    }
+}
+
+pub mod roundto {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        if a >= 0.0 {
+            let scale = 10f64.powf(a);
+            let scaled = b * scale;
+            if !scaled.is_finite() {
+                // There are no digits that far right to round away
+                return b;
+            }
+            scaled.round() / scale
+        } else {
+            // Dividing by a power of 10 keeps the result exact for integers
+            let scale = 10f64.powf(-a);
+            if !scale.is_finite() {
+                // Every finite number rounds to zero that far left
+                return if b.is_finite() { 0.0 } else { b };
+            }
+            (b / scale).round() * scale
+        }
+    }
+    pub fn byte_byte(_: u8, b: u8) -> u8 {
+        b
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot round {b} to {a} digits"))
+    }
+}
+
+pub mod bit_and {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        (a as i64 & b as i64) as f64
+    }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        Some(a & b)
+    }
+    pub fn byte_byte(a: u8, b: u8) -> u8 {
+        a & b
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the bitwise and of {a} and {b}"))
+    }
+}
+
+pub mod bit_or {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        (a as i64 | b as i64) as f64
+    }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        Some(a | b)
+    }
+    pub fn byte_byte(a: u8, b: u8) -> u8 {
+        a | b
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the bitwise or of {a} and {b}"))
+    }
+}
+
+pub mod bit_xor {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        (a as i64 ^ b as i64) as f64
+    }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        Some(a ^ b)
+    }
+    pub fn byte_byte(a: u8, b: u8) -> u8 {
+        a ^ b
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the bitwise xor of {a} and {b}"))
+    }
+}
+
+pub mod bit_shift_left {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        b * 2f64.powf(a)
+    }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        if a >= 63 {
+            return (b == 0).then_some(0);
+        }
+        b.checked_mul(1 << a)
+    }
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        num_num(a.into(), b.into())
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot shift {b} left by {a}"))
+    }
+}
+
+pub mod bit_shift_right {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        (b / 2f64.powf(a)).floor()
+    }
+    pub fn int_int(a: i64, b: i64) -> Option<i64> {
+        Some(b >> a.min(63))
+    }
+    pub fn byte_byte(a: u8, b: u8) -> u8 {
+        b.checked_shr(a.into()).unwrap_or(0)
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot shift {b} right by {a}"))
+    }
+}
+
+pub mod hypot {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        a.hypot(b)
+    }
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        f64::from(a).hypot(f64::from(b))
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        f64::from(a).hypot(b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        a.hypot(f64::from(b))
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the hypotenuse of {a} and {b}"))
+    }
+}
+
 pub mod atan2 {
     use super::*;
     pub fn num_num(a: f64, b: f64) -> f64 {
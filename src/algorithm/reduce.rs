@@ -17,6 +17,10 @@ pub fn reduce(env: &mut Uiua) -> UiuaResult {
     let f = env.pop(FunctionArg(1))?;
     let xs = env.pop(ArrayArg(1))?;
 
+    // Reducing an empty array with max or min gives the fill value if there is one
+    let max_identity = env.num_fill().unwrap_or(f64::NEG_INFINITY);
+    let min_identity = env.num_fill().unwrap_or(f64::INFINITY);
+
     match (f.as_flipped_primitive(), xs) {
         (Some((Primitive::Join, false)), mut xs) => {
             if xs.rank() < 2 {
@@ -37,8 +41,8 @@ pub fn reduce(env: &mut Uiua) -> UiuaResult {
             Primitive::Mul => fast_reduce(nums, 1.0, mul::num_num),
             Primitive::Div if flipped => fast_reduce(nums, 1.0, flip(div::num_num)),
             Primitive::Div => fast_reduce(nums, 1.0, div::num_num),
-            Primitive::Max => fast_reduce(nums, f64::NEG_INFINITY, max::num_num),
-            Primitive::Min => fast_reduce(nums, f64::INFINITY, min::num_num),
+            Primitive::Max => fast_reduce(nums, max_identity, max::num_num),
+            Primitive::Min => fast_reduce(nums, min_identity, min::num_num),
             _ => return generic_fold1(f, Value::Num(nums), None, env),
         }),
         (Some((prim, flipped)), Value::Byte(bytes)) => env.push(match prim {
@@ -48,8 +52,8 @@ pub fn reduce(env: &mut Uiua) -> UiuaResult {
             Primitive::Mul => fast_reduce(bytes.convert(), 1.0, mul::num_num),
             Primitive::Div if flipped => fast_reduce(bytes.convert(), 1.0, flip(div::num_num)),
             Primitive::Div => fast_reduce(bytes.convert(), 1.0, div::num_num),
-            Primitive::Max => fast_reduce(bytes.convert(), f64::NEG_INFINITY, max::num_num),
-            Primitive::Min => fast_reduce(bytes.convert(), f64::INFINITY, min::num_num),
+            Primitive::Max => fast_reduce(bytes.convert(), max_identity, max::num_num),
+            Primitive::Min => fast_reduce(bytes.convert(), min_identity, min::num_num),
             _ => return generic_fold1(f, Value::Byte(bytes), None, env),
         }),
         (_, xs) => generic_fold1(f, xs, None, env)?,
@@ -0,0 +1,150 @@
+//! SIMD kernels for arithmetic on contiguous numeric arrays
+
+use rayon::prelude::*;
+
+use crate::{array::*, Uiua, UiuaResult};
+
+use super::pervade::{self, bin_pervade_mut};
+
+/// The minimum number of elements for which the SIMD kernels are used
+pub const SIMD_MIN_LEN: usize = 64;
+
+/// An arithmetic operation with a SIMD kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl SimdOp {
+    /// Apply the operation to a single pair of numbers
+    ///
+    /// Like the functions in [`pervade`], the first argument is the one that was on top of the stack
+    pub fn scalar(self, a: f64, b: f64) -> f64 {
+        match self {
+            SimdOp::Add => pervade::add::num_num(a, b),
+            SimdOp::Sub => pervade::sub::num_num(a, b),
+            SimdOp::Mul => pervade::mul::num_num(a, b),
+            SimdOp::Div => pervade::div::num_num(a, b),
+        }
+    }
+}
+
+/// Like [`bin_pervade_mut`], but uses SIMD kernels for same-shape arrays
+/// of at least [`SIMD_MIN_LEN`] elements
+pub fn bin_pervade_num_mut(
+    a: &mut Array<f64>,
+    mut b: Array<f64>,
+    env: &Uiua,
+    op: SimdOp,
+) -> UiuaResult {
+    if a.shape != b.shape || a.data.len() < SIMD_MIN_LEN {
+        return bin_pervade_mut(a, b, env, move |a, b| op.scalar(a, b));
+    }
+    let min_len = env.parallel_min_len();
+    let chunk_len = min_len.max(SIMD_MIN_LEN);
+    if a.data.is_copy_of(&b.data) {
+        drop(b);
+        let a_data = a.data.as_mut_slice();
+        if a_data.len() >= min_len {
+            a_data
+                .par_chunks_mut(chunk_len)
+                .for_each(|a| apply_in_place(op, a));
+        } else {
+            apply_in_place(op, a_data);
+        }
+    } else if b.data.is_unique() {
+        let a_data = a.data.as_slice();
+        let b_data = b.data.as_mut_slice();
+        if b_data.len() >= min_len {
+            (a_data.par_chunks(chunk_len))
+                .zip(b_data.par_chunks_mut(chunk_len))
+                .for_each(|(a, b)| apply_into_b(op, a, b));
+        } else {
+            apply_into_b(op, a_data, b_data);
+        }
+        *a = b;
+    } else {
+        let a_data = a.data.as_mut_slice();
+        let b_data = b.data.as_slice();
+        if a_data.len() >= min_len {
+            (a_data.par_chunks_mut(chunk_len))
+                .zip(b_data.par_chunks(chunk_len))
+                .for_each(|(a, b)| apply_into_a(op, a, b));
+        } else {
+            apply_into_a(op, a_data, b_data);
+        }
+    }
+    Ok(())
+}
+
+/// Compute `a[i] = op(a[i], b[i])`
+pub fn apply_into_a(op: SimdOp, a: &mut [f64], b: &[f64]) {
+    assert_eq!(a.len(), b.len());
+    let out = a.as_mut_ptr();
+    // SAFETY: `out` and `b` are valid for `a.len()` elements,
+    // and `out` only aliases the `a` input
+    unsafe { kernel(op, out, out, b.as_ptr(), a.len()) }
+}
+
+/// Compute `b[i] = op(a[i], b[i])`
+pub fn apply_into_b(op: SimdOp, a: &[f64], b: &mut [f64]) {
+    assert_eq!(a.len(), b.len());
+    let out = b.as_mut_ptr();
+    // SAFETY: `a` and `out` are valid for `b.len()` elements,
+    // and `out` only aliases the `b` input
+    unsafe { kernel(op, out, a.as_ptr(), out, b.len()) }
+}
+
+/// Compute `a[i] = op(a[i], a[i])`
+pub fn apply_in_place(op: SimdOp, a: &mut [f64]) {
+    let out = a.as_mut_ptr();
+    // SAFETY: `out` is valid for `a.len()` elements
+    unsafe { kernel(op, out, out, out, a.len()) }
+}
+
+/// Compute `out[i] = op(a[i], b[i])` for `len` elements
+///
+/// # Safety
+/// All pointers must be valid for `len` elements.
+/// `out` may alias `a` or `b`, but only at the same index.
+unsafe fn kernel(op: SimdOp, out: *mut f64, a: *const f64, b: *const f64, len: usize) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx") {
+        return kernel_avx(op, out, a, b, len);
+    }
+    kernel_scalar(op, out, a, b, len)
+}
+
+unsafe fn kernel_scalar(op: SimdOp, out: *mut f64, a: *const f64, b: *const f64, len: usize) {
+    for i in 0..len {
+        *out.add(i) = op.scalar(*a.add(i), *b.add(i));
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn kernel_avx(op: SimdOp, out: *mut f64, a: *const f64, b: *const f64, len: usize) {
+    use std::arch::x86_64::*;
+    const LANES: usize = 4;
+    let body = len - len % LANES;
+    macro_rules! lanes {
+        ($f:ident) => {
+            for i in (0..body).step_by(LANES) {
+                let va = _mm256_loadu_pd(a.add(i));
+                let vb = _mm256_loadu_pd(b.add(i));
+                // Operands are swapped to match the scalar functions
+                _mm256_storeu_pd(out.add(i), $f(vb, va));
+            }
+        };
+    }
+    match op {
+        SimdOp::Add => lanes!(_mm256_add_pd),
+        SimdOp::Sub => lanes!(_mm256_sub_pd),
+        SimdOp::Mul => lanes!(_mm256_mul_pd),
+        SimdOp::Div => lanes!(_mm256_div_pd),
+    }
+    kernel_scalar(op, out.add(body), a.add(body), b.add(body), len - body)
+}
@@ -167,6 +167,37 @@ fn generic_table(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     Ok(())
 }
 
+impl Value {
+    /// Apply a function to every pair of elements of this array and another
+    ///
+    /// `f` is called with an element of this array and an element of the other.
+    /// The result's shape is this array's shape, followed by the other's shape,
+    /// followed by the shape of `f`'s results. This is like [`table`](crate::primitive::Primitive::Table).
+    pub fn outer(
+        &self,
+        other: &Self,
+        mut f: impl FnMut(Value, Value) -> UiuaResult<Value>,
+        env: &Uiua,
+    ) -> UiuaResult<Value> {
+        let mut new_shape = Shape::from(self.shape());
+        new_shape.extend_from_slice(other.shape());
+        let mut items = Value::builder(self.flat_len() * other.flat_len());
+        let other_values = other.clone().into_flat_values().collect::<Vec<_>>();
+        for x in self.clone().into_flat_values() {
+            for y in other_values.iter().cloned() {
+                let item = f(x.clone(), y)?;
+                item.validate_shape();
+                items.add_row(item, env)?;
+            }
+        }
+        let mut outer = items.finish();
+        new_shape.extend_from_slice(&outer.shape()[1..]);
+        *outer.shape_mut() = new_shape;
+        outer.validate_shape();
+        Ok(outer)
+    }
+}
+
 pub fn cross(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop(FunctionArg(1))?;
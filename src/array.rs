@@ -6,6 +6,7 @@ use std::{
 };
 
 use ecow::EcoVec;
+use num_complex::Complex64;
 use tinyvec::{tiny_vec, TinyVec};
 
 use crate::{
@@ -25,6 +26,39 @@ pub struct Array<T> {
 
 pub type Shape = TinyVec<[usize; 3]>;
 
+/// Helper methods for [`Shape`]s
+pub trait ShapeExt {
+    /// Get the number of elements in an array with this shape
+    fn product(&self) -> usize;
+    /// Get the shape that results from broadcasting this shape with another
+    ///
+    /// Shapes are aligned at their last axes. Each pair of axes must either be
+    /// equal or contain a `1`, which is stretched to match the other. A missing
+    /// axis is treated as `1`. Returns `None` if the shapes are incompatible.
+    fn broadcast_with(&self, other: &Shape) -> Option<Shape>;
+}
+
+impl ShapeExt for Shape {
+    fn product(&self) -> usize {
+        self.iter().product()
+    }
+    fn broadcast_with(&self, other: &Shape) -> Option<Shape> {
+        let rank = self.len().max(other.len());
+        let mut shape = Shape::with_capacity(rank);
+        for i in (1..=rank).rev() {
+            let a = self.len().checked_sub(i).map_or(1, |j| self[j]);
+            let b = other.len().checked_sub(i).map_or(1, |j| other[j]);
+            shape.push(match (a, b) {
+                (a, b) if a == b => a,
+                (1, b) => b,
+                (a, 1) => a,
+                _ => return None,
+            });
+        }
+        Some(shape)
+    }
+}
+
 impl<T: ArrayValue> Default for Array<T> {
     fn default() -> Self {
         Self {
@@ -206,12 +240,10 @@ impl<T: ArrayValue> Array<T> {
         } else {
             row_shape.remove(0)
         };
-        let mut data = self.data.into_iter();
-        (0..row_count).map(move |_| {
-            Array::new(
-                row_shape.clone(),
-                data.by_ref().take(row_len).collect::<CowSlice<_>>(),
-            )
+        let data = self.data;
+        (0..row_count).map(move |row| {
+            let start = row * row_len;
+            Array::new(row_shape.clone(), data.slice(start..start + row_len))
         })
     }
     pub fn into_rows_rev(self) -> impl Iterator<Item = Self> {
@@ -222,10 +254,10 @@ impl<T: ArrayValue> Array<T> {
         } else {
             row_shape.remove(0)
         };
-        let mut data = self.data.into_iter().rev();
-        (0..row_count).map(move |_| {
-            let row: CowSlice<_> = data.by_ref().take(row_len).rev().collect();
-            Array::new(row_shape.clone(), row)
+        let data = self.data;
+        (0..row_count).rev().map(move |row| {
+            let start = row * row_len;
+            Array::new(row_shape.clone(), data.slice(start..start + row_len))
         })
     }
     pub(crate) fn first_dim_zero(&self) -> Self {
@@ -238,6 +270,13 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Array<i64> {
+    /// Convert to a number array, possibly losing precision
+    pub fn into_nums(self) -> Array<f64> {
+        self.convert_with(|i| i as f64)
+    }
+}
+
 impl Array<Arc<Function>> {
     pub fn into_unboxed(self) -> Result<Value, Self> {
         match self.into_scalar() {
@@ -403,6 +442,16 @@ impl ArrayValue for f64 {
     }
 }
 
+impl ArrayValue for i64 {
+    const NAME: &'static str = "number";
+    fn get_fill(env: &Uiua) -> Option<Self> {
+        env.int_fill()
+    }
+    fn array_hash<H: Hasher>(&self, hasher: &mut H) {
+        self.hash(hasher)
+    }
+}
+
 impl ArrayValue for u8 {
     const NAME: &'static str = "number";
     fn get_fill(env: &Uiua) -> Option<Self> {
@@ -413,6 +462,17 @@ impl ArrayValue for u8 {
     }
 }
 
+impl ArrayValue for Complex64 {
+    const NAME: &'static str = "complex";
+    fn get_fill(env: &Uiua) -> Option<Self> {
+        env.complex_fill()
+    }
+    fn array_hash<H: Hasher>(&self, hasher: &mut H) {
+        self.re.array_hash(hasher);
+        self.im.array_hash(hasher);
+    }
+}
+
 impl ArrayValue for char {
     const NAME: &'static str = "character";
     fn get_fill(env: &Uiua) -> Option<Self> {
@@ -471,12 +531,26 @@ impl ArrayCmp for f64 {
     }
 }
 
+impl ArrayCmp for i64 {
+    fn array_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
 impl ArrayCmp for u8 {
     fn array_cmp(&self, other: &Self) -> Ordering {
         self.cmp(other)
     }
 }
 
+impl ArrayCmp for Complex64 {
+    fn array_cmp(&self, other: &Self) -> Ordering {
+        self.re
+            .array_cmp(&other.re)
+            .then_with(|| self.im.array_cmp(&other.im))
+    }
+}
+
 impl ArrayCmp for char {
     fn array_cmp(&self, other: &Self) -> Ordering {
         self.cmp(other)
@@ -501,6 +575,54 @@ impl ArrayCmp<u8> for f64 {
     }
 }
 
+impl ArrayCmp<f64> for i64 {
+    fn array_cmp(&self, other: &f64) -> Ordering {
+        (*self as f64).array_cmp(other)
+    }
+}
+
+impl ArrayCmp<i64> for f64 {
+    fn array_cmp(&self, other: &i64) -> Ordering {
+        self.array_cmp(&(*other as f64))
+    }
+}
+
+impl ArrayCmp<u8> for i64 {
+    fn array_cmp(&self, other: &u8) -> Ordering {
+        self.cmp(&i64::from(*other))
+    }
+}
+
+impl ArrayCmp<i64> for u8 {
+    fn array_cmp(&self, other: &i64) -> Ordering {
+        i64::from(*self).cmp(other)
+    }
+}
+
+impl ArrayCmp<f64> for Complex64 {
+    fn array_cmp(&self, other: &f64) -> Ordering {
+        self.array_cmp(&Complex64::from(*other))
+    }
+}
+
+impl ArrayCmp<Complex64> for f64 {
+    fn array_cmp(&self, other: &Complex64) -> Ordering {
+        Complex64::from(*self).array_cmp(other)
+    }
+}
+
+impl ArrayCmp<u8> for Complex64 {
+    fn array_cmp(&self, other: &u8) -> Ordering {
+        self.array_cmp(&Complex64::from(*other as f64))
+    }
+}
+
+impl ArrayCmp<Complex64> for u8 {
+    fn array_cmp(&self, other: &Complex64) -> Ordering {
+        Complex64::from(*self as f64).array_cmp(other)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct FormatShape<'a>(pub &'a [usize]);
 
@@ -522,3 +644,42 @@ impl<'a> fmt::Display for FormatShape<'a> {
         write!(f, "]")
     }
 }
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Array<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Array", 2)?;
+        state.serialize_field("shape", &self.shape)?;
+        state.serialize_field("data", &*self.data)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Clone + serde::Deserialize<'de>> serde::Deserialize<'de> for Array<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Array")]
+        struct Rep<T> {
+            shape: Shape,
+            data: Vec<T>,
+        }
+        let Rep { shape, data } = Rep::deserialize(deserializer)?;
+        array_from_parts(shape, data).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Build an array from deserialized parts, checking that the shape matches the data
+#[cfg(feature = "serde")]
+pub(crate) fn array_from_parts<T: Clone>(shape: Shape, data: Vec<T>) -> Result<Array<T>, String> {
+    let expected: usize = shape.iter().product();
+    if expected != data.len() {
+        return Err(format!(
+            "shape {} does not match data length {}",
+            FormatShape(&shape),
+            data.len()
+        ));
+    }
+    Ok(Array::new(shape, data.into_iter().collect::<CowSlice<T>>()))
+}
@@ -15,6 +15,23 @@ pub enum Item {
     ExtraNewlines(CodeSpan),
 }
 
+impl Item {
+    /// Get the span of the item's source code
+    ///
+    /// Returns `None` for scopes that contain no items.
+    /// The span of a scope does not include its delimiters.
+    pub fn span(&self) -> Option<CodeSpan> {
+        match self {
+            Item::Scoped { items, .. } => {
+                items.iter().filter_map(Item::span).reduce(CodeSpan::merge)
+            }
+            Item::Words(words) => words_span(words),
+            Item::Binding(binding) => Some(binding.span()),
+            Item::ExtraNewlines(span) => Some(span.clone()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Binding {
     pub name: Sp<Ident>,
@@ -22,6 +39,25 @@ pub struct Binding {
     pub words: Vec<Sp<Word>>,
 }
 
+impl Binding {
+    /// Get the span of the binding's source code, from its name to its last word
+    pub fn span(&self) -> CodeSpan {
+        let span = self.name.span.clone();
+        match (words_span(&self.words), &self.signature) {
+            (Some(words), _) => span.merge(words),
+            (None, Some(sig)) => span.merge(sig.span.clone()),
+            (None, None) => span,
+        }
+    }
+}
+
+/// Get the span from the first to the last of some words
+pub fn words_span(words: &[Sp<Word>]) -> Option<CodeSpan> {
+    let first = words.first()?.span.clone();
+    let last = words.last()?.span.clone();
+    Some(first.merge(last))
+}
+
 #[derive(Clone)]
 pub enum Word {
     Number(String, f64),
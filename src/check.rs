@@ -75,7 +75,9 @@ impl<'a> BasicValue<'a> {
         } else if value.rank() == 1 {
             BasicValue::Arr(match value {
                 Value::Num(n) => n.data.iter().map(|n| BasicValue::Num(*n)).collect(),
+                Value::Int(n) => n.data.iter().map(|n| BasicValue::Num(*n as f64)).collect(),
                 Value::Byte(b) => b.data.iter().map(|b| BasicValue::Num(*b as f64)).collect(),
+                Value::Complex(c) => c.data.iter().map(|_| BasicValue::Other).collect(),
                 Value::Char(c) => c.data.iter().map(|_| BasicValue::Other).collect(),
                 Value::Func(f) => f
                     .data
@@ -161,7 +163,7 @@ impl<'a> VirtualEnv<'a> {
                     }
                     self.handle_args_outputs(2, 1)?;
                 }
-                Distribute => {
+                Distribute | Memo => {
                     let sig = self.pop()?.expect_function(|| prim)?;
                     self.handle_sig(sig)?
                 }
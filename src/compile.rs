@@ -55,27 +55,19 @@ impl Uiua {
                 _ => false,
             }
         }
+        let can_run = self.item_can_run(&item, in_test);
         match item {
             Item::Scoped { items, test } => {
                 let scope_stack = self.in_scope(true, |env| env.items(items, test))?;
                 self.stack.extend(scope_stack);
             }
             Item::Words(words) => {
-                let can_run = match self.mode {
-                    RunMode::Normal => !in_test,
-                    RunMode::Test => in_test,
-                    RunMode::All => true,
-                };
                 if can_run || words_have_import(&words) || words_are_export(&words) {
                     let instrs = self.compile_words(words, true)?;
                     self.exec_global_instrs(instrs)?;
                 }
             }
             Item::Binding(binding) => {
-                let can_run = match self.mode {
-                    RunMode::Normal => !in_test,
-                    RunMode::All | RunMode::Test => true,
-                };
                 if can_run || words_have_import(&binding.words) {
                     self.binding(binding)?;
                 }
@@ -84,6 +76,69 @@ impl Uiua {
         }
         Ok(())
     }
+    /// Check if an item should be run in the current mode
+    ///
+    /// Words only run in the scopes the mode selects,
+    /// but bindings are also evaluated in test scopes when running tests.
+    fn item_can_run(&self, item: &Item, in_test: bool) -> bool {
+        match (item, self.mode) {
+            (Item::Words(_), RunMode::Test) => in_test,
+            (Item::Words(_) | Item::Binding(_), RunMode::Normal) => !in_test,
+            _ => true,
+        }
+    }
+    /// Compile an item without running its words
+    ///
+    /// Bindings are still evaluated, as they would be when loading.
+    /// Errors are collected rather than returned, and bindings that fail
+    /// are bound to placeholders so that later items can still refer to them.
+    pub(crate) fn compile_item(
+        &mut self,
+        item: Item,
+        in_test: bool,
+        lines: &mut Vec<Vec<Instr>>,
+        errors: &mut Vec<UiuaError>,
+    ) {
+        let can_run = self.item_can_run(&item, in_test);
+        match item {
+            Item::Scoped { items, test } => {
+                let res = self.in_scope(true, |env| {
+                    for item in items {
+                        env.compile_item(item, test, lines, errors);
+                    }
+                    Ok(())
+                });
+                if let Err(e) = res {
+                    errors.push(e);
+                }
+            }
+            Item::Words(words) => {
+                if can_run {
+                    match self.compile_words(words, true) {
+                        Ok(instrs) => lines.push(instrs),
+                        Err(e) => errors.push(e),
+                    }
+                }
+            }
+            Item::Binding(binding) => {
+                if !can_run {
+                    return;
+                }
+                let name = binding.name.value.clone();
+                let sig =
+                    (binding.signature.as_ref()).map_or(Signature::new(0, 0), |sig| sig.value);
+                if let Err(e) = self.binding(binding) {
+                    errors.push(e);
+                    let placeholder =
+                        Function::new(FunctionId::Named(name.clone()), Vec::new(), sig);
+                    let mut globals = self.globals.lock();
+                    self.scope.names.insert(name, globals.len());
+                    globals.push(placeholder.into());
+                }
+            }
+            Item::ExtraNewlines(_) => {}
+        }
+    }
     fn add_span(&mut self, span: impl Into<Span>) -> usize {
         let mut spans = self.spans.lock();
         let idx = spans.len();
@@ -156,13 +211,14 @@ impl Uiua {
     }
     fn compile_words(&mut self, words: Vec<Sp<Word>>, call: bool) -> UiuaResult<Vec<Instr>> {
         self.new_functions.push(Vec::new());
-        self.words(words, call)?;
+        let res = self.words(words, call);
+        let instrs = self.new_functions.pop().unwrap();
+        res?;
         if self.print_diagnostics {
             for diagnostic in self.take_diagnostics() {
                 eprintln!("{}", diagnostic.show(true));
             }
         }
-        let instrs = self.new_functions.pop().unwrap();
         Ok(instrs)
     }
     fn compile_operand_words(
@@ -658,7 +714,7 @@ impl Uiua {
             };
             self.diagnostics.insert(Diagnostic::new(
                 format!(
-                    "Warning: {}{} is deprecated and will be removed in a future version{}",
+                    "{}{} is deprecated and will be removed in a future version{}",
                     prim.name().unwrap_or_default(),
                     prim,
                     suggestion
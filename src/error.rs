@@ -30,6 +30,8 @@ pub enum UiuaError {
     Throw(Box<Value>, Span),
     Break(usize, Span),
     Timeout(Span),
+    ExecutionLimit(Span),
+    CallDepthExceeded(usize, Span),
     Fill(Box<Self>),
 }
 
@@ -76,6 +78,10 @@ impl fmt::Display for UiuaError {
             UiuaError::Throw(value, span) => write!(f, "{span}: {value}"),
             UiuaError::Break(_, span) => write!(f, "{span}: Break amount exceeded loop depth"),
             UiuaError::Timeout(_) => write!(f, "Maximum execution time exceeded"),
+            UiuaError::ExecutionLimit(_) => write!(f, "Maximum execution steps exceeded"),
+            UiuaError::CallDepthExceeded(depth, _) => {
+                write!(f, "Maximum call depth of {depth} exceeded")
+            }
             UiuaError::Fill(error) => error.fmt(f),
         }
     }
@@ -107,6 +113,40 @@ impl UiuaError {
             error => Err(error),
         }
     }
+    /// Get the frames of the call stack that the error propagated through, innermost first
+    pub fn trace(&self) -> &[TraceFrame] {
+        match self {
+            UiuaError::Traced { trace, .. } => trace,
+            UiuaError::Fill(error) => error.trace(),
+            _ => &[],
+        }
+    }
+    /// Check if the error was caused by exceeding the timeout
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.is_timeout(),
+            UiuaError::Timeout(_) => true,
+            _ => false,
+        }
+    }
+    /// Check if the error was caused by exceeding the execution step limit
+    pub fn is_execution_limit(&self) -> bool {
+        match self {
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.is_execution_limit(),
+            UiuaError::ExecutionLimit(_) => true,
+            _ => false,
+        }
+    }
+    /// Check if the error was caused by exceeding the maximum call depth
+    pub fn is_call_depth_exceeded(&self) -> bool {
+        match self {
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => {
+                error.is_call_depth_exceeded()
+            }
+            UiuaError::CallDepthExceeded(..) => true,
+            _ => false,
+        }
+    }
     /// Check if the error is fill-related
     pub(crate) fn is_fill(&self) -> bool {
         match self {
@@ -215,6 +255,19 @@ impl UiuaError {
                 kind,
                 color,
             ),
+            UiuaError::ExecutionLimit(span) => report(
+                [("Maximum execution steps exceeded", span.clone())],
+                kind,
+                color,
+            ),
+            UiuaError::CallDepthExceeded(depth, span) => report(
+                [(
+                    &format!("Maximum call depth of {depth} exceeded"),
+                    span.clone(),
+                )],
+                kind,
+                color,
+            ),
             UiuaError::Fill(error) => error.show(color),
             UiuaError::Load(..) | UiuaError::Format(..) => self.to_string(),
         }
@@ -229,11 +282,27 @@ pub struct Diagnostic {
     pub kind: DiagnosticKind,
 }
 
+/// The severity of a [`Diagnostic`]
+///
+/// Kinds are ordered from least to most severe.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DiagnosticKind {
-    Warning,
-    Advice,
+    /// A suggestion to make code clearer
     Style,
+    /// A suggestion to make code simpler or shorter
+    Advice,
+    /// Code that is likely a mistake or will stop working in the future
+    Warning,
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::Style => write!(f, "Style"),
+            DiagnosticKind::Advice => write!(f, "Advice"),
+            DiagnosticKind::Warning => write!(f, "Warning"),
+        }
+    }
 }
 
 impl fmt::Display for Diagnostic {
@@ -251,6 +320,9 @@ impl Diagnostic {
         }
     }
     pub fn show(&self, color: bool) -> String {
+        if let Span::Builtin = self.span {
+            return format!("{}: {}", self.kind, self.message);
+        }
         report(
             [(&self.message, self.span.clone())],
             match self.kind {
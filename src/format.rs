@@ -6,6 +6,7 @@ use std::{
     env,
     fmt::Display,
     fs,
+    ops::Range,
     path::{Path, PathBuf},
 };
 
@@ -14,7 +15,7 @@ use paste::paste;
 use crate::{
     ast::*,
     function::Signature,
-    grid_fmt::GridFmt,
+    grid_fmt::{GridFmt, GridFmtConfig},
     lex::{is_ident_char, CodeSpan, Loc, Sp},
     parse::parse,
     value::Value,
@@ -200,6 +201,8 @@ create_config!(
     (multiline_compact_threshold, usize, 10),
     /// Whether to align consecutive end-of-line comments
     (align_comments, bool, true),
+    /// The maximum number of consecutive blank lines to keep between items
+    (max_blank_lines, usize, 1),
 );
 
 /// The source from which to populate the formatter configuration.
@@ -374,6 +377,90 @@ pub fn format_file<P: AsRef<Path>>(path: P, config: &FormatConfig) -> UiuaResult
     Ok(formatted)
 }
 
+/// A replacement of a range of text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The byte range of the original text to replace
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+/// Format some code and get the edits that turn it into the formatted code
+///
+/// Edits are made only to the lines that change. They are sorted by position
+/// and do not overlap, and their ranges refer to the original code.
+pub fn format_edits(input: &str, config: &FormatConfig) -> UiuaResult<Vec<TextEdit>> {
+    let formatted = format_str(input, config)?;
+    Ok(line_edits(input, &formatted.output))
+}
+
+/// The maximum number of line pairs to compare when diffing
+///
+/// Above this, the changed lines are replaced with a single edit
+/// to keep the memory used by the diff table bounded.
+const MAX_DIFF_CELLS: usize = 1 << 20;
+
+/// Get the edits that turn one text into another, replacing whole lines
+fn line_edits(old: &str, new: &str) -> Vec<TextEdit> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    // Only diff the lines between the common prefix and suffix
+    let prefix = (old_lines.iter().zip(&new_lines))
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = (old_lines[prefix..].iter().rev())
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old_lines[prefix..old_lines.len() - suffix];
+    let b = &new_lines[prefix..new_lines.len() - suffix];
+    let mut pos: usize = old_lines[..prefix].iter().map(|line| line.len()).sum();
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        let len: usize = a.iter().map(|line| line.len()).sum();
+        return vec![TextEdit {
+            range: pos..pos + len,
+            new_text: b.concat(),
+        }];
+    }
+    // Lengths of the longest common subsequences of the remaining lines
+    let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut edits = Vec::new();
+    let mut hunk: Option<TextEdit> = None;
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            edits.extend(hunk.take());
+            pos += a[i].len();
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let edit = hunk.get_or_insert_with(|| TextEdit {
+            range: pos..pos,
+            new_text: String::new(),
+        });
+        if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            edit.new_text.push_str(b[j]);
+            j += 1;
+        } else {
+            pos += a[i].len();
+            edit.range.end = pos;
+            i += 1;
+        }
+    }
+    edits.extend(hunk);
+    edits
+}
+
 struct Formatter<'a> {
     config: &'a FormatConfig,
     output: String,
@@ -384,6 +471,15 @@ struct Formatter<'a> {
 impl<'a> Formatter<'a> {
     fn format_items(&mut self, items: &[Item]) {
         for item in items {
+            if let Item::ExtraNewlines(span) = item {
+                // Leading blank lines are trimmed, and runs of blank lines are clamped
+                let blank_lines = span.as_str().matches('\n').count();
+                let blank_lines = blank_lines.min(self.config.max_blank_lines);
+                if !self.output.is_empty() {
+                    self.output.push_str(&"\n".repeat(blank_lines));
+                }
+                continue;
+            }
             self.format_item(item);
             self.output.push('\n');
         }
@@ -474,8 +570,9 @@ impl<'a> Formatter<'a> {
     fn format_word(&mut self, word: &Sp<Word>, depth: usize) {
         match &word.value {
             Word::Number(s, n) => {
-                let grid_str = n.grid_string();
-                if grid_str.len() < s.len() {
+                let grid_str = GridFmtConfig::default().scoped(|| n.grid_string());
+                // Hexadecimal and binary literals are kept as written
+                if grid_str.len() < s.len() && !s.contains(['x', 'X', 'b', 'B']) {
                     self.output.push_str(&grid_str);
                 } else {
                     self.output.push_str(&s.replace('`', "¯"));
@@ -734,3 +831,130 @@ fn end_loc(s: &str) -> Loc {
         byte_pos,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_blank_lines() {
+        let input = "\n\nA ← 1\n\n\nB ← 2\n# comment\n\nC ← 3\n\n\n";
+        let config = FormatConfig::default();
+        let output = format_str(input, &config).unwrap().output;
+        assert_eq!(output, "A ← 1\n\nB ← 2\n# comment\n\nC ← 3\n");
+        let config = FormatConfig::default().with_max_blank_lines(2);
+        let output = format_str(input, &config).unwrap().output;
+        assert_eq!(output, "A ← 1\n\n\nB ← 2\n# comment\n\nC ← 3\n");
+        let config = FormatConfig::default().with_max_blank_lines(0);
+        let output = format_str(input, &config).unwrap().output;
+        assert_eq!(output, "A ← 1\nB ← 2\n# comment\nC ← 3\n");
+    }
+    #[test]
+    fn align_comments() {
+        let input = "+ 1 2 # a\n×3 # b\n⇌ ⇡ 10 # c\n\nX ← 5 # d\n# e\nY ← 123 # f";
+        let config = FormatConfig::default();
+        let output = format_str(input, &config).unwrap().output;
+        assert_eq!(
+            output,
+            "+ 1 2  # a\n×3     # b\n⇌ ⇡ 10 # c\n\nX ← 5 # d\n# e\nY ← 123 # f\n"
+        );
+        let config = FormatConfig::default().with_align_comments(false);
+        let output = format_str(input, &config).unwrap().output;
+        assert_eq!(
+            output,
+            "+ 1 2 # a\n×3 # b\n⇌ ⇡ 10 # c\n\nX ← 5 # d\n# e\nY ← 123 # f\n"
+        );
+    }
+
+    fn apply_edits(input: &str, edits: &[TextEdit]) -> String {
+        let mut output = input.to_string();
+        for edit in edits.iter().rev() {
+            output.replace_range(edit.range.clone(), &edit.new_text);
+        }
+        output
+    }
+
+    #[test]
+    fn format_edits_lines() {
+        let config = FormatConfig::default();
+        let input = "A ← 1\nB ← 2\n   C ← +1 2\nD ← 4\n";
+        let edits = format_edits(input, &config).unwrap();
+        assert_eq!(edits.len(), 1, "{edits:?}");
+        assert_eq!(&input[edits[0].range.clone()], "   C ← +1 2\n");
+        assert_eq!(edits[0].new_text, "C ← +1 2\n");
+
+        let formatted = format_str(input, &config).unwrap().output;
+        assert!(format_edits(&formatted, &config).unwrap().is_empty());
+
+        let input = "X ← +1 2\n\n\n\nY ← 3 # y\nZ ← 5  # z\n[1 2 3]";
+        let edits = format_edits(input, &config).unwrap();
+        assert!(edits.len() > 1, "{edits:?}");
+        assert!(edits.windows(2).all(|w| w[0].range.end <= w[1].range.start));
+        let formatted = format_str(input, &config).unwrap().output;
+        assert_eq!(apply_edits(input, &edits), formatted);
+        assert!(format_edits("[1 2", &config).is_err());
+
+        // Large changes are made in a single edit
+        let old = "a\n".repeat(2000);
+        let new = "b\n".repeat(2000);
+        let edits = line_edits(&old, &new);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(apply_edits(&old, &edits), new);
+    }
+
+    fn assert_idempotent(input: &str, name: &str) {
+        let config = FormatConfig::default();
+        let once = format_str(input, &config)
+            .unwrap_or_else(|e| panic!("{name} failed to format: {e}"))
+            .output;
+        let twice = format_str(&once, &config)
+            .unwrap_or_else(|e| panic!("{name} failed to format after formatting: {e}"))
+            .output;
+        assert_eq!(once, twice, "formatting {name} is not idempotent");
+    }
+
+    #[test]
+    fn idempotence() {
+        let snippets = [
+            "[1 2 3]",
+            "0xFF 0b1010 ¯0x10",
+            "[1 ∞ ¯∞ NaN] 1_¯∞",
+            "$$ raw \\n _\n$ line _",
+            "[1_2_3 4_5_6]",
+            "[[1 2 3]\n [4 5 6]]",
+            "[1 2 3\n 4 5 6\n 7 8 9]",
+            "[[1 2]\n  [3 4]\n    [5 6]]",
+            "{1 2\n\"abc\"\n[4 5]}",
+            "F ← (\n  +1\n  ×2\n)",
+            "F ← (+1\n×2)",
+            "X ← [1 2 3\n      4 5 6]",
+            "+ 1 2 # comment\n× 3 4 # another comment",
+            "A ← 1\n\n\n\nB ← 2",
+            "---\nA ← 1\n\n\nB ← 2\n---",
+            "⍥(|1\n  ×2\n)5 1",
+            "∵(\n  [.\n   .]\n)[1 2]",
+            "LongerName ← [1 2\n3 4]",
+            "Q ← [[1 2\n3 4] [5\n6]]",
+            "⊂ [1 2\n3] [4 5\n6]",
+            "[(+\n1)(×\n2)]",
+            "F ← (\n  +1 # a\n\n  # standalone\n  ×22 # b\n)",
+        ];
+        for (i, snippet) in snippets.into_iter().enumerate() {
+            assert_idempotent(snippet, &format!("snippet {i}"));
+        }
+        for entry in fs::read_dir("tests").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "ua") {
+                let input = fs::read_to_string(&path).unwrap();
+                assert_idempotent(&input, &path.display().to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn infinity_literals() {
+        let config = FormatConfig::default();
+        let output = format_str("[`∞ infinity] 2_`∞", &config).unwrap().output;
+        assert_eq!(output, "[¯∞ ∞] 2_¯∞\n");
+    }
+}
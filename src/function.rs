@@ -2,7 +2,7 @@ use std::{
     cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
-    mem::{discriminant, transmute},
+    mem::{discriminant, take, transmute},
     sync::Arc,
 };
 
@@ -404,9 +404,9 @@ impl Function {
             _ => None,
         }
     }
-    pub fn into_unboxed(self) -> Result<Value, Self> {
+    pub fn into_unboxed(mut self) -> Result<Value, Self> {
         if self.is_constant() {
-            if let Instr::Push(val) = self.instrs.into_iter().next().unwrap() {
+            if let Instr::Push(val) = self.instrs.pop().unwrap() {
                 Ok(*val)
             } else {
                 unreachable!();
@@ -446,6 +446,39 @@ impl Function {
     }
 }
 
+impl Drop for Function {
+    /// Boxes can be nested arbitrarily deep, so nested functions are
+    /// dropped iteratively rather than recursively
+    fn drop(&mut self) {
+        fn is_nested(instr: &Instr) -> bool {
+            matches!(instr, Instr::Push(val) if matches!(**val, Value::Func(_)))
+        }
+        if !self.instrs.iter().any(is_nested) {
+            return;
+        }
+        let mut instrs = take(&mut self.instrs);
+        let mut nested = Vec::new();
+        loop {
+            nested.extend(instrs.drain(..).filter(is_nested));
+            let Some(Instr::Push(val)) = nested.pop() else {
+                break;
+            };
+            let Value::Func(arr) = *val else {
+                unreachable!()
+            };
+            // Shared data is only decremented, so it cannot recurse
+            let mut data = arr.data;
+            if data.is_unique() {
+                for f in data {
+                    if let Ok(mut f) = Arc::try_unwrap(f) {
+                        instrs.append(&mut f.instrs);
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum FunctionId {
     Named(Ident),
@@ -2,15 +2,19 @@
 
 use std::{
     any::type_name,
+    borrow::Cow,
+    cell::RefCell,
     f64::{
         consts::{PI, TAU},
         INFINITY,
     },
-    iter::once,
+    iter::{once, repeat_n},
     mem::take,
     sync::Arc,
 };
 
+use num_complex::Complex64;
+
 use crate::{
     array::{Array, ArrayValue},
     function::Function,
@@ -21,14 +25,184 @@ use crate::{
 type Grid<T = char> = Vec<Vec<T>>;
 type Metagrid = Grid<Grid>;
 
+/// Configuration for pretty-printing arrays
+///
+/// The configuration is thread-local. It is read whenever a value is formatted
+/// with [`GridFmt`], so it affects [`Value::show`] and the [`std::fmt::Display`] impls.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GridFmtConfig {
+    /// The number of significant digits to which to round displayed numbers
+    ///
+    /// If `None`, numbers are displayed with full precision
+    pub float_precision: Option<usize>,
+    /// The magnitude bounds outside of which numbers are displayed in scientific notation
+    ///
+    /// Numbers with a magnitude less than the first bound or greater than or equal to
+    /// the second bound are displayed like `1.2e6`. Zero is never displayed in scientific notation.
+    pub scientific_threshold: Option<(f64, f64)>,
+    /// The maximum number of rows to display
+    ///
+    /// If an array has more rows, only the first and last few are displayed, separated by `…`
+    pub max_rows: Option<usize>,
+    /// The maximum number of columns to display
+    ///
+    /// If an array has more columns, only the first and last few are displayed, separated by `…`
+    pub max_cols: Option<usize>,
+    /// Whether to colorize numbers, characters, and functions with ANSI escape codes
+    pub color: bool,
+}
+
+thread_local! {
+    static GRID_FMT_CONFIG: RefCell<GridFmtConfig> = RefCell::new(GridFmtConfig::default());
+}
+
+impl GridFmtConfig {
+    /// Set the number of significant digits to which to round displayed numbers
+    pub fn with_float_precision(mut self, float_precision: Option<usize>) -> Self {
+        self.float_precision = float_precision;
+        self
+    }
+    /// Set the magnitude bounds outside of which numbers are displayed in scientific notation
+    pub fn with_scientific_threshold(mut self, scientific_threshold: Option<(f64, f64)>) -> Self {
+        self.scientific_threshold = scientific_threshold;
+        self
+    }
+    /// Set the maximum number of rows to display
+    pub fn with_max_rows(mut self, max_rows: Option<usize>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+    /// Set the maximum number of columns to display
+    pub fn with_max_cols(mut self, max_cols: Option<usize>) -> Self {
+        self.max_cols = max_cols;
+        self
+    }
+    /// Set whether to colorize numbers, characters, and functions with ANSI escape codes
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+    /// Get the configuration for the current thread
+    pub fn current() -> Self {
+        GRID_FMT_CONFIG.with(|config| config.borrow().clone())
+    }
+    /// Set the configuration for the current thread, returning the previous one
+    pub fn set(self) -> Self {
+        GRID_FMT_CONFIG.with(|config| config.replace(self))
+    }
+    /// Use this configuration for the current thread for the duration of a function call
+    ///
+    /// The previous configuration is restored even if the function panics.
+    pub fn scoped<T>(self, f: impl FnOnce() -> T) -> T {
+        let _guard = RestoreConfig(Some(self.set()));
+        f()
+    }
+}
+
+/// Restores a configuration when dropped
+struct RestoreConfig(Option<GridFmtConfig>);
+
+impl Drop for RestoreConfig {
+    fn drop(&mut self) {
+        if let Some(prev) = self.0.take() {
+            prev.set();
+        }
+    }
+}
+
+/// The color categories of grid cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellColor {
+    Number,
+    Char,
+    Function,
+}
+
+// Color markers are private-use characters that are replaced with
+// ANSI escape codes once the grid is laid out
+const RESET_MARKER: char = '\u{F0000}';
+const NUMBER_MARKER: char = '\u{F0001}';
+const CHAR_MARKER: char = '\u{F0002}';
+const FUNCTION_MARKER: char = '\u{F0003}';
+
+impl CellColor {
+    fn marker(self) -> char {
+        match self {
+            CellColor::Number => NUMBER_MARKER,
+            CellColor::Char => CHAR_MARKER,
+            CellColor::Function => FUNCTION_MARKER,
+        }
+    }
+}
+
+/// Check if a character is a color marker
+///
+/// Markers are only inserted when color is enabled.
+/// Otherwise, these characters are treated like any other.
+fn is_color_marker(c: char, color: bool) -> bool {
+    color && (RESET_MARKER..=FUNCTION_MARKER).contains(&c)
+}
+
+fn color_marker_escape(c: char) -> Option<&'static str> {
+    Some(match c {
+        RESET_MARKER => "\x1b[0m",
+        NUMBER_MARKER => "\x1b[33m",
+        CHAR_MARKER => "\x1b[36m",
+        FUNCTION_MARKER => "\x1b[35m",
+        _ => return None,
+    })
+}
+
+/// Wrap each row of a grid in color markers if color is enabled
+fn colorize(cell_color: CellColor, grid: &mut Grid, config: &GridFmtConfig) {
+    if !config.color {
+        return;
+    }
+    for row in grid {
+        row.insert(0, cell_color.marker());
+        row.push(RESET_MARKER);
+    }
+}
+
+/// Get the number of visible characters in a row
+fn visible_width(row: &[char], color: bool) -> usize {
+    row.iter().filter(|&&c| !is_color_marker(c, color)).count()
+}
+
+/// Truncate a row to a visible width
+fn truncate_visible(row: &mut Vec<char>, width: usize, color: bool) {
+    if visible_width(row, color) <= width {
+        return;
+    }
+    let mut seen = 0;
+    let end = (row.iter().position(|&c| {
+        if !is_color_marker(c, color) {
+            seen += 1;
+        }
+        seen > width
+    }))
+    .unwrap_or(row.len());
+    let colored = row[..end].iter().any(|&c| is_color_marker(c, color));
+    row.truncate(end);
+    if colored {
+        row.push(RESET_MARKER);
+    }
+}
+
 pub trait GridFmt {
-    fn fmt_grid(&self, boxed: bool) -> Grid;
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid;
     fn grid_string(&self) -> String {
-        let mut s: String = self
-            .fmt_grid(false)
-            .into_iter()
-            .flat_map(|v| v.into_iter().chain(once('\n')))
-            .collect();
+        let config = GridFmtConfig::current();
+        let mut s = String::new();
+        for row in self.fmt_grid(false, &config) {
+            for c in row {
+                match color_marker_escape(c) {
+                    Some(escape) if config.color => s.push_str(escape),
+                    _ => s.push(c),
+                }
+            }
+            s.push('\n');
+        }
         s.pop();
         s
     }
@@ -39,15 +213,27 @@ fn boxed_scalar(boxed: bool) -> impl Iterator<Item = char> {
 }
 
 impl GridFmt for u8 {
-    fn fmt_grid(&self, boxed: bool) -> Grid {
-        vec![boxed_scalar(boxed)
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
+        let mut grid = vec![boxed_scalar(boxed)
             .chain(self.to_string().chars())
-            .collect()]
+            .collect()];
+        colorize(CellColor::Number, &mut grid, config);
+        grid
+    }
+}
+
+impl GridFmt for i64 {
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
+        let minus = if *self < 0 { "¯" } else { "" };
+        let s = format!("{minus}{}", self.unsigned_abs());
+        let mut grid = vec![boxed_scalar(boxed).chain(s.chars()).collect()];
+        colorize(CellColor::Number, &mut grid, config);
+        grid
     }
 }
 
 impl GridFmt for f64 {
-    fn fmt_grid(&self, boxed: bool) -> Grid {
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
         let positive = self.abs();
         let minus = if *self < -0.0 { "¯" } else { "" };
         let s = if (positive - PI).abs() < f64::EPSILON {
@@ -59,9 +245,47 @@ impl GridFmt for f64 {
         } else if positive == INFINITY {
             format!("{minus}∞")
         } else {
-            format!("{minus}{positive}")
+            let rounded = if let Some(precision) = config.float_precision {
+                round_to_significant(positive, precision)
+            } else {
+                positive
+            };
+            match config.scientific_threshold {
+                Some((low, high)) if rounded != 0.0 && (rounded < low || rounded >= high) => {
+                    format!("{minus}{}", format!("{rounded:e}").replace('-', "¯"))
+                }
+                _ => format!("{minus}{rounded}"),
+            }
+        };
+        let mut grid = vec![boxed_scalar(boxed).chain(s.chars()).collect()];
+        colorize(CellColor::Number, &mut grid, config);
+        grid
+    }
+}
+
+fn round_to_significant(n: f64, digits: usize) -> f64 {
+    if n == 0.0 || !n.is_finite() {
+        return n;
+    }
+    // Round-tripping through scientific notation rounds to the given number of significant digits
+    format!("{:.*e}", digits.max(1) - 1, n).parse().unwrap_or(n)
+}
+
+impl GridFmt for Complex64 {
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
+        // The parts are formatted without color so that the whole number is colored once
+        let plain = GridFmtConfig {
+            color: false,
+            ..config.clone()
         };
-        vec![boxed_scalar(boxed).chain(s.chars()).collect()]
+        let mut grid = self.re.fmt_grid(boxed, &plain);
+        let sign = if self.im < -0.0 { '-' } else { '+' };
+        let im = self.im.abs().fmt_grid(false, &plain);
+        grid[0].push(sign);
+        grid[0].extend(im.into_iter().flatten());
+        grid[0].push('i');
+        colorize(CellColor::Number, &mut grid, config);
+        grid
     }
 }
 
@@ -83,32 +307,38 @@ pub fn format_char_inner(c: char) -> String {
 }
 
 impl GridFmt for char {
-    fn fmt_grid(&self, boxed: bool) -> Grid {
-        vec![once(if boxed {
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
+        let mut grid = vec![once(if boxed {
             Primitive::Box.glyph().unwrap()
         } else {
             '@'
         })
         .chain(format_char_inner(*self).chars())
-        .collect()]
+        .collect()];
+        colorize(CellColor::Char, &mut grid, config);
+        grid
     }
 }
 
 impl GridFmt for Arc<Function> {
-    fn fmt_grid(&self, boxed: bool) -> Grid {
-        Function::fmt_grid(self, boxed)
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
+        Function::fmt_grid(self, boxed, config)
     }
 }
 
 impl GridFmt for Function {
-    fn fmt_grid(&self, boxed: bool) -> Grid {
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
         if let Some((prim, _)) = self.as_primitive() {
-            return vec![prim.to_string().chars().collect()];
+            let mut grid = vec![prim.to_string().chars().collect()];
+            colorize(CellColor::Function, &mut grid, config);
+            return grid;
         }
         if let Some(value) = self.as_boxed() {
-            let mut grid = value.fmt_grid(true);
+            let mut grid = value.fmt_grid(true, config);
             if grid.len() == 1 && boxed {
-                grid[0].insert(0, '□');
+                let mut box_glyph = vec![vec!['□']];
+                colorize(CellColor::Function, &mut box_glyph, config);
+                grid[0].splice(0..0, box_glyph.remove(0));
             }
             return grid;
         }
@@ -126,6 +356,7 @@ impl GridFmt for Function {
                 grid[0].insert(0, '□');
             }
             grid[0].push(')');
+            colorize(CellColor::Function, &mut grid, config);
             return grid;
         }
         let row_count = grid.len();
@@ -140,25 +371,28 @@ impl GridFmt for Function {
             row.insert(0, start);
             row.push(end);
         }
+        colorize(CellColor::Function, &mut grid, config);
         grid
     }
 }
 
 impl GridFmt for Value {
-    fn fmt_grid(&self, boxed: bool) -> Grid {
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
         match self {
-            Value::Num(array) => array.fmt_grid(boxed),
-            Value::Byte(array) => array.fmt_grid(boxed),
-            Value::Char(array) => array.fmt_grid(boxed),
-            Value::Func(array) => array.fmt_grid(boxed),
+            Value::Num(array) => array.fmt_grid(boxed, config),
+            Value::Int(array) => array.fmt_grid(boxed, config),
+            Value::Byte(array) => array.fmt_grid(boxed, config),
+            Value::Complex(array) => array.fmt_grid(boxed, config),
+            Value::Char(array) => array.fmt_grid(boxed, config),
+            Value::Func(array) => array.fmt_grid(boxed, config),
         }
     }
 }
 
 impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
-    fn fmt_grid(&self, boxed: bool) -> Grid {
+    fn fmt_grid(&self, boxed: bool, config: &GridFmtConfig) -> Grid {
         if self.shape.is_empty() {
-            return self.data[0].fmt_grid(boxed);
+            return self.data[0].fmt_grid(boxed, config);
         }
         let stringy = type_name::<T>() == type_name::<char>();
         if *self.shape == [0] {
@@ -179,24 +413,96 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
         let mut just_dims = false;
         if self.shape.len() > 1 {
             let columns = *self.shape.last().unwrap();
-            if let Some((w, _)) = term_size::dimensions() {
-                if columns > w / 2 - 1 {
-                    just_dims = true;
-                }
-            } else if columns > 40 {
-                just_dims = true;
-            } else {
-                let rows = self.shape.iter().rev().skip(1).product::<usize>();
-                if rows > 100 {
-                    just_dims = true;
-                }
-            }
+            let term_width = term_size::dimensions().map(|(w, _)| w);
+            let rows = self.shape.iter().rev().skip(1).product::<usize>();
+            let too_wide =
+                config.max_cols.is_none() && columns > term_width.map_or(40, |w| w / 2 - 1);
+            let too_tall = config.max_rows.is_none() && term_width.is_none() && rows > 100;
+            just_dims = too_wide || too_tall;
         }
 
         let mut grid: Grid = Grid::new();
 
         if !just_dims {
-            fmt_array(&self.shape, &self.data, stringy, boxed, &mut metagrid);
+            // Truncate columns
+            let mut shape = self.shape.clone();
+            let columns = *shape.last().unwrap();
+            let col_split = config
+                .max_cols
+                .and_then(|max| truncation_split(columns, max));
+            let data: Cow<[T]> = if let Some((head, tail)) = col_split {
+                *shape.last_mut().unwrap() = head + tail;
+                Cow::Owned(
+                    (self.data.chunks_exact(columns))
+                        .flat_map(|row| row[..head].iter().chain(&row[columns - tail..]))
+                        .cloned()
+                        .collect(),
+                )
+            } else {
+                Cow::Borrowed(&self.data)
+            };
+            let col_ellipsis = col_split.map(|(head, _)| head);
+            // Truncate rows
+            let row_split = (shape.len() > 1)
+                .then(|| {
+                    config
+                        .max_rows
+                        .and_then(|max| truncation_split(shape[0], max))
+                })
+                .flatten();
+            if let Some((head, tail)) = row_split {
+                let row_size = data.len() / shape[0];
+                shape[0] = head;
+                fmt_array(
+                    &shape,
+                    &data[..head * row_size],
+                    stringy,
+                    boxed,
+                    col_ellipsis,
+                    config,
+                    &mut metagrid,
+                );
+                let head_height = metagrid.len();
+                shape[0] = tail;
+                fmt_array(
+                    &shape,
+                    &data[data.len() - tail * row_size..],
+                    stringy,
+                    boxed,
+                    col_ellipsis,
+                    config,
+                    &mut metagrid,
+                );
+                let width = metagrid.iter().map(|row| row.len()).max().unwrap();
+                let ellipsis_row = (0..width)
+                    .map(|i| {
+                        vec![if i == 0 {
+                            vec!['…']
+                        } else {
+                            vec![' ', '…']
+                        }]
+                    })
+                    .collect();
+                // Separate the ellipsis from higher-rank cells
+                let separators = shape.len().saturating_sub(2);
+                let blank_row = vec![vec![vec![' ']]; width];
+                metagrid.splice(
+                    head_height..head_height,
+                    repeat_n(blank_row.clone(), separators)
+                        .chain(once(ellipsis_row))
+                        .chain(repeat_n(blank_row, separators)),
+                );
+            } else {
+                fmt_array(
+                    &shape,
+                    &data,
+                    stringy,
+                    boxed,
+                    col_ellipsis,
+                    config,
+                    &mut metagrid,
+                );
+            }
             // Determine max row heights and column widths
             let metagrid_width = metagrid.iter().map(|row| row.len()).max().unwrap();
             let metagrid_height = metagrid.len();
@@ -210,10 +516,28 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
                     .unwrap_or(1);
                 row_heights[row] = max_row_height;
             }
+            // Numbers are followed by padding so that they line up with
+            // the `e` of numbers in scientific notation in the same column
+            let mut exp_pads = vec![vec![0; metagrid_width]; metagrid_height];
+            if config.scientific_threshold.is_some() && type_name::<T>() == type_name::<f64>() {
+                for col in 0..metagrid_width {
+                    let exp_widths: Vec<usize> = (metagrid.iter())
+                        .map(|row| exponent_width(&row[col], config.color))
+                        .collect();
+                    let max_exp_width = exp_widths.iter().copied().max().unwrap_or(0);
+                    for (row, exp_width) in exp_widths.into_iter().enumerate() {
+                        exp_pads[row][col] = max_exp_width - exp_width;
+                    }
+                }
+            }
             for col in 0..metagrid_width {
-                let max_col_width = metagrid
-                    .iter_mut()
-                    .map(|row| row[col].iter().map(|cell| cell.len()).max().unwrap())
+                let max_col_width = (metagrid.iter().zip(&exp_pads))
+                    .map(|(row, pads)| {
+                        (row[col].iter())
+                            .map(|cell| visible_width(cell, config.color) + pads[col])
+                            .max()
+                            .unwrap()
+                    })
                     .max()
                     .unwrap();
                 column_widths[col] = max_col_width;
@@ -222,10 +546,13 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
             for row in 0..metagrid_height {
                 let row_height = row_heights[row];
                 let mut subrows = vec![vec![]; row_height];
-                for (col_width, cell) in column_widths.iter().zip(&mut metagrid[row]) {
-                    pad_grid_center(*col_width, row_height, true, cell);
+                for ((col_width, cell), &exp_pad) in
+                    (column_widths.iter().zip(&mut metagrid[row])).zip(&exp_pads[row])
+                {
+                    pad_grid_center(col_width - exp_pad, row_height, true, config.color, cell);
                     for (subrow, cell_row) in subrows.iter_mut().zip(take(cell)) {
                         subrow.extend(cell_row);
+                        subrow.extend(repeat_n(' ', exp_pad));
                     }
                 }
                 grid.extend(subrows);
@@ -241,12 +568,13 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
                 }
             } else {
                 // Add corners to non-vectors
-                let width = grid[0].len();
+                let width = visible_width(&grid[0], config.color);
                 let height = grid.len();
                 pad_grid_center(
                     width + 4,
                     (height + 2).max(self.rank() + 1),
                     false,
+                    config.color,
                     &mut grid,
                 );
                 grid[0][0] = if boxed { '╓' } else { '╭' };
@@ -258,13 +586,18 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
                 // Handle really big grid
                 if let Some((w, _)) = term_size::dimensions() {
                     for row in grid.iter_mut() {
-                        if row.len() > w {
-                            let diff = row.len() - w;
-                            row.truncate(w);
-                            if !(row[w - 1].is_whitespace() && diff == 1)
-                                && (2..4).any(|i| !row[w - i].is_whitespace())
+                        let width = visible_width(row, config.color);
+                        if width > w {
+                            let diff = width - w;
+                            truncate_visible(row, w, config.color);
+                            let visible: Vec<usize> = (row.iter().enumerate())
+                                .filter(|(_, &c)| !is_color_marker(c, config.color))
+                                .map(|(i, _)| i)
+                                .collect();
+                            if !(row[visible[w - 1]].is_whitespace() && diff == 1)
+                                && (2..4).any(|i| !row[visible[w - i]].is_whitespace())
                             {
-                                row[w - 1] = '…';
+                                row[visible[w - 1]] = '…';
                             }
                         }
                     }
@@ -289,11 +622,31 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
     }
 }
 
+/// Get the visible width of the exponent of a number in scientific notation, including the `e`
+fn exponent_width(cell: &Grid, color: bool) -> usize {
+    let [row] = cell.as_slice() else {
+        return 0;
+    };
+    (row.iter().rev())
+        .filter(|&&c| !is_color_marker(c, color))
+        .position(|&c| c == 'e')
+        .map_or(0, |i| i + 1)
+}
+
+/// Get the number of leading and trailing elements to keep when truncating an axis
+fn truncation_split(len: usize, max: usize) -> Option<(usize, usize)> {
+    let head = max.div_ceil(2).max(1);
+    let tail = (max / 2).max(1);
+    (len > head + tail).then_some((head, tail))
+}
+
 fn fmt_array<T: GridFmt + ArrayValue>(
     shape: &[usize],
     data: &[T],
     stringy: bool,
     boxed: bool,
+    col_ellipsis: Option<usize>,
+    config: &GridFmtConfig,
     metagrid: &mut Metagrid,
 ) {
     if data.is_empty() {
@@ -302,15 +655,21 @@ fn fmt_array<T: GridFmt + ArrayValue>(
     }
     let rank = shape.len();
     if rank == 0 {
-        metagrid.push(vec![data[0].fmt_grid(false)]);
+        metagrid.push(vec![data[0].fmt_grid(false, config)]);
         return;
     }
     if rank == 1 {
         let mut row = Vec::with_capacity(shape[0]);
         if stringy {
-            let mut s = String::new();
-            s.extend(data.iter().map(|c| c.to_string()));
-            let mut s: String = s.chars().map(format_char_inner).collect();
+            let fmt_chars = |data: &[T]| -> String {
+                let s: String = data.iter().map(|c| c.to_string()).collect();
+                s.chars().map(format_char_inner).collect()
+            };
+            let mut s = if let Some(i) = col_ellipsis {
+                format!("{}…{}", fmt_chars(&data[..i]), fmt_chars(&data[i..]))
+            } else {
+                fmt_chars(data)
+            };
             if boxed {
                 s.insert(0, '⌜');
                 s.push('⌟');
@@ -318,15 +677,21 @@ fn fmt_array<T: GridFmt + ArrayValue>(
                 s.insert(0, '"');
                 s.push('"');
             }
-            row.push(vec![s.chars().collect()]);
+            let mut grid = vec![s.chars().collect()];
+            colorize(CellColor::Char, &mut grid, config);
+            row.push(grid);
         } else {
             for (i, val) in data.iter().enumerate() {
-                let mut grid = val.fmt_grid(false);
+                let mut grid = val.fmt_grid(false, config);
                 if i > 0 {
-                    pad_grid_min(grid[0].len() + 1, grid.len(), &mut grid)
+                    let width = visible_width(&grid[0], config.color) + 1;
+                    pad_grid_min(width, grid.len(), config.color, &mut grid)
                 }
                 row.push(grid);
             }
+            if let Some(i) = col_ellipsis {
+                row.insert(i, vec![vec![' ', '…']]);
+            }
         }
         metagrid.push(row);
         return;
@@ -344,11 +709,11 @@ fn fmt_array<T: GridFmt + ArrayValue>(
                 metagrid.push(vec![vec![vec![' ']]; metagrid.last().unwrap().len()]);
             }
         }
-        fmt_array(shape, cell, stringy, false, metagrid);
+        fmt_array(shape, cell, stringy, false, col_ellipsis, config, metagrid);
     }
 }
 
-fn pad_grid_center(width: usize, height: usize, align_numbers: bool, grid: &mut Grid) {
+fn pad_grid_center(width: usize, height: usize, align_numbers: bool, color: bool, grid: &mut Grid) {
     grid.truncate(height);
     if grid.len() < height {
         let diff = height - grid.len();
@@ -362,10 +727,14 @@ fn pad_grid_center(width: usize, height: usize, align_numbers: bool, grid: &mut
         }
     }
     for row in grid.iter_mut() {
-        row.truncate(width);
-        if row.len() < width {
-            let diff = width - row.len();
-            let post_pad = if align_numbers && row.last().map_or(false, char::is_ascii_digit) {
+        truncate_visible(row, width, color);
+        let row_width = visible_width(row, color);
+        if row_width < width {
+            let diff = width - row_width;
+            let last_visible = row.iter().rev().find(|&&c| !is_color_marker(c, color));
+            let post_pad = if align_numbers
+                && last_visible.map_or(false, |&c| c.is_ascii_digit() || c == '…')
+            {
                 0
             } else {
                 diff / 2
@@ -381,15 +750,177 @@ fn pad_grid_center(width: usize, height: usize, align_numbers: bool, grid: &mut
     }
 }
 
-fn pad_grid_min(width: usize, height: usize, grid: &mut Grid) {
+fn pad_grid_min(width: usize, height: usize, color: bool, grid: &mut Grid) {
     grid.truncate(height);
     while grid.len() < height {
         grid.insert(0, vec![' '; width]);
     }
     for row in grid.iter_mut() {
-        row.truncate(width);
-        while row.len() < width {
+        truncate_visible(row, width, color);
+        for _ in visible_width(row, color)..width {
             row.insert(0, ' ');
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn float_precision() {
+        let config = GridFmtConfig::default().with_float_precision(Some(3));
+        let show = |value: Value| config.clone().scoped(|| value.show());
+        assert_eq!(show(3.14159265.into()), "3.14");
+        assert_eq!(show(5.0.into()), "5");
+        assert_eq!(show(1234.5678.into()), "1230");
+        assert_eq!(show((-0.000123456).into()), "¯0.000123");
+        assert_eq!(
+            show([2.71828, -31.4159, 0.001].into_iter().collect()),
+            "[2.72 ¯31.4 0.001]"
+        );
+        assert_eq!(Value::from(3.14159265).show(), "3.14159265");
+    }
+    #[test]
+    fn scientific_threshold() {
+        let config = GridFmtConfig::default().with_scientific_threshold(Some((1e-4, 1e6)));
+        let show = |value: Value| config.clone().scoped(|| value.show());
+        assert_eq!(show(1.2e6.into()), "1.2e6");
+        assert_eq!(show((-2.5e-7).into()), "¯2.5e¯7");
+        assert_eq!(show(0.0.into()), "0");
+        assert_eq!(show(1234.0.into()), "1234");
+        let column = Value::from(Array::<f64>::new(
+            tinyvec::tiny_vec![2, 1],
+            [1e-9, 1234.0]
+                .into_iter()
+                .collect::<crate::cowslice::CowSlice<_>>(),
+        ));
+        assert_eq!(
+            show(column.clone()),
+            "╭─         \n╷    1e¯9  \n  1234     \n          ╯"
+        );
+        // Exponents line up, and other numbers line up with the `e`
+        let mixed = Value::from(Array::<f64>::new(
+            tinyvec::tiny_vec![3, 1],
+            [1.5e6, 2e-12, 3.0]
+                .into_iter()
+                .collect::<crate::cowslice::CowSlice<_>>(),
+        ));
+        assert_eq!(
+            show(mixed),
+            [
+                "╭─         ",
+                "╷ 1.5e6    ",
+                "    2e¯12  ",
+                "    3      ",
+                "          ╯",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            column.show(),
+            "╭─             \n╷ 0.000000001  \n         1234  \n              ╯"
+        );
+        let config = config.with_float_precision(Some(2));
+        assert_eq!(config.scoped(|| Value::from(123456789.0).show()), "1.2e8");
+    }
+    #[test]
+    fn truncation() {
+        let rows = Value::from(Array::<f64>::new(
+            tinyvec::tiny_vec![1000, 2],
+            (0..2000)
+                .map(|i| i as f64)
+                .collect::<crate::cowslice::CowSlice<_>>(),
+        ));
+        let config = GridFmtConfig::default().with_max_rows(Some(6));
+        assert_eq!(
+            config.scoped(|| rows.show()),
+            [
+                "╭─           ",
+                "╷    0    1  ",
+                "     2    3  ",
+                "     4    5  ",
+                "     …    …  ",
+                "  1994 1995  ",
+                "  1996 1997  ",
+                "  1998 1999  ",
+                "            ╯",
+            ]
+            .join("\n")
+        );
+        let row: Value = (0..100).map(|i| i as f64).collect();
+        let config = GridFmtConfig::default().with_max_cols(Some(4));
+        assert_eq!(config.clone().scoped(|| row.show()), "[0 1 … 98 99]");
+        let string = Value::from("abcdefghij");
+        assert_eq!(config.scoped(|| string.show()), "\"ab…ij\"");
+        assert!(!rows.show().contains('…'));
+    }
+    #[test]
+    fn color() {
+        let strip = |s: String| {
+            let mut stripped = String::new();
+            let mut chars = s.chars();
+            while let Some(c) = chars.next() {
+                if c == '\x1b' {
+                    chars.by_ref().find(|&c| c == 'm');
+                } else {
+                    stripped.push(c);
+                }
+            }
+            stripped
+        };
+        let config = GridFmtConfig::default().with_color(true);
+        let values: Vec<Value> = vec![
+            [1.5, -20.0, 300.25, 4.0].into_iter().collect(),
+            Value::from(Array::<f64>::new(
+                tinyvec::tiny_vec![2, 2],
+                [1.0, 22.0, -333.0, 4.5]
+                    .into_iter()
+                    .collect::<crate::cowslice::CowSlice<_>>(),
+            )),
+            Value::from("hello"),
+            Value::from('x'),
+            Value::from(Array::<char>::new(
+                tinyvec::tiny_vec![2, 3],
+                "abcdef".chars().collect::<crate::cowslice::CowSlice<_>>(),
+            )),
+            Value::from(Function::new(
+                crate::function::FunctionId::Primitive(Primitive::Add),
+                vec![crate::function::Instr::Prim(Primitive::Add, 0)],
+                crate::function::Signature::new(2, 1),
+            )),
+        ];
+        let mut env = crate::Uiua::with_native_sys();
+        env.load_str(
+            r#"{1 "ab" [1_2 3_4] (+)}
+            ⊞+.⇡3
+            ∵□ ["ab" "cd"]"#,
+        )
+        .unwrap();
+        for value in values.into_iter().chain(env.take_stack()) {
+            let plain = value.show();
+            let colored = config.clone().scoped(|| value.show());
+            assert!(colored.contains('\x1b'), "{plain} is not colored");
+            assert_ne!(plain, colored);
+            assert_eq!(plain, strip(colored));
+        }
+        assert!(!Value::from(5.0).show().contains('\x1b'));
+    }
+    #[test]
+    fn color_markers_without_color() {
+        let row: Vec<char> = format!("{NUMBER_MARKER}ab{RESET_MARKER}").chars().collect();
+        assert_eq!(visible_width(&row, false), 4);
+        assert_eq!(visible_width(&row, true), 2);
+        let mut truncated = row.clone();
+        truncate_visible(&mut truncated, 2, false);
+        assert_eq!(truncated, [NUMBER_MARKER, 'a']);
+    }
+    #[test]
+    fn scoped_restores_on_panic() {
+        let config = GridFmtConfig::default().with_max_cols(Some(4));
+        let res = std::panic::catch_unwind(|| config.scoped(|| panic!("formatting failed")));
+        assert!(res.is_err());
+        assert_eq!(GridFmtConfig::current(), GridFmtConfig::default());
+    }
+}
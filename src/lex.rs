@@ -26,12 +26,24 @@ pub fn lex(input: &str, file: Option<&Path>) -> (Vec<Sp<Token>>, Vec<Sp<LexError
     .run()
 }
 
+/// Lex some code into tokens for tooling
+///
+/// Unlike [`lex`], spaces are omitted. Lexing does not stop at invalid input,
+/// so the tokens are returned along with every error that was encountered.
+/// Use [`Token::kind`] to categorize the tokens.
+pub fn tokenize(input: &str) -> (Vec<Sp<Token>>, Vec<Sp<LexError>>) {
+    let (mut tokens, errors) = lex(input, None);
+    tokens.retain(|token| token.value != Token::Spaces);
+    (tokens, errors)
+}
+
 #[derive(Debug, Clone)]
 pub enum LexError {
     UnexpectedChar(char),
     ExpectedCharacter(Option<char>),
     InvalidEscape(char),
     ExpectedNumber,
+    ExpectedDigits(u32),
 }
 
 impl fmt::Display for LexError {
@@ -42,6 +54,9 @@ impl fmt::Display for LexError {
             LexError::ExpectedCharacter(None) => write!(f, "Expected character"),
             LexError::InvalidEscape(c) => write!(f, "Invalid escape character {c:?}"),
             LexError::ExpectedNumber => write!(f, "Expected number"),
+            LexError::ExpectedDigits(16) => write!(f, "Expected hexadecimal digits after `0x`"),
+            LexError::ExpectedDigits(2) => write!(f, "Expected binary digits after `0b`"),
+            LexError::ExpectedDigits(radix) => write!(f, "Expected base {radix} digits"),
         }
     }
 }
@@ -322,6 +337,39 @@ impl Token {
             _ => None,
         }
     }
+    /// Get the category of the token
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Comment => TokenKind::Comment,
+            Token::Ident => TokenKind::Ident,
+            Token::Number => TokenKind::Number,
+            Token::Char(_) | Token::Str(_) | Token::FormatStr(_) | Token::MultilineString(_) => {
+                TokenKind::String
+            }
+            Token::Glyph(prim) => TokenKind::Primitive(*prim),
+            Token::Simple(ascii) => match Primitive::from_simple(*ascii) {
+                Some(prim) => TokenKind::Primitive(prim),
+                None => TokenKind::Punctuation,
+            },
+            Token::LeftArrow => TokenKind::Punctuation,
+            Token::Newline | Token::Spaces => TokenKind::Whitespace,
+        }
+    }
+}
+
+/// The category of a [`Token`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Comment,
+    Ident,
+    Number,
+    /// A character, string, format string, or multiline string literal
+    String,
+    /// A primitive written as a glyph or ASCII symbol
+    Primitive(Primitive),
+    /// Brackets, separators, and binding arrows
+    Punctuation,
+    Whitespace,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -347,32 +395,39 @@ pub enum AsciiToken {
     TripleTilde,
 }
 
-impl fmt::Display for AsciiToken {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl AsciiToken {
+    /// Get the text of the token
+    pub fn as_str(&self) -> &'static str {
         match self {
-            AsciiToken::OpenParen => write!(f, "("),
-            AsciiToken::CloseParen => write!(f, ")"),
-            AsciiToken::OpenCurly => write!(f, "{{"),
-            AsciiToken::CloseCurly => write!(f, "}}"),
-            AsciiToken::OpenBracket => write!(f, "["),
-            AsciiToken::CloseBracket => write!(f, "]"),
-            AsciiToken::Underscore => write!(f, "_"),
-            AsciiToken::Bar => write!(f, "|"),
-            AsciiToken::Colon => write!(f, ":"),
-            AsciiToken::Star => write!(f, "*"),
-            AsciiToken::Percent => write!(f, "%"),
-            AsciiToken::Caret => write!(f, "^"),
-            AsciiToken::Equal => write!(f, "="),
-            AsciiToken::BangEqual => write!(f, "!="),
-            AsciiToken::LessEqual => write!(f, "<="),
-            AsciiToken::GreaterEqual => write!(f, ">="),
-            AsciiToken::Backtick => write!(f, "`"),
-            AsciiToken::TripleMinus => write!(f, "---"),
-            AsciiToken::TripleTilde => write!(f, "~~~"),
+            AsciiToken::OpenParen => "(",
+            AsciiToken::CloseParen => ")",
+            AsciiToken::OpenCurly => "{",
+            AsciiToken::CloseCurly => "}",
+            AsciiToken::OpenBracket => "[",
+            AsciiToken::CloseBracket => "]",
+            AsciiToken::Underscore => "_",
+            AsciiToken::Bar => "|",
+            AsciiToken::Colon => ":",
+            AsciiToken::Star => "*",
+            AsciiToken::Percent => "%",
+            AsciiToken::Caret => "^",
+            AsciiToken::Equal => "=",
+            AsciiToken::BangEqual => "!=",
+            AsciiToken::LessEqual => "<=",
+            AsciiToken::GreaterEqual => ">=",
+            AsciiToken::Backtick => "`",
+            AsciiToken::TripleMinus => "---",
+            AsciiToken::TripleTilde => "~~~",
         }
     }
 }
 
+impl fmt::Display for AsciiToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl From<AsciiToken> for Token {
     fn from(s: AsciiToken) -> Self {
         Self::Simple(s)
@@ -488,14 +543,14 @@ impl Lexer {
                 '|' => self.end(Bar, start),
                 ':' => self.end(Colon, start),
                 '`' => {
-                    if self.number('-') {
+                    if self.number(start, '-') {
                         self.end(Number, start)
                     } else {
                         self.end(Backtick, start)
                     }
                 }
-                '¯' if self.peek_char().filter(char::is_ascii_digit).is_some() => {
-                    self.number('-');
+                '¯' if (self.peek_char()).is_some_and(|c| c.is_ascii_digit() || c == '∞') => {
+                    self.number(start, '-');
                     self.end(Number, start)
                 }
                 '*' => self.end(Star, start),
@@ -538,12 +593,18 @@ impl Lexer {
                 // Strings
                 '"' | '$' => {
                     let format = c == '$';
-                    if format && self.next_char_exact(' ') {
+                    let mut raw = format && self.next_chars_exact("$ ");
+                    if raw || format && self.next_char_exact(' ') {
                         // Multiline strings
                         let mut start = start;
                         loop {
-                            let inner = self.parse_string_contents(start, None);
-                            let string = parse_format_fragments(&inner);
+                            let string = if raw {
+                                // Raw lines are a single fragment with no escapes
+                                vec![self.raw_string_contents()]
+                            } else {
+                                let inner = self.parse_string_contents(start, None);
+                                parse_format_fragments(&inner)
+                            };
                             self.end(MultilineString(string), start);
                             let checkpoint = self.loc;
                             while self.next_char_exact('\r') {}
@@ -554,6 +615,11 @@ impl Lexer {
                                 {}
                                 start = self.loc;
                                 if self.next_chars_exact("$ ") {
+                                    raw = false;
+                                    continue;
+                                }
+                                if self.next_chars_exact("$$ ") {
+                                    raw = true;
                                     continue;
                                 }
                             }
@@ -591,6 +657,18 @@ impl Lexer {
                     while let Some(c) = self.next_char_if(is_ident_char) {
                         ident.push(c);
                     }
+                    // `log2` and `log10` end in digits, but only when the digits
+                    // are not the start of a longer number like `2.5` or `2_3`
+                    if ident == "log" {
+                        let rest = &self.input_chars[self.loc.char_pos..];
+                        let digits: String =
+                            rest.iter().take_while(|c| c.is_ascii_digit()).collect();
+                        let number_continues = matches!(rest.get(digits.len()), Some('_' | '.'));
+                        if ["2", "10"].contains(&digits.as_str()) && !number_continues {
+                            self.next_chars_exact(&digits);
+                            ident.push_str(&digits);
+                        }
+                    }
                     // Try to parse as primitives
                     let lowercase_end = ident
                         .char_indices()
@@ -623,7 +701,7 @@ impl Lexer {
                 }
                 // Numbers
                 c if c.is_ascii_digit() => {
-                    self.number(c);
+                    self.number(start, c);
                     self.end(Number, start)
                 }
                 // Newlines
@@ -645,12 +723,10 @@ impl Lexer {
                 c => {
                     if let Some(prim) = Primitive::from_glyph(c) {
                         self.end(Glyph(prim), start)
-                    } else if !self
-                        .errors
-                        .iter()
-                        .last()
-                        .map_or(true, |e| matches!(e.value, LexError::UnexpectedChar(_)))
-                    {
+                    } else if !(self.errors.last()).is_some_and(|e| {
+                        matches!(e.value, LexError::UnexpectedChar(_)) && e.span.end == start
+                    }) {
+                        // Runs of unexpected characters are only reported once
                         self.errors
                             .push(self.end_span(start).sp(LexError::UnexpectedChar(c)));
                     }
@@ -659,7 +735,36 @@ impl Lexer {
         }
         (self.tokens, self.errors)
     }
-    fn number(&mut self, init: char) -> bool {
+    fn number(&mut self, start: Loc, init: char) -> bool {
+        // Negative infinity
+        if !init.is_ascii_digit() && self.next_char_exact('∞') {
+            return true;
+        }
+        // Hexadecimal and binary integers
+        let before_prefix = self.loc;
+        if init == '0' || (!init.is_ascii_digit() && self.next_char_exact('0')) {
+            if let Some(prefix) = self.next_char_if(|c| "xXbB".contains(c)) {
+                let radix = if prefix.eq_ignore_ascii_case(&'x') {
+                    16
+                } else {
+                    2
+                };
+                let mut got_digit = false;
+                while self.next_char_if(|c| c.is_digit(radix)).is_some() {
+                    got_digit = true;
+                }
+                if got_digit {
+                    return true;
+                }
+                // A prefix followed by a name, like `0box`, is `0` followed by that name
+                if !self.peek_char().is_some_and(is_ident_char) {
+                    let span = self.end_span(start);
+                    self.errors.push(span.sp(LexError::ExpectedDigits(radix)));
+                    return true;
+                }
+            }
+        }
+        self.loc = before_prefix;
         // Whole part
         let mut got_digit = false;
         while self.next_char_if(|c| c.is_ascii_digit()).is_some() {
@@ -740,6 +845,13 @@ impl Lexer {
             c
         }))
     }
+    fn raw_string_contents(&mut self) -> String {
+        let mut string = String::new();
+        while let Some(c) = self.next_char_if(|c| !"\r\n".contains(c)) {
+            string.push(c);
+        }
+        string
+    }
     fn parse_string_contents(&mut self, start: Loc, escape_char: Option<char>) -> String {
         let mut string = String::new();
         let mut escaped = false;
@@ -781,3 +893,44 @@ pub fn is_ident_char(c: char) -> bool {
 pub fn is_custom_glyph(c: char) -> bool {
     c as u32 > 127 && !is_ident_char(c) && Primitive::from_glyph(c).is_none()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_spans() {
+        let (tokens, errors) = tokenize("+1 # hi");
+        assert!(errors.is_empty(), "{errors:?}");
+        let kinds: Vec<_> = tokens.iter().map(|token| token.value.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Primitive(Primitive::Add),
+                TokenKind::Number,
+                TokenKind::Comment
+            ]
+        );
+        let spans: Vec<_> = (tokens.iter())
+            .map(|token| (token.span.start.byte_pos, token.span.end.byte_pos))
+            .collect();
+        assert_eq!(spans, [(0, 1), (1, 2), (3, 7)]);
+        assert_eq!(tokens[2].span.as_str(), "# hi");
+        assert_eq!(
+            (tokens[2].span.start.line, tokens[2].span.start.col),
+            (1, 4)
+        );
+
+        let (tokens, errors) = tokenize("1 \u{7} 2\nx ← \"a");
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(matches!(errors[0].value, LexError::UnexpectedChar('\u{7}')));
+        let kinds: Vec<_> = tokens.iter().map(|token| token.value.kind()).collect();
+        assert!(kinds.contains(&TokenKind::Ident));
+        assert_eq!(kinds.iter().filter(|&&k| k == TokenKind::Number).count(), 2);
+        let x = tokens
+            .iter()
+            .find(|token| token.value == Token::Ident)
+            .unwrap();
+        assert_eq!((x.span.start.line, x.span.start.col), (2, 1));
+    }
+}
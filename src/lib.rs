@@ -24,13 +24,15 @@ pub mod primitive;
 #[doc(hidden)]
 pub mod profile;
 pub mod run;
+pub mod sparse;
 mod sys;
+mod sys_memory;
 mod sys_native;
 pub mod value;
 
 use std::sync::Arc;
 
-pub use {error::*, run::Uiua, sys::*, sys_native::*};
+pub use {error::*, grid_fmt::GridFmtConfig, run::Uiua, sys::*, sys_memory::*, sys_native::*};
 
 pub type Ident = Arc<str>;
 
@@ -40,10 +42,17 @@ fn suite() {
         let entry = entry.unwrap();
         let path = entry.path();
         if path.is_file() && path.extension().is_some_and(|s| s == "ua") {
+            if cfg!(not(feature = "regex")) && path.file_stem().is_some_and(|s| s == "regex") {
+                continue;
+            }
             let mut env = Uiua::with_native_sys();
             if let Err(e) = env.load_file(&path) {
                 panic!("Test failed in {}:\n{}", path.display(), e.show(true));
-            } else if let Some(diag) = env.take_diagnostics().into_iter().next() {
+            } else if let Some(diag) = env
+                .take_diagnostics_at_least(DiagnosticKind::Warning)
+                .into_iter()
+                .next()
+            {
                 panic!("Test failed in {}:\n{}", path.display(), diag.show(true));
             }
         }
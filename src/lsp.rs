@@ -1,10 +1,14 @@
-use std::slice;
+use std::{collections::HashMap, slice};
 
 use crate::{
-    ast::{Item, Word},
-    lex::{CodeSpan, Loc, Sp},
+    ast::{Binding, Item, Word},
+    check::instrs_signature,
+    function::Signature,
+    lex::{lex, CodeSpan, Loc, Sp, Token},
     parse::parse,
-    primitive::Primitive,
+    primitive::{PrimDocFragment, Primitive},
+    value::Value,
+    Ident, MemorySys, Uiua,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -97,6 +101,463 @@ fn words_spans(words: &[Sp<Word>]) -> Vec<Sp<SpanKind>> {
     spans
 }
 
+/// The maximum number of steps to run when evaluating bindings for [`hover`]
+const SANDBOX_STEP_LIMIT: u64 = 100_000;
+
+/// Information about the code at some position
+#[derive(Debug, Clone)]
+pub struct HoverInfo {
+    /// The span of the code
+    pub span: CodeSpan,
+    /// The signature of the code, if it is a function with a known signature
+    pub signature: Option<Signature>,
+    /// A markdown description of the code
+    pub description: String,
+}
+
+/// Get information about the code at a 1-indexed line and column
+///
+/// Columns count characters rather than bytes, so every glyph is one column wide.
+///
+/// Primitives show their signature and a short description.
+/// Bindings show their declared signature, or their inferred one if they were not declared with one.
+/// Number literals show their value and type.
+pub fn hover(input: &str, line: usize, col: usize) -> Option<HoverInfo> {
+    let (items, _, _) = parse(input, None);
+    Some(match items_hover_target(&items, line, col)? {
+        HoverTarget::Primitive(prim, span) => {
+            let signature = prim.signature();
+            let mut description = match (prim.glyph(), prim.name()) {
+                (Some(glyph), Some(name)) => format!("`{glyph}` {name}"),
+                (None, Some(name)) => format!("`{name}`"),
+                _ => format!("`{prim}`"),
+            };
+            if let Some(sig) = signature {
+                description.push_str(&format!(" `{sig}`"));
+            } else if let Some(margs) = prim.modifier_args() {
+                let s = if margs == 1 { "" } else { "s" };
+                description.push_str(&format!(" (modifier with {margs} function{s})"));
+            }
+            if let Some(doc) = prim.doc() {
+                description.push_str("\n\n");
+                description.push_str(&prim_doc_markdown(&doc.short));
+            }
+            HoverInfo {
+                span: span.clone(),
+                signature,
+                description,
+            }
+        }
+        HoverTarget::Number(n, span) => {
+            let value = Value::from(n);
+            HoverInfo {
+                span: span.clone(),
+                signature: None,
+                description: format!("`{}`: {}", value.show(), value.type_name()),
+            }
+        }
+        HoverTarget::Binding(name, span) => {
+            let declared = (find_binding(&items, name))
+                .and_then(|binding| binding.signature.as_ref())
+                .map(|sig| sig.value);
+            let signature = declared.or_else(|| {
+                // Compile the code in a sandbox to infer the signature
+                let mut env =
+                    Uiua::with_backend(MemorySys::default()).with_step_limit(SANDBOX_STEP_LIMIT);
+                env.compile_all(input);
+                let value = env.all_bindings_in_scope().remove(name)?;
+                value.as_function().map(|f| f.signature())
+            });
+            let mut description = format!("`{name}`");
+            if let Some(sig) = signature {
+                description.push_str(&format!(" `{sig}`"));
+            }
+            HoverInfo {
+                span: span.clone(),
+                signature,
+                description,
+            }
+        }
+    })
+}
+
+/// Render primitive documentation as markdown
+pub(crate) fn prim_doc_markdown(frags: &[PrimDocFragment]) -> String {
+    let mut md = String::new();
+    for frag in frags {
+        match frag {
+            PrimDocFragment::Text(text) => md.push_str(text),
+            PrimDocFragment::Code(text) => md.push_str(&format!("`{}`", text)),
+            PrimDocFragment::Emphasis(text) => md.push_str(&format!("*{}*", text)),
+            PrimDocFragment::Strong(text) => md.push_str(&format!("**{}**", text)),
+            PrimDocFragment::Link { text, url } => md.push_str(&format!("[{}]({})", text, url)),
+            PrimDocFragment::Primitive { prim, named } => {
+                let name = prim.name().unwrap();
+                md.push_str(&if *named {
+                    if let Some(unicode) = prim.glyph() {
+                        format!("`{unicode} {name}`")
+                    } else {
+                        format!("`{name}`")
+                    }
+                } else if let Some(unicode) = prim.glyph() {
+                    format!("`{unicode}`")
+                } else {
+                    format!("`{name}`")
+                })
+            }
+        }
+    }
+    md
+}
+
+enum HoverTarget<'a> {
+    Primitive(Primitive, &'a CodeSpan),
+    Number(f64, &'a CodeSpan),
+    Binding(&'a Ident, &'a CodeSpan),
+}
+
+/// Check if a span contains a position, excluding the end of the span
+fn span_contains(span: &CodeSpan, line: usize, col: usize) -> bool {
+    (span.start.line, span.start.col) <= (line, col) && (line, col) < (span.end.line, span.end.col)
+}
+
+fn items_hover_target(items: &[Item], line: usize, col: usize) -> Option<HoverTarget<'_>> {
+    for item in items {
+        let target = match item {
+            Item::Scoped { items, .. } => items_hover_target(items, line, col),
+            Item::Words(words) => words_hover_target(words, line, col),
+            Item::Binding(binding) if span_contains(&binding.name.span, line, col) => Some(
+                HoverTarget::Binding(&binding.name.value, &binding.name.span),
+            ),
+            Item::Binding(binding) => words_hover_target(&binding.words, line, col),
+            Item::ExtraNewlines(_) => None,
+        };
+        if target.is_some() {
+            return target;
+        }
+    }
+    None
+}
+
+fn words_hover_target(words: &[Sp<Word>], line: usize, col: usize) -> Option<HoverTarget<'_>> {
+    for word in words {
+        if !span_contains(&word.span, line, col) {
+            continue;
+        }
+        return match &word.value {
+            Word::Number(_, n) => Some(HoverTarget::Number(*n, &word.span)),
+            Word::Ident(name) => Some(HoverTarget::Binding(name, &word.span)),
+            Word::Primitive(prim) => Some(HoverTarget::Primitive(*prim, &word.span)),
+            Word::Strand(items) => words_hover_target(items, line, col),
+            Word::Array(arr) => (arr.lines.iter()).find_map(|w| words_hover_target(w, line, col)),
+            Word::Func(func) => (func.lines.iter()).find_map(|w| words_hover_target(w, line, col)),
+            Word::Modified(m) if span_contains(&m.modifier.span, line, col) => {
+                Some(HoverTarget::Primitive(m.modifier.value, &m.modifier.span))
+            }
+            Word::Modified(m) => words_hover_target(&m.operands, line, col),
+            _ => None,
+        };
+    }
+    None
+}
+
+/// Find the last binding of a name
+fn find_binding<'a>(items: &'a [Item], name: &str) -> Option<&'a Binding> {
+    let mut found = None;
+    for item in items {
+        match item {
+            Item::Binding(binding) if &*binding.name.value == name => found = Some(binding),
+            Item::Scoped { items, .. } => found = find_binding(items, name).or(found),
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Whether a binding is bound to a function or a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Function,
+    Value,
+}
+
+/// A binding in a document
+#[derive(Debug, Clone)]
+pub struct BindingSymbol {
+    pub name: Ident,
+    /// The span of the binding's name
+    pub name_span: CodeSpan,
+    /// The span of the whole binding
+    pub span: CodeSpan,
+    pub kind: BindingKind,
+    /// The signature of the binding, if it is a function
+    pub signature: Option<Signature>,
+}
+
+/// Get the bindings in a document, including those in scopes, in the order they appear
+///
+/// Bindings are classified from the syntax tree alone, so no code is run.
+/// A binding is a function if it declares a signature, or if its body is a single
+/// function, modifier, or reference to another function binding.
+pub fn document_symbols(input: &str) -> Vec<BindingSymbol> {
+    let (items, _, _) = parse(input, None);
+    let mut bindings = Vec::new();
+    collect_bindings(&items, &mut bindings);
+    let mut symbols: Vec<BindingSymbol> = Vec::with_capacity(bindings.len());
+    for binding in bindings {
+        let (kind, signature) = binding_kind(binding, &symbols);
+        symbols.push(BindingSymbol {
+            name: binding.name.value.clone(),
+            name_span: binding.name.span.clone(),
+            span: binding.span(),
+            kind,
+            signature,
+        });
+    }
+    symbols
+}
+
+fn binding_kind(binding: &Binding, earlier: &[BindingSymbol]) -> (BindingKind, Option<Signature>) {
+    if let Some(sig) = &binding.signature {
+        return (BindingKind::Function, Some(sig.value));
+    }
+    let mut words = (binding.words.iter())
+        .filter(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)));
+    let (Some(word), None) = (words.next(), words.next()) else {
+        return (BindingKind::Value, None);
+    };
+    match &word.value {
+        Word::Func(func) => (
+            BindingKind::Function,
+            func.signature.as_ref().map(|sig| sig.value),
+        ),
+        Word::Modified(_) => (BindingKind::Function, None),
+        // Noadic primitives like `π` are evaluated when bound
+        Word::Primitive(prim) => match prim.signature() {
+            Some(sig) if sig.args == 0 => (BindingKind::Value, None),
+            signature => (BindingKind::Function, signature),
+        },
+        Word::Ident(name) => (earlier.iter().rev())
+            .find(|symbol| symbol.name == *name)
+            .map_or((BindingKind::Value, None), |symbol| {
+                (symbol.kind, symbol.signature)
+            }),
+        _ => (BindingKind::Value, None),
+    }
+}
+
+fn collect_bindings<'a>(items: &'a [Item], bindings: &mut Vec<&'a Binding>) {
+    for item in items {
+        match item {
+            Item::Binding(binding) => bindings.push(binding),
+            Item::Scoped { items, .. } => collect_bindings(items, bindings),
+            _ => {}
+        }
+    }
+}
+
+/// A hint shown at the end of a line of code
+#[derive(Debug, Clone)]
+pub struct InlayHint {
+    /// Where the hint should be shown
+    pub position: Loc,
+    /// The inferred signature of the line
+    pub signature: Signature,
+    pub label: String,
+}
+
+/// Get hints showing the signature of each top-level line of code
+///
+/// Each hint is positioned at the end of the line's last word, not counting comments.
+/// Lines whose signature cannot be inferred are skipped.
+pub fn inlay_hints(input: &str) -> Vec<InlayHint> {
+    let (items, _, _) = parse(input, None);
+    let mut env = Uiua::with_backend(MemorySys::default()).with_step_limit(SANDBOX_STEP_LIMIT);
+    let mut hints = Vec::new();
+    for item in items {
+        let end = match &item {
+            Item::Words(words) => (words.iter())
+                .rev()
+                .find(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)))
+                .map(|word| word.span.end),
+            _ => None,
+        };
+        // Bindings are still compiled so that later lines can refer to them
+        let mut lines = Vec::new();
+        env.compile_item(item, false, &mut lines, &mut Vec::new());
+        let (Some(position), [instrs]) = (end, lines.as_slice()) else {
+            continue;
+        };
+        if let Ok(signature) = instrs_signature(instrs) {
+            hints.push(InlayHint {
+                position,
+                signature,
+                label: signature.to_string(),
+            });
+        }
+    }
+    hints
+}
+
+/// Get the spans of every use of the binding or primitive at a 1-indexed line and column
+///
+/// Uses are resolved the same way the compiler resolves them, so a binding that
+/// shadows another, or is local to a scope, only has its own uses returned.
+/// If `include_declaration` is true, the name of the binding's definition is included.
+/// Spans are returned in the order they appear.
+pub fn references(
+    input: &str,
+    line: usize,
+    col: usize,
+    include_declaration: bool,
+) -> Vec<CodeSpan> {
+    let names = ResolvedNames::new(input);
+    let mut spans: Vec<CodeSpan> = match names.referent_at(line, col) {
+        Some(Referent::Primitive(prim)) => (names.primitives.into_iter())
+            .filter(|(p, _)| *p == prim)
+            .map(|(_, span)| span)
+            .collect(),
+        Some(Referent::Binding(id)) => {
+            let mut spans: Vec<CodeSpan> = (names.uses.into_iter())
+                .filter(|(use_id, _)| *use_id == id)
+                .map(|(_, span)| span)
+                .collect();
+            if include_declaration {
+                spans.push(names.definitions[id].clone());
+            }
+            spans
+        }
+        None => Vec::new(),
+    };
+    spans.sort_by_key(|span| span.start.char_pos);
+    spans
+}
+
+/// A change to the text of a document
+#[derive(Debug, Clone)]
+pub struct Edit {
+    /// The span of text to replace
+    pub span: CodeSpan,
+    pub new_text: String,
+}
+
+/// Rename the binding at a 1-indexed line and column, along with all of its uses
+///
+/// Fails if there is no binding at the position or if the new name is not a valid identifier.
+pub fn rename(input: &str, line: usize, col: usize, new_name: &str) -> Result<Vec<Edit>, String> {
+    let (tokens, errors) = lex(new_name, None);
+    let is_ident = errors.is_empty()
+        && matches!(tokens.as_slice(), [token] if token.value == Token::Ident
+            && token.span.as_str() == new_name);
+    if !is_ident {
+        return Err(format!("`{new_name}` is not a valid identifier"));
+    }
+    match ResolvedNames::new(input).referent_at(line, col) {
+        Some(Referent::Binding(_)) => {}
+        Some(Referent::Primitive(prim)) => {
+            return Err(format!(
+                "{prim} is a primitive, and primitives cannot be renamed"
+            ))
+        }
+        None => return Err("There is no binding here to rename".into()),
+    }
+    Ok((references(input, line, col, true).into_iter())
+        .map(|span| Edit {
+            span,
+            new_text: new_name.into(),
+        })
+        .collect())
+}
+
+enum Referent {
+    Primitive(Primitive),
+    /// The index of a binding
+    Binding(usize),
+}
+
+/// The names in a document, resolved to the bindings they refer to
+#[derive(Default)]
+struct ResolvedNames {
+    /// The name spans of each binding
+    definitions: Vec<CodeSpan>,
+    /// The spans of identifiers that refer to a binding, by the binding's index
+    uses: Vec<(usize, CodeSpan)>,
+    /// The spans of every primitive
+    primitives: Vec<(Primitive, CodeSpan)>,
+}
+
+impl ResolvedNames {
+    fn new(input: &str) -> Self {
+        let (items, _, _) = parse(input, None);
+        let mut names = ResolvedNames::default();
+        names.items(&items, &mut vec![HashMap::new()]);
+        names
+    }
+    fn referent_at(&self, line: usize, col: usize) -> Option<Referent> {
+        if let Some((prim, _)) =
+            (self.primitives.iter()).find(|(_, span)| span_contains(span, line, col))
+        {
+            return Some(Referent::Primitive(*prim));
+        }
+        (self.definitions.iter())
+            .position(|span| span_contains(span, line, col))
+            .or_else(|| {
+                (self.uses.iter())
+                    .find(|(_, span)| span_contains(span, line, col))
+                    .map(|(id, _)| *id)
+            })
+            .map(Referent::Binding)
+    }
+    fn items(&mut self, items: &[Item], scopes: &mut Vec<HashMap<Ident, usize>>) {
+        for item in items {
+            match item {
+                Item::Scoped { items, .. } => {
+                    scopes.push(HashMap::new());
+                    self.items(items, scopes);
+                    scopes.pop();
+                }
+                Item::Words(words) => self.words(words, scopes),
+                Item::Binding(binding) => {
+                    // A binding's own name is not bound until its words are compiled
+                    self.words(&binding.words, scopes);
+                    let id = self.definitions.len();
+                    self.definitions.push(binding.name.span.clone());
+                    (scopes.last_mut().unwrap()).insert(binding.name.value.clone(), id);
+                }
+                Item::ExtraNewlines(_) => {}
+            }
+        }
+    }
+    fn words(&mut self, words: &[Sp<Word>], scopes: &[HashMap<Ident, usize>]) {
+        for word in words {
+            match &word.value {
+                Word::Ident(ident) => {
+                    if let Some(&id) = scopes.iter().rev().find_map(|scope| scope.get(ident)) {
+                        self.uses.push((id, word.span.clone()));
+                    }
+                }
+                Word::Primitive(prim) => self.primitives.push((*prim, word.span.clone())),
+                Word::Strand(items) => self.words(items, scopes),
+                Word::Array(arr) => {
+                    for line in &arr.lines {
+                        self.words(line, scopes);
+                    }
+                }
+                Word::Func(func) => {
+                    for line in &func.lines {
+                        self.words(line, scopes);
+                    }
+                }
+                Word::Modified(m) => {
+                    (self.primitives).push((m.modifier.value, m.modifier.span.clone()));
+                    self.words(&m.operands, scopes);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
 #[cfg(feature = "lsp")]
 pub use server::run_server;
 
@@ -110,9 +571,9 @@ mod server {
     use super::*;
 
     use crate::{
-        format::{format_str, FormatConfig},
+        format::{format_edits, FormatConfig},
         lex::Loc,
-        primitive::{PrimClass, PrimDocFragment},
+        primitive::PrimClass,
         Ident, Uiua,
     };
 
@@ -259,6 +720,10 @@ mod server {
                         TextDocumentSyncKind::FULL,
                     )),
                     hover_provider: Some(HoverProviderCapability::Simple(true)),
+                    document_symbol_provider: Some(OneOf::Left(true)),
+                    inlay_hint_provider: Some(OneOf::Left(true)),
+                    references_provider: Some(OneOf::Left(true)),
+                    rename_provider: Some(OneOf::Left(true)),
                     document_formatting_provider: Some(OneOf::Left(true)),
                     semantic_tokens_provider: Some(
                         SemanticTokensServerCapabilities::SemanticTokensOptions(
@@ -308,82 +773,112 @@ mod server {
             } else {
                 return Ok(None);
             };
-            let (line, col) = lsp_pos_to_uiua(params.text_document_position_params.position);
-            let mut prim_range = None;
-            for sp in &doc.spans {
-                if sp.span.contains_line_col(line, col) {
-                    match sp.value {
-                        SpanKind::Primitive(prim) => {
-                            if prim.name().is_some() {
-                                prim_range = Some((prim, uiua_span_to_lsp(&sp.span)));
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            let mut binding_range = None;
-            for (ident, binding) in &doc.bindings {
-                if ident.span.contains_line_col(line, col) {
-                    binding_range = Some((ident, binding, uiua_span_to_lsp(&ident.span)));
-                }
+            let position = params.text_document_position_params.position;
+            let (line, col) = lsp_pos_to_uiua(&doc.input, position);
+            let Some(info) = hover(&doc.input, line, col) else {
+                return Ok(None);
+            };
+            let mut value = info.description;
+            if let Some(comment) = (doc.bindings.iter())
+                .find(|(ident, _)| ident.span == info.span)
+                .and_then(|(_, binding)| binding.comment.as_ref())
+            {
+                value.push('\n');
+                value.push_str(comment);
             }
-            Ok(Some(if let Some((prim, range)) = prim_range {
-                let mut value: String = prim.name().unwrap().into();
-                if let Some(doc) = prim.doc() {
-                    value.push('\n');
-                    for frag in doc.short.iter() {
-                        match frag {
-                            PrimDocFragment::Text(text) => value.push_str(text),
-                            PrimDocFragment::Code(text) => value.push_str(&format!("`{}`", text)),
-                            PrimDocFragment::Emphasis(text) => {
-                                value.push_str(&format!("*{}*", text))
-                            }
-                            PrimDocFragment::Strong(text) => {
-                                value.push_str(&format!("**{}**", text))
-                            }
-                            PrimDocFragment::Link { text, url } => {
-                                value.push_str(&format!("[{}]({})", text, url))
-                            }
-                            PrimDocFragment::Primitive { prim, named } => {
-                                let name = prim.name().unwrap();
-                                value.push_str(&if *named {
-                                    if let Some(unicode) = prim.glyph() {
-                                        format!("`{unicode} {name}`")
-                                    } else {
-                                        format!("`{name}`")
-                                    }
-                                } else if let Some(unicode) = prim.glyph() {
-                                    format!("`{unicode}`")
-                                } else {
-                                    format!("`{name}`")
-                                })
-                            }
-                        }
-                    }
-                }
-                Hover {
-                    contents: HoverContents::Markup(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value,
-                    }),
-                    range: Some(range),
-                }
-            } else if let Some((ident, binding, range)) = binding_range {
-                let mut value: String = ident.value.as_ref().into();
-                if let Some(comment) = &binding.comment {
-                    value.push('\n');
-                    value.push_str(comment);
-                }
-                Hover {
-                    contents: HoverContents::Markup(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value,
-                    }),
-                    range: Some(range),
-                }
-            } else {
+            Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: Some(uiua_span_to_lsp(&doc.input, &info.span)),
+            }))
+        }
+
+        async fn document_symbol(
+            &self,
+            params: DocumentSymbolParams,
+        ) -> Result<Option<DocumentSymbolResponse>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
+                return Ok(None);
+            };
+            #[allow(deprecated)]
+            let symbols = (document_symbols(&doc.input).into_iter())
+                .map(|symbol| DocumentSymbol {
+                    name: symbol.name.to_string(),
+                    detail: symbol.signature.map(|sig| sig.to_string()),
+                    kind: match symbol.kind {
+                        BindingKind::Function => SymbolKind::FUNCTION,
+                        BindingKind::Value => SymbolKind::CONSTANT,
+                    },
+                    tags: None,
+                    deprecated: None,
+                    range: uiua_span_to_lsp(&doc.input, &symbol.span),
+                    selection_range: uiua_span_to_lsp(&doc.input, &symbol.name_span),
+                    children: None,
+                })
+                .collect();
+            Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+        }
+
+        async fn inlay_hint(
+            &self,
+            params: InlayHintParams,
+        ) -> Result<Option<Vec<lsp_types::InlayHint>>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
                 return Ok(None);
+            };
+            let hints = (super::inlay_hints(&doc.input).into_iter())
+                .map(|hint| lsp_types::InlayHint {
+                    position: uiua_loc_to_lsp_utf16(&doc.input, hint.position),
+                    label: InlayHintLabel::String(hint.label),
+                    kind: None,
+                    text_edits: None,
+                    tooltip: None,
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                })
+                .collect();
+            Ok(Some(hints))
+        }
+
+        async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+            let uri = params.text_document_position.text_document.uri;
+            let Some(doc) = self.docs.get(&uri) else {
+                return Ok(None);
+            };
+            let position = params.text_document_position.position;
+            let (line, col) = lsp_pos_to_uiua(&doc.input, position);
+            let include_declaration = params.context.include_declaration;
+            let locations = (super::references(&doc.input, line, col, include_declaration))
+                .into_iter()
+                .map(|span| Location {
+                    uri: uri.clone(),
+                    range: uiua_span_to_lsp(&doc.input, &span),
+                })
+                .collect();
+            Ok(Some(locations))
+        }
+
+        async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+            let uri = params.text_document_position.text_document.uri;
+            let Some(doc) = self.docs.get(&uri) else {
+                return Ok(None);
+            };
+            let position = params.text_document_position.position;
+            let (line, col) = lsp_pos_to_uiua(&doc.input, position);
+            let edits = super::rename(&doc.input, line, col, &params.new_name)
+                .map_err(jsonrpc::Error::invalid_params)?;
+            let edits = (edits.into_iter())
+                .map(|edit| TextEdit {
+                    range: uiua_span_to_lsp(&doc.input, &edit.span),
+                    new_text: edit.new_text,
+                })
+                .collect();
+            Ok(Some(WorkspaceEdit {
+                changes: Some([(uri.clone(), edits)].into()),
+                ..Default::default()
             }))
         }
 
@@ -397,15 +892,21 @@ mod server {
                 return Ok(None);
             };
 
-            let Ok(formatted) = format_str(&doc.input, &FormatConfig::find().unwrap_or_default())
+            let Ok(edits) = format_edits(&doc.input, &FormatConfig::find().unwrap_or_default())
             else {
                 return Ok(None);
             };
-            let range = Range::new(Position::new(0, 0), Position::new(u32::MAX, u32::MAX));
-            Ok(Some(vec![TextEdit {
-                range,
-                new_text: formatted.output,
-            }]))
+            Ok(Some(
+                (edits.into_iter())
+                    .map(|edit| TextEdit {
+                        range: Range::new(
+                            byte_pos_to_lsp(&doc.input, edit.range.start),
+                            byte_pos_to_lsp(&doc.input, edit.range.end),
+                        ),
+                        new_text: edit.new_text,
+                    })
+                    .collect(),
+            ))
         }
 
         async fn inline_value(
@@ -508,19 +1009,146 @@ mod server {
         }
     }
 
-    fn lsp_pos_to_uiua(pos: Position) -> (usize, usize) {
-        (pos.line as usize + 1, pos.character as usize + 1)
+    /// Convert an LSP position, whose character is in UTF-16 code units,
+    /// to a 1-indexed line and character column
+    fn lsp_pos_to_uiua(input: &str, pos: Position) -> (usize, usize) {
+        let line = input.lines().nth(pos.line as usize).unwrap_or_default();
+        let mut units = 0;
+        let col = (line.chars())
+            .take_while(|c| {
+                units += c.len_utf16();
+                units <= pos.character as usize
+            })
+            .count();
+        (pos.line as usize + 1, col + 1)
+    }
+
+    /// Convert a Uiua location to an LSP position, whose character is in UTF-16 code units
+    fn uiua_loc_to_lsp_utf16(input: &str, loc: Loc) -> Position {
+        let line = input.lines().nth(loc.line - 1).unwrap_or_default();
+        let units: usize = line.chars().take(loc.col - 1).map(char::len_utf16).sum();
+        Position::new(loc.line as u32 - 1, units as u32)
+    }
+
+    /// Convert a byte position to an LSP position, whose character is in UTF-16 code units
+    fn byte_pos_to_lsp(input: &str, pos: usize) -> Position {
+        let before = &input[..pos];
+        let line = before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let units: usize = before[line_start..].chars().map(char::len_utf16).sum();
+        Position::new(line as u32, units as u32)
+    }
+
+    fn uiua_span_to_lsp(input: &str, span: &CodeSpan) -> Range {
+        Range::new(
+            uiua_loc_to_lsp_utf16(input, span.start),
+            uiua_loc_to_lsp_utf16(input, span.end),
+        )
     }
 
     fn uiua_loc_to_lsp(loc: Loc) -> Position {
         Position::new(loc.line as u32 - 1, loc.col as u32 - 1)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hover_info() {
+        let info = hover("+ 1 2", 1, 1).unwrap();
+        assert_eq!(info.signature, Some(Signature::new(2, 1)));
+        assert!(info.description.contains("|2.1"), "{}", info.description);
+
+        // Columns count characters, not bytes
+        let info = hover("⇡ ⊂ 1 2", 1, 3).unwrap();
+        assert!(
+            info.description.starts_with("`⊂` join"),
+            "{}",
+            info.description
+        );
+
+        let info = hover("+ 1 2.5", 1, 5).unwrap();
+        assert_eq!(info.description, "`2.5`: number");
 
-    fn uiua_locs_to_lsp(start: Loc, end: Loc) -> Range {
-        Range::new(uiua_loc_to_lsp(start), uiua_loc_to_lsp(end))
+        let info = hover("F ← +1\nF 2", 2, 1).unwrap();
+        assert_eq!(info.signature, Some(Signature::new(1, 1)));
+
+        assert!(hover("+ 1 2", 1, 2).is_none());
+    }
+
+    #[test]
+    fn document_symbol_list() {
+        let symbols = document_symbols(
+            "X ← 5\nAdd ← +\n---\nInc ← |1 +1\n---\n[X Inc 1]\nSum ← /+\nPlus ← Add\nP ← π",
+        );
+        let names: Vec<&str> = symbols.iter().map(|symbol| &*symbol.name).collect();
+        assert_eq!(names, ["X", "Add", "Inc", "Sum", "Plus", "P"]);
+        let kinds: Vec<BindingKind> = symbols.iter().map(|symbol| symbol.kind).collect();
+        use BindingKind::*;
+        assert_eq!(
+            kinds,
+            [Value, Function, Function, Function, Function, Value]
+        );
+        assert_eq!(symbols[1].signature, Some(Signature::new(2, 1)));
+        assert_eq!(symbols[2].signature, Some(Signature::new(1, 1)));
+        assert_eq!(symbols[4].signature, Some(Signature::new(2, 1)));
+        let ranges: Vec<(usize, usize, usize)> = (symbols.iter())
+            .map(|symbol| {
+                let span = &symbol.name_span;
+                (span.start.line, span.start.col, span.end.col)
+            })
+            .collect();
+        assert_eq!(ranges[..3], [(1, 1, 2), (2, 1, 4), (4, 1, 4)]);
+    }
+
+    #[test]
+    fn inlay_hint_signatures() {
+        let hints = inlay_hints("# Comment\n+×2 # Add double\nF ← +1\nF 5");
+        let labels: Vec<&str> = hints.iter().map(|hint| hint.label.as_str()).collect();
+        assert_eq!(labels, ["|2.1", "|0.1"]);
+        let position = hints[0].position;
+        // `×` is 2 bytes long
+        assert_eq!((position.line, position.col, position.byte_pos), (2, 4, 14));
     }
 
-    fn uiua_span_to_lsp(span: &CodeSpan) -> Range {
-        uiua_locs_to_lsp(span.start, span.end)
+    #[test]
+    fn binding_references() {
+        let lines = |spans: Vec<CodeSpan>| -> Vec<(usize, usize)> {
+            (spans.iter())
+                .map(|span| (span.start.line, span.start.col))
+                .collect()
+        };
+        let input = "X ← 5\n+X ×2X";
+        assert_eq!(lines(references(input, 1, 1, false)), [(2, 2), (2, 6)]);
+        assert_eq!(
+            lines(references(input, 2, 6, true)),
+            [(1, 1), (2, 2), (2, 6)]
+        );
+
+        // Shadowed bindings have their own references
+        let input = "X ← 1\n---\nX ← +1 X\nX\n---\nX";
+        assert_eq!(lines(references(input, 1, 1, false)), [(3, 8), (6, 1)]);
+        assert_eq!(lines(references(input, 3, 1, false)), [(4, 1)]);
+
+        // Primitives return all of their uses
+        assert_eq!(lines(references("+1 ×2 +3", 1, 1, true)), [(1, 1), (1, 7)]);
+    }
+
+    #[test]
+    fn rename_binding() {
+        let input = "F ← +1\nF 5\n---\nF ← ×2\nF 3\n---";
+        let edits = rename(input, 2, 1, "Foo").unwrap();
+        let spans: Vec<(usize, usize)> = (edits.iter())
+            .map(|edit| (edit.span.start.line, edit.span.start.col))
+            .collect();
+        assert_eq!(spans, [(1, 1), (2, 1)]);
+        assert!(edits.iter().all(|edit| edit.new_text == "Foo"));
+
+        assert!(rename(input, 1, 1, "2x").is_err());
+        assert!(rename(input, 1, 1, "add").is_err());
+        let err = rename(input, 1, 5, "Foo").unwrap_err();
+        assert!(err.contains("primitives cannot be renamed"), "{err}");
     }
 }
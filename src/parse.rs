@@ -101,18 +101,39 @@ pub fn parse(
         errors,
         diagnostics: Vec::new(),
     };
-    let items = parser.items(true);
-    if parser.errors.is_empty() && parser.index < parser.tokens.len() {
-        parser.errors.push(
+    let mut items = parser.items(true);
+    // Recover from unexpected tokens at the next line
+    // so that errors on later lines are reported as well
+    while let Some(token) = parser.tokens.get(parser.index) {
+        let line = token.span.start.line;
+        if !(parser.errors.iter()).any(|error| error.span.start.line == line) {
             parser
-                .tokens
-                .remove(parser.index)
-                .map(ParseError::Unexpected),
-        );
+                .errors
+                .push(token.clone().map(ParseError::Unexpected));
+        }
+        parser.index += 1;
+        while (parser.tokens.get(parser.index)).is_some_and(|token| token.value != Newline) {
+            parser.index += 1;
+        }
+        items.extend(parser.items(true));
     }
     (items, parser.errors, parser.diagnostics)
 }
 
+/// Parse some code into items with their spans
+///
+/// Parsing does not stop at the first error. If there are errors, the items
+/// are those that could be parsed, and incomplete constructs like unclosed arrays
+/// are included as far as they go.
+/// Scopes that contain no items are omitted. Use [`parse`] to get every item.
+pub fn parse_spanned(input: &str) -> (Vec<Sp<Item>>, Vec<Sp<ParseError>>) {
+    let (items, errors, _) = parse(input, None);
+    let items = (items.into_iter())
+        .filter_map(|item| Some(item.span()?.sp(item)))
+        .collect();
+    (items, errors)
+}
+
 struct Parser {
     tokens: Vec<Sp<crate::lex::Token>>,
     index: usize,
@@ -554,7 +575,37 @@ impl Parser {
     fn try_num(&mut self) -> Option<Sp<(String, f64)>> {
         let span = self.try_exact(Token::Number)?;
         let s = span.as_str().to_string();
-        let parseable = s.replace(['`', '¯'], "-");
+        let (negative, unsigned) = match s.strip_prefix(['`', '¯']) {
+            Some(unsigned) => (true, unsigned),
+            None => (false, s.as_str()),
+        };
+        let radix = match unsigned.get(..2) {
+            Some("0x" | "0X") => Some(16),
+            Some("0b" | "0B") => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            let digits = &unsigned[2..];
+            // Missing digits are reported by the lexer
+            let n = digits.chars().fold(0.0, |acc, c| {
+                acc * radix as f64 + c.to_digit(radix).unwrap_or(0) as f64
+            });
+            let exact = u64::from_str_radix(digits, radix)
+                .map_or(digits.is_empty(), |n| n <= 1 << f64::MANTISSA_DIGITS);
+            if !exact {
+                self.diagnostics.push(Diagnostic::new(
+                    format!(
+                        "Number literal `{s}` is too large to be represented \
+                        exactly and will be rounded to {n}"
+                    ),
+                    span.clone(),
+                    DiagnosticKind::Warning,
+                ));
+            }
+            let n = if negative { -n } else { n };
+            return Some(span.sp((s, n)));
+        }
+        let parseable = s.replace(['`', '¯'], "-").replace('∞', "inf");
         let n: f64 = match parseable.parse() {
             Ok(n) => n,
             Err(_) => {
@@ -605,3 +656,116 @@ impl Parser {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(input: &str) -> Vec<f64> {
+        let (items, errors, _) = parse(input, None);
+        assert!(errors.is_empty(), "{errors:?}");
+        let Some(Item::Words(words)) = items.first() else {
+            panic!("expected words");
+        };
+        (words.iter())
+            .filter_map(|word| match word.value {
+                Word::Number(_, n) => Some(n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hex_and_binary_literals() {
+        assert_eq!(numbers("0xFF"), [255.0]);
+        assert_eq!(
+            numbers("0Xff 0b1010 0B11 ¯0x10 `0b1"),
+            [255.0, 10.0, 3.0, -16.0, -1.0]
+        );
+        // A prefix without digits followed by a name is `0` and that name
+        for input in ["0box", "0bits", "0both", "0Bar", "0xor"] {
+            assert_eq!(numbers(input), [0.0], "{input}");
+        }
+        let (_, errors, _) = parse("[0box 1]", None);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(numbers("0 1.5 0.25"), [0.0, 1.5, 0.25]);
+        // Missing digits
+        let (_, errors, _) = parse("1 0x", None);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span.as_str(), "0x");
+        assert!(errors[0].to_string().contains("hexadecimal"));
+        let (_, errors, _) = parse("0b2", None);
+        assert!(errors[0].to_string().contains("binary"));
+        // Inexact literals are warnings
+        let (_, errors, diagnostics) = parse("0x20000000000001", None);
+        assert!(errors.is_empty());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::Warning);
+        let (_, _, diagnostics) = parse("0x20000000000000", None);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn negation_before_terms() {
+        for input in ["¯[1 2 3]", "¯(+1)", "¯x", "¯.", "¯ 5"] {
+            let (items, errors, _) = parse(input, None);
+            assert!(errors.is_empty(), "{input}: {errors:?}");
+            let Some(Item::Words(words)) = items.first() else {
+                panic!("{input}: expected words");
+            };
+            assert!(
+                matches!(words[0].value, Word::Primitive(Primitive::Neg)),
+                "{input}: {:?}",
+                words[0].value
+            );
+            assert_eq!(words[0].span.as_str(), "¯");
+            let formatted = crate::format::format_str(input, &Default::default()).unwrap();
+            assert_eq!(formatted.output.trim_end(), input);
+        }
+        // A negative sign directly before a digit is part of the number
+        assert_eq!(numbers("¯1 ¯2.5"), [-1.0, -2.5]);
+        let (items, _, _) = parse("¯[1 ¯2]", None);
+        let Some(Item::Words(words)) = items.first() else {
+            panic!("expected words");
+        };
+        assert_eq!(words[1].span.as_str(), "[1 ¯2]");
+    }
+
+    #[test]
+    fn infinity_literals() {
+        assert_eq!(
+            numbers("¯∞ `∞ 1"),
+            [f64::NEG_INFINITY, f64::NEG_INFINITY, 1.0]
+        );
+        // Positive infinity is a constant primitive
+        assert!(numbers("∞").is_empty());
+    }
+
+    #[test]
+    fn spanned_items() {
+        let input = "X ← 1\nY ← +1 2 # three";
+        let (items, errors) = parse_spanned(input);
+        assert!(errors.is_empty(), "{errors:?}");
+        let bindings: Vec<_> = (items.iter())
+            .filter(|item| matches!(item.value, Item::Binding(_)))
+            .collect();
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings[0].span.as_str(), "X ← 1");
+        assert_eq!(bindings[1].span.as_str(), "Y ← +1 2 # three");
+        assert_eq!(bindings[1].span.start.line, 2);
+        let Item::Binding(binding) = &bindings[1].value else {
+            unreachable!()
+        };
+        assert_eq!(binding.name.span.as_str(), "Y");
+
+        let (items, errors) = parse_spanned("1 [2 3");
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        let Some(Item::Words(words)) = items.first().map(|item| &item.value) else {
+            panic!("expected words");
+        };
+        assert!(words
+            .iter()
+            .any(|word| matches!(word.value, Word::Array(_))));
+        assert_eq!(items[0].span.as_str(), "1 [2 3");
+    }
+}
@@ -253,6 +253,54 @@ primitive!(
     (1, Asin, MonadicPervasive),
     /// Get the arccosine of a number
     (1, Acos, MonadicPervasive),
+    /// Get the hyperbolic sine of a number
+    ///
+    /// ex: sinh 1
+    /// ex: sinh [¯1 0 1]
+    (1, Sinh, MonadicPervasive, "sinh"),
+    /// Get the hyperbolic cosine of a number
+    ///
+    /// ex: cosh 1
+    /// ex: cosh [¯1 0 1]
+    (1, Cosh, MonadicPervasive, "cosh"),
+    /// Get the hyperbolic tangent of a number
+    ///
+    /// ex: tanh 1
+    /// ex: tanh [¯1 0 1]
+    (1, Tanh, MonadicPervasive, "tanh"),
+    /// Get the cube root of a number
+    ///
+    /// Unlike `ⁿ÷3 1`, this gives the real cube root of negative numbers.
+    /// ex: cbrt 27
+    /// ex: cbrt [¯8 0 2]
+    (1, Cbrt, MonadicPervasive, "cbrt"),
+    /// Get the gamma function of a number
+    ///
+    /// For a positive integer `n`, this is the factorial of `n-1`.
+    /// The gamma function of `0` is `∞`, and the gamma function of a negative integer is `NaN`.
+    /// ex: gamma 5
+    /// ex: gamma [0.5 1 1.5]
+    /// ex: gamma ¯0.5
+    (1, Gamma, MonadicPervasive, "gamma"),
+    /// Get the factorial of a number
+    ///
+    /// Non-integers are extended with [gamma].
+    /// ex: factorial 5
+    /// ex: factorial [0 1 2 3 4]
+    /// ex: factorial 0.5
+    (1, Factorial, MonadicPervasive, "factorial"),
+    /// Get the base-2 logarithm of a number
+    ///
+    /// The logarithm of `0` is `¯∞`, and the logarithm of a negative number is `NaN`.
+    /// ex: log2 8
+    /// ex: log2 [1 2 1024 0.5]
+    (1, Log2, MonadicPervasive, "log2"),
+    /// Get the base-10 logarithm of a number
+    ///
+    /// The logarithm of `0` is `¯∞`, and the logarithm of a negative number is `NaN`.
+    /// ex: log10 1000
+    /// ex: log10 [1 10 0.01]
+    (1, Log10, MonadicPervasive, "log10"),
     /// Round to the nearest integer towards `¯∞`
     ///
     /// ex: ⌊1.5
@@ -411,6 +459,67 @@ primitive!(
     /// ex: ₙ2 [8 16 32]
     /// ex: ₙ [2 3 4] [16 27 1024]
     (2, Log, DyadicPervasive, ("logarithm", 'ₙ')),
+    /// Get the greatest common divisor of two integers
+    ///
+    /// The result is never negative.
+    /// ex: gcd 12 18
+    /// ex: gcd 0 ¯5
+    /// ex: gcd [4 9 15] 6
+    /// ex! gcd 1.5 3
+    (2, Gcd, DyadicPervasive, "gcd"),
+    /// Get the least common multiple of two integers
+    ///
+    /// The result is never negative.
+    /// ex: lcm 4 6
+    /// ex: lcm 0 5
+    /// ex: lcm [2 3 4] 10
+    (2, Lcm, DyadicPervasive, "lcm"),
+    /// Round to a number of decimal places
+    ///
+    /// The first argument is the number of digits after the decimal point to keep.
+    /// A negative number of digits rounds to tens, hundreds, and so on.
+    /// Halfway values are rounded away from zero, just like [round].
+    /// ex: roundto 2 3.14159
+    /// ex: roundto 0 [1.5 2.5 ¯0.5]
+    /// ex: roundto ¯2 1250
+    /// ex: roundto [0 1 2 3] π
+    (2, RoundTo, DyadicPervasive, "roundto"),
+    /// Get the bitwise and of two integers
+    ///
+    /// ex: band 12 10
+    /// ex: band [1 2 3] 3
+    (2, Band, DyadicPervasive, "band"),
+    /// Get the bitwise or of two integers
+    ///
+    /// ex: bor 12 10
+    /// ex: bor [1 2 4] 8
+    (2, Bor, DyadicPervasive, "bor"),
+    /// Get the bitwise xor of two integers
+    ///
+    /// ex: bxor 12 10
+    /// [bxor]ing by the same value twice gives back the original.
+    /// ex: bxor 5 bxor 5 [1 2 3]
+    (2, Bxor, DyadicPervasive, "bxor"),
+    /// Shift the bits of an integer left
+    ///
+    /// The first argument is the number of bits to shift by.
+    /// ex: bshl 2 3
+    /// ex: bshl 4 255
+    /// ex! bshl ¯1 3
+    (2, Bshl, DyadicPervasive, "bshl"),
+    /// Shift the bits of an integer right
+    ///
+    /// The first argument is the number of bits to shift by.
+    /// ex: bshr 2 12
+    /// ex: bshr 1 ¯5
+    (2, Bshr, DyadicPervasive, "bshr"),
+    /// Invert the bits of an integer
+    ///
+    /// The width used is the smallest of 8, 16, 32, or 64 bits that fits every number in the array.
+    /// ex: bnot 0
+    /// ex: bnot [1 300]
+    /// ex: bnot ¯1
+    (1, Bnot, MonadicArray, "bnot"),
     /// Take the minimum of two arrays
     ///
     /// ex: ↧ 3 5
@@ -430,6 +539,32 @@ primitive!(
     /// [maximum] can be used as a logical OR.
     /// ex: ↥,,≤5∶≥8. [6 2 5 9 6 5 0 4]
     (2, Max, DyadicPervasive, ("maximum", '↥')),
+    /// Clamp an array between a low and a high bound
+    ///
+    /// The first argument is the low bound, and the second is the high bound.
+    /// ex: clamp 0 10 [¯5 3 20]
+    /// ex: clamp [0 5] 10 [¯5 3]
+    /// ex: clamp @b @y "abcxyz"
+    /// ex! clamp 10 0 5
+    (3, Clamp, Misc, "clamp"),
+    /// Choose each element from one of two arrays based on a mask
+    ///
+    /// The first argument is a mask of `0`s and `1`s.
+    /// Where the mask is `1`, the element comes from the second argument.
+    /// Where it is `0`, the element comes from the third argument.
+    /// ex: ifelse [1 0 1] [1 2 3] [4 5 6]
+    /// The arguments pervade, so scalars are used for every element.
+    /// ex: ifelse [1 0 1] 0 [4 5 6]
+    /// ex: ifelse >3 [1 5 2 6] @+ @-
+    /// ex! ifelse [1 2] 1 0
+    (3, IfElse, Misc, "ifelse"),
+    /// Get the length of the hypotenuse of a right triangle
+    ///
+    /// This is the square root of the sum of the squares of the arguments,
+    /// but it does not overflow for large arguments.
+    /// ex: hypot 3 4
+    /// ex: hypot [5 8] 12
+    (2, Hypot, DyadicPervasive, "hypot"),
     /// Take the arctangent of two numbers
     ///
     /// This takes a `y` and `x` argument and returns the angle in radians in the range `(-π, π]`.
@@ -581,6 +716,12 @@ primitive!(
     /// ex: ⊝"Hello, World!"
     /// ex: ⊝[3_2 1_4 3_2 5_6 1_4 7_8]
     (1, Deduplicate, MonadicArray, ("deduplicate", '⊝')),
+    /// Get the Euclidean length of a list of numbers
+    ///
+    /// ex: norm [3 4]
+    /// ex: norm [1 1 1 1]
+    /// ex: norm []
+    (1, Norm, MonadicArray, "norm"),
     /// Turn an array into a box
     ///
     /// This is Uiua's primary way to create nested or mixed-type arrays.
@@ -776,6 +917,50 @@ primitive!(
     /// Multi-dimensional window sizes are supported.
     /// ex: ◫2_2 .[1_2_3 4_5_6 7_8_9]
     (2, Windows, DyadicArray, ("windows", '◫')),
+    /// Get the dot product of two lists of numbers
+    ///
+    /// ex: dot [1 2 3] [4 5 6]
+    /// The lists must have the same length.
+    /// ex! dot [1 2] [3 4 5]
+    (2, Dot, DyadicArray, "dot"),
+    /// Alternate the rows of two arrays
+    ///
+    /// ex: interleave [1 2 3] [4 5 6]
+    /// ex: interleave ["ab" "cd"] ["12" "34"]
+    /// If one array has more rows, its extra rows are added at the end.
+    /// ex: interleave [1 2] [3 4 5 6]
+    /// The rows must have the same shape.
+    /// ex! interleave [1 2] [3_4 5_6]
+    (2, Interleave, DyadicArray, "interleave"),
+    /// Find where values would be inserted into a sorted list
+    ///
+    /// The first argument is a list of numbers sorted in ascending order.
+    /// The result is the index at which each number in the second argument could be inserted while keeping the list sorted.
+    /// ex: bisect [1 3 5 7] [4 5 0 8]
+    /// ex: bisect [1 3 5 7] 6
+    /// If a number is already in the list, the index is the leftmost position it could be inserted at.
+    /// Use [bisectright] to get the rightmost position instead.
+    /// ex: bisect [1 2 2 2 3] 2
+    ///
+    /// The list is searched with a binary search, so it is not checked to be sorted.
+    (2, Bisect, DyadicArray, "bisect"),
+    /// Find where values would be inserted into a sorted list, after any equal elements
+    ///
+    /// This is like [bisect], but if a number is already in the list, the index is the rightmost position it could be inserted at.
+    /// ex: bisectright [1 2 2 2 3] 2
+    /// ex: bisectright [1 3 5 7] [4 5 0 8]
+    (2, BisectRight, DyadicArray, "bisectright"),
+    /// Get a quantile of an array's rows
+    ///
+    /// The first argument is a number between `0` and `1`.
+    /// The result is linearly interpolated between the two closest rows in sorted order.
+    /// ex: quantile 0.5 [3 1 4 1 5]
+    /// ex: quantile 0.5 [1 2 3 4]
+    /// ex: quantile 0.9 ⇡11
+    /// Each column of a higher-rank array is treated separately.
+    /// ex: quantile 0.5 [1_10 2_20 4_40]
+    /// ex! quantile 2 [1 2 3]
+    (2, Quantile, DyadicArray, "quantile"),
     /// Discard or copy some rows of an array
     ///
     /// Takes two arrays. The first array is the number of copies to keep of each row of the second array.
@@ -1339,6 +1524,20 @@ primitive!(
     /// ex: ↯3_3⇡9
     ///   : wait≡spawn/+.
     ([1], Spawn, OtherModifier, "spawn"),
+    /// Call a function, caching its outputs for each set of arguments
+    ///
+    /// If a [memo]ized function is called again with the same arguments, the cached outputs are pushed instead of calling the function.
+    /// ex: memo(/+⇡) 10
+    /// ex: [memo(+1) 1 memo(+1) 1]
+    ///
+    /// This is useful for recursive functions that would otherwise recompute the same results many times.
+    /// Here is a recursive fibonacci function that runs in linear time.
+    /// ex: Fib ← |1 memo(?∘(|1 +↬3-1∶↬3-2.) <2.)
+    ///   : Fib 30
+    ///
+    /// Arguments that contain functions other than [box]ed values are never cached.
+    /// The cache is kept for the whole run and is shared with threads created by [spawn].
+    ([1], Memo, OtherModifier, "memo"),
     /// Wait for a thread to finish and push its results to the stack
     ///
     /// The argument must be a handle returned by [spawn].
@@ -1426,6 +1625,21 @@ primitive!(
     /// ex: deal⚂ [1 2 3 4 5]
     /// ex: deal⚂ [1_2 3_4 5_6 7_8]
     (2, Deal, Misc, "deal"),
+    /// Randomly reorder the rows of an array
+    ///
+    /// Unlike [deal], this does not take a seed.
+    /// The order is only reproducible if the interpreter was given a seed.
+    /// ex: shuffle [1 2 3 4 5]
+    /// ex: shuffle [1_2 3_4 5_6 7_8]
+    (1, Shuffle, Misc, "shuffle"),
+    /// Randomly choose some of the rows of an array
+    ///
+    /// The first argument is the number of rows to choose.
+    /// No row is chosen more than once.
+    /// ex: sample 3 [1 2 3 4 5]
+    /// ex: sample 2 [1_2 3_4 5_6 7_8]
+    /// ex! sample 4 [1 2 3]
+    (2, Sample, Misc, "sample"),
     /// Parse a string as a number
     ///
     /// ex: parse "17"
@@ -1439,12 +1653,27 @@ primitive!(
     /// Escaped regex characters must be double-escaped.
     /// ex: regex "\\d+" "123"
     (2, Regex, Misc, "regex"),
+    /// Check whether a regex pattern matches anywhere in a string
+    ///
+    /// ex: regexmatch "\\d+" "abc123"
+    /// ex: regexmatch "^\\d+$" "abc123"
+    (2, RegexMatch, Misc, "regexmatch"),
+    /// Replace all matches of a regex pattern in a string
+    ///
+    /// The first argument is the pattern, the second is the replacement, and the third is the string to search.
+    /// The replacement can refer to capture groups with `$1`, `$2`, etc.
+    /// ex: regexreplace "(\\w+)@(\\w+)" "$2:$1" "me@home you@work"
+    (3, RegexReplace, Misc, "regexreplace"),
     /// Convert a string to UTF-8 bytes
     ///
     /// ex: utf "hello!"
     /// ex: utf "❤️"
     /// You can use [invert] to convert UTF-8 bytes back to a string.
     /// ex: ⍘utf [226 156 168 32 119 111 119 33]
+    /// Converting invalid UTF-8 back is an error.
+    /// Use [fill] with a character to replace invalid sequences instead.
+    /// ex! ⍘utf [104 105 255]
+    /// ex: ⬚@\uFFFD⍘utf [104 105 255]
     ///
     /// [utf] is different from just [add]ing or [subtracting] `@\0`.
     /// Character math can only convert to and from UTF-32.
@@ -1453,6 +1682,21 @@ primitive!(
     (1, Utf, Misc, "utf"),
     /// Convert UTF-8 bytes to a string
     (1, InvUtf, Misc),
+    /// Encode an array as MessagePack bytes
+    ///
+    /// ex: msgpack [1 2 3]
+    /// ex: msgpack "hello"
+    /// Lists of bytes are encoded as binary data.
+    /// ex: msgpack utf "hi"
+    /// Boxed arrays are encoded as MessagePack arrays.
+    /// Rank `2` boxed arrays of string-value pairs are encoded as maps.
+    /// ex: msgpack {"hi" [1 2]}
+    /// ex: msgpack [{"a" 1} {"b" "c"}]
+    /// You can use [invert] to decode MessagePack bytes.
+    /// ex: ⍘msgpack msgpack [{"a" 1} {"b" "c"}]
+    (1, Msgpack, Misc, "msgpack"),
+    /// Decode MessagePack bytes
+    (1, InvMsgpack, Misc),
     /// Extract a named function from a module
     ///
     /// Can be used after [&i].
@@ -1473,6 +1717,7 @@ primitive!(
     /// `0` indicates a number array.
     /// `1` indicates a character array.
     /// `2` indicates a function array.
+    /// `3` indicates a complex array.
     /// ex: type 5
     /// ex: type "hello"
     /// ex: type (+)
@@ -1491,12 +1736,19 @@ primitive!(
     /// ex: sig (|3 /∘)
     /// ex: sig +_-_×_÷
     (1, Sig, Misc, "sig"),
-    /// Get the current time in seconds
+    /// Get the current time in seconds since the Unix epoch
     ///
     /// ex: now
     /// [under][now] can be used to time a function.
     /// ex: ⍜now(5&sl1)
+    /// For timing, [monotonic] is more reliable, as it is not affected by changes to the system clock.
     (0, Now, Misc, "now"),
+    /// Get the value of a monotonic clock in seconds
+    ///
+    /// Only differences between values are meaningful.
+    /// The value never decreases, so it is useful for benchmarking.
+    /// ex: ⍜monotonic(5&sl1)
+    (0, Monotonic, Misc, "monotonic"),
     /// The number of radians in a quarter circle
     ///
     /// Equivalent to `divide``2``pi` or `divide``4``tau`
@@ -4,12 +4,10 @@
 
 mod defs;
 pub use defs::*;
-use ecow::EcoVec;
 
 use std::{
     borrow::Cow,
     cell::RefCell,
-    collections::HashMap,
     f64::{
         consts::{PI, TAU},
         INFINITY,
@@ -17,20 +15,25 @@ use std::{
     fmt::{self},
     sync::{
         atomic::{self, AtomicUsize},
-        Arc, OnceLock,
+        OnceLock,
     },
 };
 
 use enum_iterator::{all, Sequence};
 use once_cell::sync::Lazy;
 use rand::prelude::*;
-use regex::Regex;
+#[cfg(feature = "regex")]
+use {
+    ecow::EcoVec,
+    regex::Regex,
+    std::{collections::HashMap, sync::Arc},
+};
 
 use crate::{
     algorithm::{fork, loops, reduce, table, zip},
     array::Array,
     cowslice::cowslice,
-    function::Function,
+    function::{Function, Signature},
     grid_fmt::GridFmt,
     lex::AsciiToken,
     run::FunctionArg,
@@ -70,6 +73,23 @@ impl PrimClass {
     }
 }
 
+/// The broad kind of a primitive, determined by its arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrimCategory {
+    /// A function that takes no arguments
+    Noadic,
+    /// A function that takes one argument
+    Monadic,
+    /// A function that takes two arguments
+    Dyadic,
+    /// A function that takes three or more arguments
+    Polyadic,
+    /// A modifier that takes functions as operands
+    Modifier,
+    /// A primitive with a variable or context-dependent signature
+    Other,
+}
+
 /// The names of a primitive
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PrimNames {
@@ -121,6 +141,7 @@ impl fmt::Display for Primitive {
                 InverseBits => write!(f, "⍘{Bits}"),
                 InvTrace => write!(f, "⍘{Trace}"),
                 InvWhere => write!(f, "⍘{Where}"),
+                InvMsgpack => write!(f, "⍘{Msgpack}"),
                 Uncouple => write!(f, "⍘{Couple}"),
                 Untake => write!(f, "⍘{Take}"),
                 Undrop => write!(f, "⍘{Drop}"),
@@ -152,6 +173,34 @@ impl Primitive {
     pub fn glyph(&self) -> Option<char> {
         self.names().and_then(|n| n.glyph)
     }
+    /// Get the ASCII spelling of the primitive, if it has one
+    pub fn ascii_name(&self) -> Option<&'static str> {
+        self.ascii().map(|token| token.as_str())
+    }
+    /// Get the signature of the primitive as a function
+    ///
+    /// Returns `None` for modifiers and primitives whose signature depends on context
+    pub fn signature(&self) -> Option<Signature> {
+        if self.is_modifier() {
+            return None;
+        }
+        let args = self.args()?;
+        let outputs = self.outputs()?;
+        Some(Signature::new(args as usize, outputs as usize))
+    }
+    /// Get the broad kind of the primitive
+    pub fn category(&self) -> PrimCategory {
+        if self.is_modifier() {
+            return PrimCategory::Modifier;
+        }
+        match self.signature().map(|sig| sig.args) {
+            Some(0) => PrimCategory::Noadic,
+            Some(1) => PrimCategory::Monadic,
+            Some(2) => PrimCategory::Dyadic,
+            Some(_) => PrimCategory::Polyadic,
+            None => PrimCategory::Other,
+        }
+    }
     /// Find a primitive by its text name
     pub fn from_name(name: &str) -> Option<Self> {
         Self::all().find(|p| p.names().is_some_and(|n| n.text.eq_ignore_ascii_case(name)))
@@ -197,6 +246,8 @@ impl Primitive {
             InvWhere => Where,
             Utf => InvUtf,
             InvUtf => Utf,
+            Msgpack => InvMsgpack,
+            InvMsgpack => Msgpack,
             _ => return None,
         })
     }
@@ -286,6 +337,14 @@ impl Primitive {
             Primitive::Cos => env.monadic_env(Value::cos)?,
             Primitive::Asin => env.monadic_env(Value::asin)?,
             Primitive::Acos => env.monadic_env(Value::acos)?,
+            Primitive::Sinh => env.monadic_env(Value::sinh)?,
+            Primitive::Cosh => env.monadic_env(Value::cosh)?,
+            Primitive::Tanh => env.monadic_env(Value::tanh)?,
+            Primitive::Cbrt => env.monadic_env(Value::cbrt)?,
+            Primitive::Gamma => env.monadic_env(Value::gamma)?,
+            Primitive::Factorial => env.monadic_env(Value::factorial)?,
+            Primitive::Log2 => env.monadic_env(Value::log2)?,
+            Primitive::Log10 => env.monadic_env(Value::log10)?,
             Primitive::Floor => env.monadic_env(Value::floor)?,
             Primitive::Ceil => env.monadic_env(Value::ceil)?,
             Primitive::Round => env.monadic_env(Value::round)?,
@@ -302,9 +361,31 @@ impl Primitive {
             Primitive::Mod => env.dyadic_oo_env(Value::modulus)?,
             Primitive::Pow => env.dyadic_oo_env(Value::pow)?,
             Primitive::Log => env.dyadic_oo_env(Value::log)?,
+            Primitive::Gcd => env.dyadic_oo_env(Value::gcd)?,
+            Primitive::Lcm => env.dyadic_oo_env(Value::lcm)?,
+            Primitive::RoundTo => env.dyadic_oo_env(Value::round_to)?,
+            Primitive::Band => env.dyadic_oo_env(Value::bit_and)?,
+            Primitive::Bor => env.dyadic_oo_env(Value::bit_or)?,
+            Primitive::Bxor => env.dyadic_oo_env(Value::bit_xor)?,
+            Primitive::Bshl => env.dyadic_oo_env(Value::bit_shift_left)?,
+            Primitive::Bshr => env.dyadic_oo_env(Value::bit_shift_right)?,
+            Primitive::Bnot => env.monadic_env(|value, env| value.bit_not(None, env))?,
             Primitive::Min => env.dyadic_oo_env(Value::min)?,
             Primitive::Max => env.dyadic_oo_env(Value::max)?,
+            Primitive::Clamp => {
+                let low = env.pop(1)?;
+                let high = env.pop(2)?;
+                let value = env.pop(3)?;
+                env.push(value.clamp(low, high, env)?);
+            }
+            Primitive::IfElse => {
+                let mask = env.pop(1)?;
+                let if_true = env.pop(2)?;
+                let if_false = env.pop(3)?;
+                env.push(mask.if_else(if_true, if_false, env)?);
+            }
             Primitive::Atan => env.dyadic_oo_env(Value::atan2)?,
+            Primitive::Hypot => env.dyadic_oo_env(Value::hypot)?,
             Primitive::Match => env.dyadic_rr(|a, b| a == b)?,
             Primitive::Join => env.dyadic_oo_env(Value::join)?,
             Primitive::Transpose => env.monadic_mut(Value::transpose)?,
@@ -355,10 +436,16 @@ impl Primitive {
                 env.push(from.unselect(index, into, env)?);
             }
             Primitive::Windows => env.dyadic_rr_env(Value::windows)?,
+            Primitive::Dot => env.dyadic_rr_env(Value::dot)?,
+            Primitive::Interleave => env.dyadic_oo_env(Value::interleave)?,
+            Primitive::Bisect => env.dyadic_rr_env(|a, b, env| a.bisect(b, false, env))?,
+            Primitive::BisectRight => env.dyadic_rr_env(|a, b, env| a.bisect(b, true, env))?,
+            Primitive::Quantile => env.dyadic_rr_env(Value::quantile)?,
             Primitive::Where => env.monadic_ref_env(Value::wher)?,
             Primitive::InvWhere => env.monadic_ref_env(Value::inverse_where)?,
             Primitive::Classify => env.monadic_ref_env(Value::classify)?,
             Primitive::Deduplicate => env.monadic_mut(Value::deduplicate)?,
+            Primitive::Norm => env.monadic_ref_env(Value::norm)?,
             Primitive::Member => env.dyadic_rr_env(Value::member)?,
             Primitive::Find => env.dyadic_rr_env(Value::find)?,
             Primitive::IndexOf => env.dyadic_rr_env(Value::index_of)?,
@@ -381,6 +468,8 @@ impl Primitive {
             Primitive::Parse => env.monadic_ref_env(Value::parse_num)?,
             Primitive::Utf => env.monadic_ref_env(Value::utf8)?,
             Primitive::InvUtf => env.monadic_ref_env(Value::inv_utf8)?,
+            Primitive::Msgpack => env.monadic_ref_env(Value::msgpack_encode)?,
+            Primitive::InvMsgpack => env.monadic_ref_env(Value::msgpack_decode)?,
             Primitive::Range => env.monadic_ref_env(Value::range)?,
             Primitive::Reverse => env.monadic_mut(Value::reverse)?,
             Primitive::Deshape => env.monadic_mut(Value::deshape)?,
@@ -392,13 +481,22 @@ impl Primitive {
                     Array::row_count,
                     Array::row_count,
                     Array::row_count,
+                    Array::row_count,
+                    Array::row_count,
                 )
             })?,
             Primitive::Shape => env.monadic_ref(|v| {
-                v.generic_ref_shallow(Array::shape, Array::shape, Array::shape, Array::shape)
-                    .iter()
-                    .copied()
-                    .collect::<Value>()
+                v.generic_ref_shallow(
+                    Array::shape,
+                    Array::shape,
+                    Array::shape,
+                    Array::shape,
+                    Array::shape,
+                    Array::shape,
+                )
+                .iter()
+                .copied()
+                .collect::<Value>()
             })?,
             Primitive::Bits => env.monadic_ref_env(Value::bits)?,
             Primitive::InverseBits => env.monadic_ref_env(Value::inverse_bits)?,
@@ -417,7 +515,7 @@ impl Primitive {
             Primitive::Reshape => {
                 let shape = env.pop(1)?;
                 let mut array = env.pop(2)?;
-                array.reshape(&shape, env)?;
+                array.reshape_by(&shape, env)?;
                 env.push(array);
             }
             Primitive::Break => {
@@ -517,10 +615,8 @@ impl Primitive {
                 }
             }
             Primitive::Rand => {
-                thread_local! {
-                    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(instant::now().to_bits()));
-                }
-                env.push(RNG.with(|rng| rng.borrow_mut().gen::<f64>()));
+                let n = with_rng(env, |rng| rng.gen::<f64>());
+                env.push(n);
             }
             Primitive::Gen => {
                 let seed = env.pop(1)?;
@@ -538,6 +634,40 @@ impl Primitive {
                 rows.shuffle(&mut SmallRng::seed_from_u64(seed));
                 env.push(Value::from_row_values_infallible(rows));
             }
+            Primitive::Shuffle => {
+                let arr = env.pop(1)?;
+                if arr.row_count() <= 1 {
+                    env.push(arr);
+                } else {
+                    let mut rows: Vec<Value> = arr.into_rows().collect();
+                    with_rng(env, |rng| rows.shuffle(rng));
+                    env.push(Value::from_row_values_infallible(rows));
+                }
+            }
+            Primitive::Sample => {
+                let count = env
+                    .pop(1)?
+                    .as_nat(env, "Sample count must be a natural number")?;
+                let arr = env.pop(2)?;
+                if arr.rank() == 0 {
+                    return Err(env.error("Cannot sample from a scalar"));
+                }
+                if count > arr.row_count() {
+                    return Err(env.error(format!(
+                        "Cannot sample {count} rows from an array with {} rows",
+                        arr.row_count()
+                    )));
+                }
+                if count == 0 {
+                    env.push(arr.first_dim_zero());
+                } else {
+                    let indices = with_rng(env, |rng| {
+                        rand::seq::index::sample(rng, arr.row_count(), count)
+                    });
+                    let rows = indices.iter().map(|i| arr.row(i));
+                    env.push(Value::from_row_values_infallible(rows));
+                }
+            }
             Primitive::Use => {
                 let name = env.pop(1)?.as_string(env, "Use name must be a string")?;
                 let lib = env.pop(2)?;
@@ -555,9 +685,10 @@ impl Primitive {
             Primitive::Type => {
                 let val = env.pop(1)?;
                 env.push(match val {
-                    Value::Num(_) | Value::Byte(_) => 0,
+                    Value::Num(_) | Value::Int(_) | Value::Byte(_) => 0,
                     Value::Char(_) => 1,
                     Value::Func(_) => 2,
+                    Value::Complex(_) => 3,
                 });
             }
             Primitive::Sig => {
@@ -571,45 +702,90 @@ impl Primitive {
                 let handle = env.spawn(f.signature().args, |env| env.call(f))?;
                 env.push(handle);
             }
+            Primitive::Memo => {
+                let f = env.pop("memoized function")?;
+                env.call_memoized(f)?;
+            }
             Primitive::Wait => {
                 let handle = env.pop(1)?;
                 env.wait(handle)?;
             }
-            Primitive::Now => env.push(instant::now() / 1000.0),
+            Primitive::Now => env.push(env.now()),
+            Primitive::Monotonic => {
+                let time = env.monotonic();
+                env.push(time)
+            }
             Primitive::Trace => trace(env, false)?,
             Primitive::InvTrace => trace(env, true)?,
             Primitive::Dump => dump(env)?,
             Primitive::Sys(io) => io.run(env)?,
-            Primitive::Regex => {
-                thread_local! {
-                    pub static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
-                }
-                let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
-                let matching = env
-                    .pop(1)?
-                    .as_string(env, "Matching target must be a string")?;
-                REGEX_CACHE.with(|cache| -> UiuaResult {
-                    let mut cache = cache.borrow_mut();
-                    let regex = if let Some(regex) = cache.get(&pattern) {
-                        regex
-                    } else {
-                        let regex = Regex::new(&pattern)
-                            .map_err(|e| env.error(format!("Invalid pattern: {}", e)))?;
-                        cache.entry(pattern.clone()).or_insert(regex.clone())
-                    };
-                    let matches: EcoVec<Arc<Function>> = regex
-                        .find_iter(&matching)
-                        .map(|m| Function::boxed(m.as_str()).into())
-                        .collect();
-                    env.push(matches);
-                    Ok(())
-                })?
+            Primitive::Regex | Primitive::RegexMatch | Primitive::RegexReplace => {
+                regex(*self, env)?
             }
         }
         Ok(())
     }
 }
 
+/// Call a function with the seeded random number generator if there is one,
+/// or with a thread-local generator otherwise
+fn with_rng<T>(env: &mut Uiua, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    thread_local! {
+        static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(instant::now().to_bits()));
+    }
+    match &mut env.rng {
+        Some(rng) => f(rng),
+        None => RNG.with(|rng| f(&mut *rng.borrow_mut())),
+    }
+}
+
+#[cfg(feature = "regex")]
+fn regex(prim: Primitive, env: &mut Uiua) -> UiuaResult {
+    thread_local! {
+        pub static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+    }
+    let pattern = env.pop(1)?.as_string(env, "Pattern must be a string")?;
+    let replacement = if prim == Primitive::RegexReplace {
+        Some(env.pop(2)?.as_string(env, "Replacement must be a string")?)
+    } else {
+        None
+    };
+    let matching = env
+        .pop(1)?
+        .as_string(env, "Matching target must be a string")?;
+    REGEX_CACHE.with(|cache| -> UiuaResult {
+        let mut cache = cache.borrow_mut();
+        let regex = if let Some(regex) = cache.get(&pattern) {
+            regex
+        } else {
+            let regex =
+                Regex::new(&pattern).map_err(|e| env.error(format!("Invalid pattern: {}", e)))?;
+            cache.entry(pattern.clone()).or_insert(regex.clone())
+        };
+        match (prim, replacement) {
+            (Primitive::RegexMatch, _) => env.push(regex.is_match(&matching)),
+            (Primitive::RegexReplace, Some(replacement)) => env.push(
+                regex
+                    .replace_all(&matching, replacement.as_str())
+                    .into_owned(),
+            ),
+            _ => {
+                let matches: EcoVec<Arc<Function>> = regex
+                    .find_iter(&matching)
+                    .map(|m| Function::boxed(m.as_str()).into())
+                    .collect();
+                env.push(matches);
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(not(feature = "regex"))]
+fn regex(_: Primitive, env: &mut Uiua) -> UiuaResult {
+    Err(env.error("Regular expressions are not supported in this environment"))
+}
+
 fn trace(env: &mut Uiua, inverse: bool) -> UiuaResult {
     let val = env.pop(1)?;
     let span: String = if inverse {
@@ -965,6 +1141,26 @@ fn parse_doc_line_fragments(line: &str) -> Vec<PrimDocFragment> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn metadata() {
+        let all: Vec<Primitive> = Primitive::all().collect();
+        assert_eq!(all, Primitive::all().collect::<Vec<_>>());
+        let by_class: usize = PrimClass::all()
+            .map(|class| class.primitives().count())
+            .sum();
+        assert_eq!(all.len(), by_class);
+        assert_eq!(Primitive::Add.signature(), Some(Signature::new(2, 1)));
+        assert_eq!(Primitive::Add.signature().unwrap().to_string(), "|2.1");
+        assert_eq!(Primitive::Add.glyph(), Some('+'));
+        assert_eq!(Primitive::Add.category(), PrimCategory::Dyadic);
+        assert_eq!(Primitive::Neg.category(), PrimCategory::Monadic);
+        assert_eq!(Primitive::Pi.category(), PrimCategory::Noadic);
+        assert_eq!(Primitive::Reduce.category(), PrimCategory::Modifier);
+        assert_eq!(Primitive::Reduce.signature(), None);
+        assert_eq!(Primitive::Mul.ascii_name(), Some("*"));
+        assert_eq!(Primitive::Add.ascii_name(), None);
+    }
+
     #[test]
     fn name_collisions() {
         for a in Primitive::all() {
@@ -994,7 +1190,12 @@ mod tests {
                             if !ex.should_error {
                                 panic!("\nExample failed:\n{}\n{}", ex.input, e.show(true));
                             }
-                        } else if let Some(diag) = env.take_diagnostics().into_iter().next() {
+                        } else if let Some(diag) =
+                            (env.take_diagnostics().into_iter()).find(|diag| {
+                                // Examples that should fail may demonstrate any diagnostic
+                                ex.should_error || diag.kind >= crate::DiagnosticKind::Warning
+                            })
+                        {
                             if !ex.should_error {
                                 panic!("\nExample failed:\n{}\n{}", ex.input, diag.show(true));
                             }
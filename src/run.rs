@@ -2,15 +2,20 @@ use std::{
     collections::{BTreeSet, HashMap, HashSet},
     fs,
     hash::Hash,
-    mem::take,
+    mem::{swap, take},
     panic::{catch_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
 use instant::Duration;
+use num_complex::Complex64;
 use parking_lot::Mutex;
+use rand::prelude::*;
 
 use crate::{
     array::Array,
@@ -19,10 +24,13 @@ use crate::{
     parse::parse,
     primitive::{Primitive, CONSTANTS},
     value::Value,
-    Diagnostic, DiagnosticKind, Handle, Ident, NativeSys, SysBackend, TraceFrame, UiuaError,
-    UiuaResult,
+    CapturedSys, Diagnostic, DiagnosticKind, Handle, Ident, MemorySys, NativeSys, SysBackend,
+    TraceFrame, UiuaError, UiuaResult,
 };
 
+const DEFAULT_TIMEOUT_CHECK_INTERVAL: u32 = 64;
+const DEFAULT_PARALLEL_MIN_LEN: usize = 10000;
+
 /// The Uiua runtime
 #[derive(Clone)]
 pub struct Uiua {
@@ -46,6 +54,12 @@ pub struct Uiua {
     pub(crate) mode: RunMode,
     /// A limit on the execution duration in milliseconds
     execution_limit: Option<f64>,
+    /// The number of instructions to execute between checks of the timeout
+    timeout_check_interval: u32,
+    /// The number of instructions left until the timeout is checked
+    instrs_until_timeout_check: u32,
+    /// The maximum number of primitive calls per run, and the number that may still be executed
+    step_limit: Option<(u64, Arc<AtomicU64>)>,
     /// The time at which execution started
     execution_start: f64,
     /// The paths of files currently being imported (used to detect import cycles)
@@ -54,6 +68,8 @@ pub struct Uiua {
     imports: Arc<Mutex<HashMap<PathBuf, Vec<Value>>>>,
     /// Accumulated diagnostics
     pub(crate) diagnostics: BTreeSet<Diagnostic>,
+    /// Diagnostics emitted while running, where the runtime is only borrowed immutably
+    runtime_diagnostics: Arc<Mutex<Vec<Diagnostic>>>,
     /// Print diagnostics as they are encountered
     pub(crate) print_diagnostics: bool,
     /// Whether to print the time taken to execute each instruction
@@ -66,6 +82,24 @@ pub struct Uiua {
     cli_file_path: PathBuf,
     /// The system backend
     pub(crate) backend: Arc<dyn SysBackend>,
+    /// The seeded random number generator, if any
+    pub(crate) rng: Option<StdRng>,
+    /// A fixed time returned by [`Primitive::Now`], if any
+    fixed_clock: Option<f64>,
+    /// The last value read from the monotonic clock
+    last_monotonic: f64,
+    /// Cached results of [`Primitive::Memo`] calls
+    memo: Arc<Mutex<MemoCache>>,
+    /// Whether self-recursive calls in tail position reuse the current frame
+    tail_calls: bool,
+    /// The minimum number of elements for which pervasive operations run in parallel
+    parallel_min_len: usize,
+    /// Whether dyadic pervasive operations broadcast axes of length 1
+    broadcasting: bool,
+    /// The maximum number of nested function calls, if any
+    max_call_depth: Option<usize>,
+    /// Primitive call statistics, if profiling
+    profile: Option<Arc<Mutex<Profile>>>,
 }
 
 #[derive(Clone)]
@@ -106,6 +140,7 @@ impl Default for Scope {
 #[derive(Default, Clone)]
 struct Fills {
     nums: Vec<f64>,
+    complexes: Vec<Complex64>,
     chars: Vec<char>,
     functions: Vec<Arc<Function>>,
 }
@@ -122,6 +157,70 @@ struct StackFrame {
     spans: Vec<(usize, Option<Primitive>)>,
 }
 
+/// A program compiled by [`Uiua::compile_all`]
+#[derive(Clone, Default)]
+pub struct Compiled {
+    /// The instructions of each top-level line
+    lines: Vec<Vec<Instr>>,
+}
+
+/// A snapshot of the interpreter state created by [`Uiua::snapshot`]
+///
+/// Values share their data with the interpreter, so snapshots are cheap to create.
+#[derive(Clone)]
+pub struct UiuaState {
+    stack: Vec<Value>,
+    globals: Vec<Value>,
+    scope: Scope,
+    higher_scopes: Vec<Scope>,
+}
+
+/// The call count and total time in milliseconds of each primitive
+type Profile = HashMap<Primitive, (u64, f64)>;
+
+/// A cache of function results keyed by the function and its arguments
+#[derive(Default)]
+struct MemoCache {
+    entries: HashMap<Vec<Value>, MemoEntry>,
+    /// The maximum number of entries, if any
+    capacity: Option<usize>,
+    /// Incremented on every access to track recency
+    tick: u64,
+}
+
+struct MemoEntry {
+    outputs: Vec<Value>,
+    last_used: u64,
+}
+
+impl MemoCache {
+    fn get(&mut self, key: &[Value]) -> Option<Vec<Value>> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.tick;
+        Some(entry.outputs.clone())
+    }
+    fn insert(&mut self, key: Vec<Value>, outputs: Vec<Value>) {
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                return;
+            }
+            // Evict the least recently used entry
+            if self.entries.len() >= capacity && !self.entries.contains_key(&key) {
+                let oldest = (self.entries.iter())
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.tick += 1;
+        let last_used = self.tick;
+        self.entries.insert(key, MemoEntry { outputs, last_used });
+    }
+}
+
 impl Default for Uiua {
     fn default() -> Self {
         Self::with_native_sys()
@@ -176,6 +275,7 @@ impl Uiua {
             imports: Arc::new(Mutex::new(HashMap::new())),
             mode: RunMode::Normal,
             diagnostics: BTreeSet::new(),
+            runtime_diagnostics: Arc::new(Mutex::new(Vec::new())),
             backend: Arc::new(NativeSys),
             print_diagnostics: false,
             time_instrs: false,
@@ -183,7 +283,19 @@ impl Uiua {
             cli_arguments: Vec::new(),
             cli_file_path: PathBuf::new(),
             execution_limit: None,
+            timeout_check_interval: DEFAULT_TIMEOUT_CHECK_INTERVAL,
+            instrs_until_timeout_check: 0,
+            step_limit: None,
             execution_start: 0.0,
+            rng: None,
+            fixed_clock: None,
+            last_monotonic: 0.0,
+            memo: Arc::new(Mutex::new(MemoCache::default())),
+            tail_calls: true,
+            parallel_min_len: DEFAULT_PARALLEL_MIN_LEN,
+            broadcasting: false,
+            max_call_depth: None,
+            profile: None,
         }
     }
     /// Create a new Uiua runtime with a custom IO backend
@@ -193,6 +305,12 @@ impl Uiua {
             ..Default::default()
         }
     }
+    /// Create a new Uiua runtime that writes stdout to the given buffer
+    ///
+    /// See [`CapturedSys`] for more control over stderr and stdin.
+    pub fn with_captured_output(stdout: Arc<Mutex<Vec<u8>>>) -> Self {
+        Self::with_backend(CapturedSys::new(stdout))
+    }
     pub fn backend(&self) -> &dyn SysBackend {
         &*self.backend
     }
@@ -208,10 +326,129 @@ impl Uiua {
         self
     }
     /// Limit the execution duration
+    ///
+    /// Exceeding it returns a [`UiuaError::Timeout`].
     pub fn with_execution_limit(mut self, limit: Duration) -> Self {
         self.execution_limit = Some(limit.as_millis() as f64);
         self
     }
+    /// Set how many instructions are executed between checks of the timeout
+    ///
+    /// Higher values reduce the overhead of reading the clock,
+    /// but let execution overshoot the timeout by more.
+    pub fn with_timeout_check_interval(mut self, interval: u32) -> Self {
+        self.timeout_check_interval = interval.max(1);
+        self
+    }
+    /// Limit the number of primitive calls that may be executed
+    ///
+    /// Calls in nested functions and spawned threads count toward the same limit.
+    /// The limit applies to each load or run separately.
+    /// Exceeding it returns a [`UiuaError::ExecutionLimit`].
+    pub fn with_step_limit(mut self, steps: u64) -> Self {
+        self.step_limit = Some((steps, Arc::new(AtomicU64::new(steps))));
+        self
+    }
+    /// Limit the number of nested function calls
+    ///
+    /// Calls in tail position that reuse the current frame do not count.
+    /// Exceeding the limit returns a [`UiuaError::CallDepthExceeded`].
+    pub fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = Some(depth);
+        self
+    }
+    /// Record how many times each primitive is called and how long it takes
+    ///
+    /// The results are available from [`Uiua::profile_report`].
+    /// Calls in spawned threads are recorded as well.
+    /// This is disabled by default.
+    pub fn with_profiling(mut self, enabled: bool) -> Self {
+        self.profile = enabled.then(Default::default);
+        self
+    }
+    /// Get the call count and total time of each primitive called since profiling was enabled
+    ///
+    /// The time of a primitive includes the time of any functions it calls.
+    /// Entries are sorted by time, longest first.
+    /// The report is empty if profiling is disabled.
+    pub fn profile_report(&self) -> Vec<(Primitive, u64, Duration)> {
+        let Some(profile) = &self.profile else {
+            return Vec::new();
+        };
+        let mut report: Vec<_> = (profile.lock().iter())
+            .map(|(&prim, &(count, ms))| (prim, count, Duration::from_secs_f64(ms / 1000.0)))
+            .collect();
+        report.sort_by(|(a, _, a_time), (b, _, b_time)| b_time.cmp(a_time).then(a.cmp(b)));
+        report
+    }
+    /// Use a seeded random number generator
+    ///
+    /// This makes [`Primitive::Rand`] deterministic.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Some(StdRng::seed_from_u64(seed));
+        self
+    }
+    /// Make [`Primitive::Now`] always return the given number of seconds since the Unix epoch
+    pub fn with_fixed_clock(mut self, seconds: f64) -> Self {
+        self.fixed_clock = Some(seconds);
+        self
+    }
+    /// Enable or disable tail call optimization
+    ///
+    /// When enabled, a [`Primitive::Recur`] of `1` that is the last instruction of a function
+    /// restarts the current function instead of calling it again, so it does not grow the stack.
+    /// Whether a call is in tail position is checked when it is executed rather than when
+    /// the function is compiled, which costs only a comparison of the instruction index.
+    /// This is enabled by default. Disabling it keeps every recursive call in error traces.
+    pub fn with_tail_calls(mut self, enabled: bool) -> Self {
+        self.tail_calls = enabled;
+        self
+    }
+    /// Set the minimum number of elements for which pervasive operations run in parallel
+    ///
+    /// Smaller arrays are processed on the current thread. The default is 10000.
+    /// Pass `usize::MAX` to disable parallelism entirely.
+    pub fn with_parallel_min_len(mut self, min_len: usize) -> Self {
+        self.parallel_min_len = min_len;
+        self
+    }
+    /// Get the minimum number of elements for which pervasive operations run in parallel
+    pub fn parallel_min_len(&self) -> usize {
+        self.parallel_min_len
+    }
+    /// Enable or disable NumPy-style broadcasting for dyadic pervasive operations
+    ///
+    /// When enabled, arrays whose shapes do not match by prefix are aligned at their last axes,
+    /// and axes of length 1 are repeated to match the other array. For example, adding arrays
+    /// of shape `[3 1]` and `[1 4]` gives an array of shape `[3 4]`.
+    /// This is disabled by default.
+    pub fn with_broadcasting(mut self, enabled: bool) -> Self {
+        self.broadcasting = enabled;
+        self
+    }
+    /// Check whether broadcasting is enabled for dyadic pervasive operations
+    pub fn broadcasting(&self) -> bool {
+        self.broadcasting
+    }
+    /// Limit the number of results cached by [`Primitive::Memo`]
+    ///
+    /// When the cache is full, the least recently used result is evicted.
+    /// By default, the cache is unbounded.
+    pub fn with_memo_capacity(self, capacity: usize) -> Self {
+        self.memo.lock().capacity = Some(capacity);
+        self
+    }
+    /// Get the current time in seconds since the Unix epoch
+    pub(crate) fn now(&self) -> f64 {
+        self.fixed_clock.unwrap_or_else(|| self.backend.now())
+    }
+    /// Read the backend's monotonic clock in seconds
+    ///
+    /// The result never decreases across calls, even if the backend's clock does.
+    pub(crate) fn monotonic(&mut self) -> f64 {
+        self.last_monotonic = self.last_monotonic.max(self.backend.monotonic());
+        self.last_monotonic
+    }
     /// Set the [`RunMode`]
     ///
     /// Default is [`RunMode::Normal`]
@@ -255,6 +492,16 @@ impl Uiua {
     pub fn load_str_path<P: AsRef<Path>>(&mut self, input: &str, path: P) -> UiuaResult {
         self.load_impl(input, Some(path.as_ref()))
     }
+    /// Run a Uiua program in a fresh runtime and return the final stack
+    ///
+    /// The program uses a [`MemorySys`] backend, so it cannot touch the real filesystem,
+    /// environment, or standard streams. Because the runtime shares nothing with any
+    /// other, this may be called from many threads at once.
+    pub fn run_isolated(input: &str) -> UiuaResult<Vec<Value>> {
+        let mut env = Uiua::with_backend(MemorySys::default());
+        env.load_str(input)?;
+        Ok(env.take_stack())
+    }
     /// Run in a scoped context. Names defined in this context will be removed when the scope ends.
     ///
     /// While names defined in this context will be removed when the scope ends, values *bound* to
@@ -274,8 +521,39 @@ impl Uiua {
         self.scope = self.higher_scopes.pop().unwrap();
         Ok(self.stack.split_off(start_height.min(end_height)))
     }
+    /// Compile a Uiua program without running it, collecting every error
+    ///
+    /// Parsing and compilation recover at the start of each line, so an error in
+    /// one line does not prevent errors in later lines from being reported.
+    /// Each error has its own span.
+    ///
+    /// Bindings are still evaluated, as they would be by [`Uiua::load_str`].
+    /// The compiled program is only returned if there are no errors.
+    /// It can be run with [`Uiua::run_compiled`].
+    pub fn compile_all(&mut self, input: &str) -> (Option<Compiled>, Vec<UiuaError>) {
+        self.start_execution();
+        let (items, parse_errors, diagnostics) = parse(input, None);
+        self.diagnostics.extend(diagnostics);
+        let mut errors: Vec<UiuaError> = (parse_errors.into_iter())
+            .map(|error| UiuaError::Parse(vec![error]))
+            .collect();
+        let mut lines = Vec::new();
+        for item in items {
+            self.compile_item(item, false, &mut lines, &mut errors);
+        }
+        let compiled = errors.is_empty().then_some(Compiled { lines });
+        (compiled, errors)
+    }
+    /// Run a program compiled with [`Uiua::compile_all`]
+    pub fn run_compiled(&mut self, compiled: Compiled) -> UiuaResult {
+        self.start_execution();
+        for instrs in compiled.lines {
+            self.exec_global_instrs(instrs)?;
+        }
+        Ok(())
+    }
     fn load_impl(&mut self, input: &str, path: Option<&Path>) -> UiuaResult {
-        self.execution_start = instant::now();
+        self.start_execution();
         let (items, errors, diagnostics) = parse(input, path);
         if self.print_diagnostics {
             for diagnostic in diagnostics {
@@ -308,6 +586,7 @@ code:
         if let Some(path) = path {
             self.current_imports.lock().remove(path);
         }
+        self.collect_runtime_diagnostics();
         res
     }
     fn trace_error(&self, mut error: UiuaError, frame: StackFrame) -> UiuaError {
@@ -359,6 +638,12 @@ code:
     }
     fn exec(&mut self, frame: StackFrame) -> UiuaResult {
         let ret_height = self.scope.call.len();
+        // The bottom frame is the top-level code
+        if let Some(max) = self.max_call_depth {
+            if ret_height > max {
+                return Err(UiuaError::CallDepthExceeded(max, self.span()));
+            }
+        }
         self.scope.call.push(frame);
         let mut formatted_instr = String::new();
         while self.scope.call.len() > ret_height {
@@ -385,11 +670,46 @@ code:
                 formatted_instr = format!("{instr:?}");
                 self.last_time = instant::now();
             }
+            // Nothing happens after a recursive call at the end of a function,
+            // so the function can be restarted instead of called again
+            let in_tail_position = self.tail_calls && frame.pc + 1 == frame.function.instrs.len();
+            let mut restart = false;
             let res = match instr {
+                &Instr::Prim(Primitive::Recur, span) if in_tail_position => {
+                    self.push_span(span, Some(Primitive::Recur));
+                    let res = self.consume_step().and_then(|_| {
+                        let n = self
+                            .pop(1)?
+                            .as_nat(self, "Recur expects a natural number")?;
+                        if n == 1 {
+                            restart = true;
+                            Ok(())
+                        } else {
+                            self.recur(n)
+                        }
+                    });
+                    self.pop_span();
+                    res
+                }
                 &Instr::Prim(prim, span) => {
                     self.push_span(span, Some(prim));
-                    let res = prim.run(self);
-                    self.pop_span();
+                    let res = if let Some(profile) = self.profile.clone() {
+                        let start = instant::now();
+                        let res = self.consume_step().and_then(|_| prim.run(self));
+                        let elapsed = instant::now() - start;
+                        let mut profile = profile.lock();
+                        let (count, ms) = profile.entry(prim).or_default();
+                        *count += 1;
+                        *ms += elapsed;
+                        res
+                    } else {
+                        self.consume_step().and_then(|_| prim.run(self))
+                    };
+                    // Keep the span if the error came from a function the primitive called,
+                    // so that the primitive appears in the trace
+                    if !matches!(res, Err(UiuaError::Traced { .. })) {
+                        self.pop_span();
+                    }
                     res
                 }
                 Instr::Push(val) => {
@@ -523,16 +843,46 @@ code:
                 return Err(err);
             } else {
                 // Go to next instruction
-                self.scope.call.last_mut().unwrap().pc += 1;
+                let frame = self.scope.call.last_mut().unwrap();
+                if restart {
+                    frame.pc = 0;
+                    frame.spans.clear();
+                } else {
+                    frame.pc += 1;
+                }
                 if let Some(limit) = self.execution_limit {
-                    if instant::now() - self.execution_start > limit {
-                        return Err(UiuaError::Timeout(self.span()));
+                    if self.instrs_until_timeout_check > 0 {
+                        self.instrs_until_timeout_check -= 1;
+                    } else {
+                        self.instrs_until_timeout_check = self.timeout_check_interval - 1;
+                        if instant::now() - self.execution_start > limit {
+                            return Err(UiuaError::Timeout(self.span()));
+                        }
                     }
                 }
             }
         }
         Ok(())
     }
+    /// Reset the execution time and step limits at the start of a run
+    fn start_execution(&mut self) {
+        self.execution_start = instant::now();
+        self.instrs_until_timeout_check = 0;
+        if let Some((limit, remaining)) = &self.step_limit {
+            remaining.store(*limit, Ordering::Relaxed);
+        }
+    }
+    /// Use up one step of the step limit
+    fn consume_step(&self) -> UiuaResult {
+        if let Some((_, remaining)) = &self.step_limit {
+            let update =
+                remaining.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+            if update.is_err() {
+                return Err(UiuaError::ExecutionLimit(self.span()));
+            }
+        }
+        Ok(())
+    }
     pub(crate) fn push_span(&mut self, span: usize, prim: Option<Primitive>) {
         self.scope.call.last_mut().unwrap().spans.push((span, prim));
     }
@@ -584,6 +934,32 @@ code:
         let f = self.scope.call[self.scope.call.len() - n].function.clone();
         self.call_function(f)
     }
+    /// Call a function, reusing its outputs if it was already called with the same arguments
+    ///
+    /// Arguments that contain unboxed functions bypass the cache.
+    pub(crate) fn call_memoized(&mut self, f: Value) -> UiuaResult {
+        let args = f.signature().args;
+        let mut key = Vec::with_capacity(args + 1);
+        key.push(f.clone());
+        for i in 0..args {
+            key.push(self.pop(i + 1)?);
+        }
+        let cacheable = !key[1..].iter().any(contains_function);
+        if cacheable {
+            if let Some(outputs) = self.memo.lock().get(&key) {
+                self.stack.extend(outputs);
+                return Ok(());
+            }
+        }
+        self.stack.extend(key[1..].iter().rev().cloned());
+        let height = self.stack.len() - args;
+        self.call(f)?;
+        if cacheable {
+            let outputs = self.stack.get(height..).unwrap_or_default().to_vec();
+            self.memo.lock().insert(key, outputs);
+        }
+        Ok(())
+    }
     pub fn call_catch_break(&mut self, f: Value) -> UiuaResult<bool> {
         match self.call(f) {
             Ok(_) => Ok(false),
@@ -632,6 +1008,19 @@ code:
         self.diagnostics
             .insert(Diagnostic::new(message.into(), self.span(), kind));
     }
+    /// Emit a diagnostic from code that only has shared access to the runtime
+    pub(crate) fn runtime_diagnostic(&self, message: impl Into<String>, kind: DiagnosticKind) {
+        let diagnostic = Diagnostic::new(message.into(), self.span(), kind);
+        if self.print_diagnostics {
+            eprintln!("{}", diagnostic.show(true));
+        } else {
+            self.runtime_diagnostics.lock().push(diagnostic);
+        }
+    }
+    fn collect_runtime_diagnostics(&mut self) {
+        let diagnostics = take(&mut *self.runtime_diagnostics.lock());
+        self.diagnostics.extend(diagnostics);
+    }
     /// Pop a value from the stack
     pub fn pop(&mut self, arg: impl StackArg) -> UiuaResult<Value> {
         let res = self.stack.pop().ok_or_else(|| {
@@ -655,10 +1044,66 @@ code:
     pub fn push(&mut self, val: impl Into<Value>) {
         self.stack.push(val.into());
     }
+    /// Get the entire stack
+    ///
+    /// The top of the stack is the last element
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
     /// Take the entire stack
     pub fn take_stack(&mut self) -> Vec<Value> {
         take(&mut self.stack)
     }
+    /// Capture the stack, bindings, and fills so they can be restored later
+    ///
+    /// Continuing execution does not affect the snapshot.
+    pub fn snapshot(&self) -> UiuaState {
+        UiuaState {
+            stack: self.stack.clone(),
+            globals: self.globals.lock().clone(),
+            scope: self.scope.clone(),
+            higher_scopes: self.higher_scopes.clone(),
+        }
+    }
+    /// Restore a state captured with [`Uiua::snapshot`]
+    ///
+    /// This discards any partially-executed state, so it is safe to call after an error.
+    pub fn restore(&mut self, state: UiuaState) {
+        self.stack = state.stack;
+        *self.globals.lock() = state.globals;
+        self.scope = state.scope;
+        self.higher_scopes = state.higher_scopes;
+        self.new_functions.clear();
+        self.inline_stack.clear();
+        self.under_stack.clear();
+    }
+    /// Call a top-level binding by name
+    ///
+    /// Function bindings are called on the current stack.
+    /// Other bindings have their value pushed.
+    pub fn call_binding(&mut self, name: &str) -> UiuaResult {
+        let Some(&idx) = self.scope.names.get(name) else {
+            let lower = name.to_lowercase();
+            let max_distance = (name.chars().count() / 3).max(1);
+            let mut close: Vec<(usize, &Ident)> = (self.scope.names.keys())
+                .filter_map(|known| {
+                    let distance = edit_distance(&known.to_lowercase(), &lower);
+                    (distance <= max_distance).then_some((distance, known))
+                })
+                .collect();
+            close.sort();
+            let mut message = format!("Unknown binding `{name}`");
+            if !close.is_empty() {
+                let suggestions: Vec<String> = (close.iter().take(3))
+                    .map(|(_, known)| format!("`{known}`"))
+                    .collect();
+                message.push_str(&format!(". Did you mean {}?", suggestions.join(", ")));
+            }
+            return Err(self.error(message));
+        };
+        let value = self.globals.lock()[idx].clone();
+        self.call(value)
+    }
     /// Get the values for all bindings in the current scope
     pub fn all_bindings_in_scope(&self) -> HashMap<Ident, Value> {
         let mut bindings = HashMap::new();
@@ -674,11 +1119,24 @@ code:
         &self.diagnostics
     }
     pub fn diagnostics_mut(&mut self) -> &mut BTreeSet<Diagnostic> {
+        self.collect_runtime_diagnostics();
         &mut self.diagnostics
     }
     pub fn take_diagnostics(&mut self) -> BTreeSet<Diagnostic> {
+        self.collect_runtime_diagnostics();
         take(&mut self.diagnostics)
     }
+    /// Take the diagnostics that are at least as severe as the given kind
+    ///
+    /// Less severe diagnostics are kept.
+    pub fn take_diagnostics_at_least(&mut self, kind: DiagnosticKind) -> BTreeSet<Diagnostic> {
+        self.collect_runtime_diagnostics();
+        let (taken, kept) = take(&mut self.diagnostics)
+            .into_iter()
+            .partition(|diagnostic| diagnostic.kind >= kind);
+        self.diagnostics = kept;
+        taken
+    }
     pub fn clone_stack_top(&self, n: usize) -> Vec<Value> {
         self.stack.iter().rev().take(n).rev().cloned().collect()
     }
@@ -751,10 +1209,17 @@ code:
     pub(crate) fn num_fill(&self) -> Option<f64> {
         self.scope.fills.nums.last().copied()
     }
+    pub(crate) fn int_fill(&self) -> Option<i64> {
+        let n = self.scope.fills.nums.last().copied()?;
+        (n.fract() == 0.0 && n.abs() <= i64::MAX as f64).then_some(n as i64)
+    }
     pub(crate) fn byte_fill(&self) -> Option<u8> {
         let n = self.scope.fills.nums.last().copied()?;
         (n.fract() == 0.0 && (0.0..=255.0).contains(&n)).then_some(n as u8)
     }
+    pub(crate) fn complex_fill(&self) -> Option<Complex64> {
+        self.scope.fills.complexes.last().copied()
+    }
     pub(crate) fn char_fill(&self) -> Option<char> {
         self.scope.fills.chars.last().copied()
     }
@@ -775,12 +1240,24 @@ code:
                     set = true;
                 }
             }
+            Value::Int(i) => {
+                if let Some(&i) = i.as_scalar() {
+                    self.scope.fills.nums.push(i as f64);
+                    set = true;
+                }
+            }
             Value::Byte(b) => {
                 if let Some(&b) = b.as_scalar() {
                     self.scope.fills.nums.push(b as f64);
                     set = true;
                 }
             }
+            Value::Complex(c) => {
+                if let Some(&c) = c.as_scalar() {
+                    self.scope.fills.complexes.push(c);
+                    set = true;
+                }
+            }
             Value::Char(c) => {
                 if let Some(&c) = c.as_scalar() {
                     self.scope.fills.chars.push(c);
@@ -802,9 +1279,12 @@ code:
         }
         let res = in_ctx(self);
         match fill {
-            Value::Num(_) | Value::Byte(_) => {
+            Value::Num(_) | Value::Int(_) | Value::Byte(_) => {
                 self.scope.fills.nums.pop();
             }
+            Value::Complex(_) => {
+                self.scope.fills.complexes.pop();
+            }
             Value::Char(_) => {
                 self.scope.fills.chars.pop();
             }
@@ -843,6 +1323,7 @@ code:
             current_imports: self.current_imports.clone(),
             imports: self.imports.clone(),
             diagnostics: BTreeSet::new(),
+            runtime_diagnostics: self.runtime_diagnostics.clone(),
             print_diagnostics: self.print_diagnostics,
             time_instrs: self.time_instrs,
             last_time: self.last_time,
@@ -850,7 +1331,21 @@ code:
             cli_file_path: self.cli_file_path.clone(),
             backend: self.backend.clone(),
             execution_limit: self.execution_limit,
+            timeout_check_interval: self.timeout_check_interval,
+            instrs_until_timeout_check: 0,
+            step_limit: self.step_limit.clone(),
             execution_start: self.execution_start,
+            // Seed the thread's generator from this one so that results
+            // don't depend on how the threads are scheduled
+            rng: (self.rng.as_mut()).map(|rng| StdRng::seed_from_u64(rng.gen())),
+            fixed_clock: self.fixed_clock,
+            last_monotonic: self.last_monotonic,
+            memo: self.memo.clone(),
+            tail_calls: self.tail_calls,
+            parallel_min_len: self.parallel_min_len,
+            broadcasting: self.broadcasting,
+            max_call_depth: self.max_call_depth,
+            profile: self.profile.clone(),
         };
         self.backend
             .spawn(env, Box::new(f))
@@ -968,3 +1463,684 @@ where
         format!("function {}'s {}", self.0, self.1.arg_name())
     }
 }
+
+/// Whether a value contains functions that are not just boxed values
+fn contains_function(value: &Value) -> bool {
+    match value {
+        Value::Func(arr) => (arr.data.iter()).any(|f| match f.as_boxed() {
+            Some(value) => contains_function(value),
+            None => true,
+        }),
+        _ => false,
+    }
+}
+
+/// The number of single-character edits needed to turn one string into another
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ac) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, &bc) in b.iter().enumerate() {
+            let substitution = prev[j] + (ac != bc) as usize;
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_rand() {
+        let run = |seed| {
+            let mut env = Uiua::with_native_sys().with_seed(seed);
+            env.load_str("[⍥⚂10]").unwrap();
+            env.take_stack()
+        };
+        assert_eq!(run(5), run(5));
+        assert_ne!(run(5), run(6));
+    }
+
+    #[test]
+    fn step_limit() {
+        let mut env = Uiua::with_native_sys().with_step_limit(1000);
+        let err = env.load_str("⍥(+1)∞ 0").unwrap_err();
+        assert!(err.is_execution_limit(), "{err}");
+
+        let mut env = Uiua::with_native_sys().with_step_limit(1000);
+        env.load_str("⍥(+1)100 0").unwrap();
+        assert_eq!(env.take_stack(), [Value::from(100.0)]);
+        // The budget is reset for each load
+        for _ in 0..20 {
+            env.load_str("⍥(+1)100 0").unwrap();
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        let start = instant::now();
+        let mut env = Uiua::with_native_sys().with_execution_limit(Duration::from_millis(100));
+        let err = env.load_str("⍥(+1)∞ 0").unwrap_err();
+        assert!(err.is_timeout(), "{err}");
+        assert!(instant::now() - start < 2000.0);
+
+        let mut env = Uiua::with_native_sys();
+        env.load_str("+1 2").unwrap();
+        assert_eq!(env.take_stack(), [Value::from(3.0)]);
+    }
+
+    #[test]
+    fn captured_output() {
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let mut env = Uiua::with_captured_output(stdout.clone());
+        env.load_str(r#"&p "Hello, World!" &pf 5"#).unwrap();
+        assert_eq!(*stdout.lock(), b"5Hello, World!\n");
+
+        let backend = CapturedSys::new(stdout.clone()).with_stdin(&b"first\nsecond"[..]);
+        let stderr = backend.stderr().clone();
+        let mut env = Uiua::with_backend(backend);
+        env.load_str(r#"&sc &sc &rs 2 0"#).unwrap();
+        let stack = env.take_stack();
+        assert_eq!(stack[0].as_string(&env, "").unwrap(), "fi");
+        assert_eq!(stack[1].as_string(&env, "").unwrap(), "rst");
+        assert_eq!(stack[2].as_string(&env, "").unwrap(), "second");
+        assert!(stderr.lock().is_empty());
+    }
+
+    #[test]
+    fn memory_backend() {
+        let files = [("data/input.txt".to_string(), b"Hello".to_vec())].into();
+        let mut env = Uiua::with_backend(crate::MemorySys::new(files));
+        env.load_str(
+            r#"
+            &p &fras "data/input.txt"
+            &fwa "data/output.txt" ⊂∶" World!" &fras "data/input.txt"
+            &p &fe "data/missing.txt"
+            "#,
+        )
+        .unwrap();
+        let backend = env.downcast_backend::<crate::MemorySys>().unwrap();
+        assert_eq!(backend.stdout(), b"Hello\n0\n");
+        assert_eq!(backend.file("data/output.txt").unwrap(), b"Hello World!");
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn image_round_trip() {
+        let gradient: Vec<f64> = (0..64)
+            .flat_map(|i| [(i % 8 * 30) as f64, (i / 8) as f64, 255.0])
+            .collect();
+        let image = Array::new(
+            tinyvec::tiny_vec![8, 8, 3],
+            gradient
+                .iter()
+                .map(|&n| n / 255.0)
+                .collect::<crate::cowslice::CowSlice<_>>(),
+        );
+        let mut env = Uiua::with_native_sys();
+        env.push(image);
+        env.load_str(r#"&imd &ime "png""#).unwrap();
+        let decoded = env.pop(1).unwrap();
+        assert_eq!(decoded.shape(), [8, 8, 3]);
+        let Value::Byte(decoded) = decoded else {
+            panic!("decoded image is not a byte array");
+        };
+        for (&actual, expected) in decoded.data.iter().zip(&gradient) {
+            assert!((actual as f64 - expected).abs() <= 1.0);
+        }
+        assert!(env.take_diagnostics().is_empty());
+
+        // Decoded images can be encoded again unchanged
+        env.push(decoded.clone());
+        env.load_str(r#"&imd &ime "png""#).unwrap();
+        assert_eq!(env.pop(1).unwrap(), Value::Byte(decoded));
+
+        env.load_str("&ime \"png\" [[2 ¯1]]").unwrap();
+        let diagnostics = env.take_diagnostics();
+        assert!(diagnostics.iter().any(|d| d.message.contains("clamped")));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn gif_encode() {
+        fn frame_count(bytes: &[u8]) -> usize {
+            let mut decoder = gif::DecodeOptions::new().read_info(bytes).unwrap();
+            let mut count = 0;
+            while decoder.read_next_frame().unwrap().is_some() {
+                count += 1;
+            }
+            count
+        }
+        let mut env = Uiua::with_native_sys();
+        env.load_str(
+            r#"
+            &gife 10 ÷4 ↯3_4_5_3 ⇡4
+            &gife [10 20] ÷2 ↯2_4_5 ⇡2
+            &gife 10 [[[0 1] [1 0]]]
+            "#,
+        )
+        .unwrap();
+        for frames in [1, 2, 3] {
+            let bytes = env.pop(1).unwrap().into_bytes(&env, "").unwrap();
+            assert!(bytes.starts_with(b"GIF89a"));
+            assert_eq!(frame_count(&bytes), frames);
+        }
+
+        let err = env.load_str(r#"&gife 10 {↯2_2 0 ↯3_3 0}"#).unwrap_err();
+        assert!(err.to_string().contains("same dimensions"), "{err}");
+        let err = env.load_str(r#"&gife [1 2 3] ↯2_2_2 0"#).unwrap_err();
+        assert!(err.to_string().contains("frame rates"), "{err}");
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn http_request() {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"ping") {
+                let n = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nX-Mock: yes\r\n\r\npong")
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        let code =
+            format!(r#"&http ↯3_2 {{"url" "http://{addr}/echo" "method" "post" "body" "ping"}}"#);
+        let mut env = Uiua::with_native_sys();
+        env.load_str(&code).unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /echo HTTP/1.1"), "{request}");
+
+        let response = crate::record_fields(&env.pop(1).unwrap(), &env).unwrap();
+        assert_eq!(response[0], ("status".into(), Value::from(200.0)));
+        let headers = crate::record_fields(&response[1].1, &env).unwrap();
+        assert!(headers.contains(&("x-mock".into(), Value::from("yes".to_string()))));
+        assert_eq!(
+            response[2],
+            ("body".into(), Value::from("pong".to_string()))
+        );
+
+        let mut env = Uiua::with_backend(crate::MemorySys::default());
+        let err = env.load_str(&code).unwrap_err();
+        assert!(err.to_string().contains("not supported"), "{err}");
+    }
+
+    #[test]
+    fn tcp_echo() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str(
+            r#"
+            L ← &tcpl "127.0.0.1:0"
+            C ← &tcpc &tcpaddr L
+            S ← &tcpa L
+            &tcps "hello" C
+            &tcps &tcpr 1024 S S
+            &tcpr 1024 C
+            &cl C
+            &tcpr 1024 S
+            &cl S
+            &cl L
+            "#,
+        )
+        .unwrap();
+        let closed = env.pop(1).unwrap();
+        let echoed = env.pop(1).unwrap();
+        assert_eq!(echoed.into_bytes(&env, "").unwrap(), b"hello");
+        assert_eq!(closed.shape(), [0]);
+
+        let err = env.load_str("&tcps \"again\" C").unwrap_err();
+        assert!(err.to_string().contains("already been closed"), "{err}");
+    }
+
+    #[test]
+    fn env_vars() {
+        let vars = [
+            ("SET".to_string(), "value".to_string()),
+            ("EMPTY".to_string(), String::new()),
+        ]
+        .into();
+        let mut env = Uiua::with_backend(crate::MemorySys::default().with_vars(vars));
+        env.load_str(r#"&var "SET" &var "EMPTY" &var "UNSET""#)
+            .unwrap();
+        let [set, empty, unset] = [(); 3].map(|_| env.pop(1).unwrap());
+        assert_eq!(set, Value::from("value".to_string()));
+        assert_eq!(empty, Value::from(String::new()));
+        assert_ne!(unset, empty);
+        assert!(unset.as_func_array().unwrap().as_boxed().is_some());
+        env.load_str("&vars").unwrap();
+        let mut vars = crate::record_fields(&env.pop(1).unwrap(), &env).unwrap();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            vars,
+            [
+                ("EMPTY".into(), String::new().into()),
+                ("SET".into(), "value".to_string().into())
+            ]
+        );
+
+        // The native backend reads the process environment
+        let mut env = Uiua::with_native_sys();
+        env.load_str(r#"&var "UIUA_TEST_UNSET_VARIABLE""#).unwrap();
+        assert_eq!(env.pop(1).unwrap(), unset);
+    }
+
+    #[test]
+    fn clocks() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("[⍥monotonic100]").unwrap();
+        let times = env.pop(1).unwrap();
+        let times = times.as_num_array().unwrap();
+        // Later readings come first in the array
+        assert!(times.data.windows(2).all(|w| w[0] - w[1] >= 0.0));
+
+        let mut env = Uiua::with_native_sys().with_fixed_clock(1_700_000_000.5);
+        env.load_str("now &sl 0.01 now").unwrap();
+        let time = Value::from(1_700_000_000.5);
+        assert_eq!(env.take_stack(), [time.clone(), time]);
+    }
+
+    #[test]
+    fn run_process() {
+        let mut env = Uiua::with_native_sys();
+        let err = env
+            .load_str(r#"&runp "uiua-nonexistent-program" []"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("Failed to run"), "{err}");
+
+        #[cfg(unix)]
+        {
+            env.load_str(r#"&runp "echo" {"hello" "world"}"#).unwrap();
+            let output = crate::record_fields(&env.pop(1).unwrap(), &env).unwrap();
+            assert_eq!(output[0], ("code".into(), Value::from(0)));
+            assert_eq!(
+                output[1],
+                ("stdout".into(), "hello world\n".to_string().into())
+            );
+
+            // Processes killed by a signal report 128 plus the signal number
+            env.load_str(r#"&runp "sh" {"-c" "kill -9 $$"}"#).unwrap();
+            let output = crate::record_fields(&env.pop(1).unwrap(), &env).unwrap();
+            assert_eq!(output[0], ("code".into(), Value::from(137)));
+        }
+
+        let mut env = Uiua::with_backend(crate::MemorySys::default());
+        let err = env.load_str(r#"&runp "echo" []"#).unwrap_err();
+        assert!(err.to_string().contains("not supported"), "{err}");
+    }
+
+    #[test]
+    fn memo() {
+        let run = |code: &str| {
+            let stdout = Arc::new(Mutex::new(Vec::new()));
+            let mut env = Uiua::with_captured_output(stdout.clone());
+            env.load_str(code).unwrap();
+            let calls = stdout.lock().iter().filter(|&&b| b == b'\n').count();
+            (env.pop(1).unwrap(), calls)
+        };
+        let (fib, calls) = run("Fib ← |1 memo(?∘(|1 +↬3-1∶↬3-2.) <2. &p\"call\")\nFib 20");
+        assert_eq!(fib, Value::from(6765));
+        assert_eq!(calls, 21);
+        let (fib, calls) = run("Fib ← |1 ?∘(|1 +↬2-1∶↬2-2.) <2. &p\"call\"\nFib 20");
+        assert_eq!(fib, Value::from(6765));
+        assert_eq!(calls, 21891);
+
+        // Function arguments bypass the cache
+        let (_, calls) = run("F ← memo(|2 !&p\"call\")\n[F(+1) 1 F(+1) 1]");
+        assert_eq!(calls, 2);
+
+        // Evicted results are recomputed
+        let stdout = Arc::new(Mutex::new(Vec::new()));
+        let mut env = Uiua::with_captured_output(stdout.clone()).with_memo_capacity(1);
+        env.load_str("F ← memo(&p\"call\".)\nF 1 F 2 F 2 F 1")
+            .unwrap();
+        assert_eq!(stdout.lock().iter().filter(|&&b| b == b'\n').count(), 3);
+    }
+
+    #[test]
+    fn tail_calls() {
+        let countdown = |n: usize, env: Uiua| {
+            let mut env = env;
+            env.load_str(&format!("!(|1 ↬>0.-1) {n}")).unwrap();
+            env.take_stack()
+        };
+        assert_eq!(
+            countdown(1_000_000, Uiua::with_native_sys()),
+            [Value::from(0)]
+        );
+        let env = Uiua::with_native_sys().with_tail_calls(false);
+        assert_eq!(countdown(100, env), [Value::from(0)]);
+
+        // Recursion that is not in tail position is unaffected
+        let mut env = Uiua::with_native_sys();
+        env.load_str("!(|1 ×↬>2.-1.) 5").unwrap();
+        assert_eq!(env.take_stack(), [Value::from(120)]);
+    }
+
+    #[test]
+    fn error_trace() {
+        let mut env = Uiua::with_native_sys();
+        let err =
+            (env.load_str("F ← |1 ⊡10\nG ← |1 ×2 F\nH ← |1 ∵(|1 -1 G)\nH [1 2]")).unwrap_err();
+        let ids: Vec<String> = (err.trace().iter())
+            .filter(|frame| frame.id != FunctionId::Main)
+            .map(|frame| frame.id.to_string())
+            .collect();
+        assert_eq!(ids, ["`F`", "`G`", "fn from 3:9", "∵", "`H`"]);
+        let shown = err.show(false);
+        let lines: Vec<&str> = shown
+            .lines()
+            .filter(|line| line.starts_with("  in "))
+            .collect();
+        assert_eq!(lines.len(), 5, "{shown}");
+        assert!(lines[0].contains("`F` "), "{shown}");
+        assert!(lines[4].contains("`H` "), "{shown}");
+    }
+
+    #[test]
+    fn compile_all() {
+        let mut env = Uiua::with_native_sys();
+        let (compiled, errors) = env.compile_all("1 )\nx ← 5\n+ x ]\nF ← |2 +1\n+1 F 2");
+        assert!(compiled.is_none());
+        let lines: Vec<usize> = (errors.iter())
+            .map(|error| match error {
+                UiuaError::Parse(errors) => errors[0].span.start.line,
+                UiuaError::Run(error) => match &error.span {
+                    Span::Code(span) => span.start.line,
+                    Span::Builtin => 0,
+                },
+                error => panic!("unexpected error: {error}"),
+            })
+            .collect();
+        assert_eq!(lines, [1, 3, 4]);
+
+        let mut env = Uiua::with_native_sys();
+        let (compiled, errors) = env.compile_all("x ← 5\n+1 x");
+        assert!(errors.is_empty());
+        assert!(env.take_stack().is_empty());
+        env.run_compiled(compiled.unwrap()).unwrap();
+        assert_eq!(env.take_stack(), [Value::from(6)]);
+    }
+
+    #[test]
+    fn diagnostic_severity() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("∶, 1 2").unwrap();
+        assert!(env
+            .take_diagnostics_at_least(DiagnosticKind::Warning)
+            .is_empty());
+        let diagnostics = env.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = diagnostics.into_iter().next().unwrap();
+        assert_eq!(diagnostic.kind, DiagnosticKind::Style);
+        assert!(diagnostic.show(false).starts_with("Style"));
+    }
+
+    #[test]
+    fn raw_strings() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("$$ a\\n_b\n$$ \"c\"").unwrap();
+        assert_eq!(env.take_stack(), [Value::from("a\\n_b\n\"c\"")]);
+
+        // Raw and format lines can be mixed
+        let mut env = Uiua::with_native_sys();
+        env.load_str("5\n$ x = _\n$$ _").unwrap();
+        assert_eq!(env.take_stack(), [Value::from("x = 5\n_")]);
+    }
+
+    #[test]
+    fn parallel_min_len() {
+        let code = "\
+            ⇡100000
+            ¯√÷3×2+1 .
+            -1. ×.
+            ⌵ ⌊÷7 ×
+            +⇌⇡100000";
+        let run = |min_len| {
+            let mut env = Uiua::with_native_sys().with_parallel_min_len(min_len);
+            env.load_str(code).unwrap();
+            env.take_stack()
+        };
+        assert_eq!(run(usize::MAX), run(1));
+        assert_eq!(run(usize::MAX), run(1000));
+    }
+
+    #[test]
+    fn reduce_min_max_empty() {
+        let run = |code: &str| {
+            let mut env = Uiua::with_native_sys();
+            env.load_str(code).unwrap();
+            env.take_stack().pop().unwrap()
+        };
+        assert_eq!(run("/↧[]"), Value::from(f64::INFINITY));
+        assert_eq!(run("/↥[]"), Value::from(f64::NEG_INFINITY));
+        assert_eq!(run("⬚5/↧[]"), Value::from(5.0));
+        assert_eq!(run("⬚5/↥ ↯0_3 0"), Value::from_iter([5.0; 3]));
+        // The fill does not affect non-empty arrays
+        assert_eq!(run("⬚5/↧[3 8 1]"), Value::from(1.0));
+        assert_eq!(run("⬚0/↥[3 8 1]"), Value::from(8.0));
+    }
+
+    #[test]
+    fn broadcasting() {
+        let run = |code: &str, broadcasting: bool| {
+            let mut env = Uiua::with_native_sys().with_broadcasting(broadcasting);
+            env.load_str(code).map(|_| env.take_stack().pop().unwrap())
+        };
+        let table = run("⊞+ [1 2 3] [10 20 30 40]", false).unwrap();
+        let code = "+ ↯3_1 [1 2 3] ↯1_4 [10 20 30 40]";
+        assert_eq!(run(code, true).unwrap().shape(), [3, 4]);
+        assert_eq!(run(code, true).unwrap(), table);
+        assert!(run(code, false).is_err());
+
+        // Bindings are compressed to bytes, which take the fill retry path
+        let code = "A ← ↯3_1 [1 2 3]\nB ← ↯1_4 [10 20 30 40]\n⬚0+ A B";
+        assert_eq!(run(code, true).unwrap(), table);
+
+        // Prefix pervasion is unchanged
+        let code = "+ [1 2 3] ↯3_2 [10 20 30 40 50 60]";
+        assert_eq!(run(code, true).unwrap(), run(code, false).unwrap());
+    }
+
+    #[test]
+    fn read_line_eof() {
+        let backend = CapturedSys::new(Arc::default()).with_stdin("a\n\nb".as_bytes());
+        let mut env = Uiua::with_backend(backend);
+        env.load_str("&rl &rl &rl &rl").unwrap();
+        let stack = env.take_stack();
+        assert_eq!(stack[0], Value::from("a"));
+        // An empty line is an empty string, which is distinct from EOF
+        assert_eq!(stack[1].shape(), [0]);
+        assert_eq!(stack[1].type_name(), "character");
+        assert_eq!(stack[2], Value::from("b"));
+        assert_eq!(
+            stack[3],
+            Value::from(Function::boxed(Array::<f64>::default()))
+        );
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("X ← 5\n1 2").unwrap();
+        let snapshot = env.snapshot();
+        env.load_str("X ← 10\nY ← 3\n3 4 ×2").unwrap();
+        assert!(env.load_str("+[1 2] [1 2 3]").is_err());
+        env.restore(snapshot.clone());
+        assert_eq!(env.snapshot().stack, snapshot.stack);
+        assert_eq!(env.take_stack(), [Value::from(2.0), Value::from(1.0)]);
+        env.load_str("X").unwrap();
+        assert_eq!(env.take_stack(), [Value::from(5u8)]);
+        assert!(env.load_str("Y").is_err());
+        env.restore(snapshot);
+        env.load_str("+").unwrap();
+        assert_eq!(env.take_stack(), [Value::from(3.0)]);
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("Add ← +").unwrap();
+        env.push(1);
+        env.push(2.5);
+        assert_eq!(env.stack(), [Value::from(1), Value::from(2.5)]);
+        env.load_str("Add").unwrap();
+        assert_eq!(env.pop("sum").unwrap(), Value::from(3.5));
+        assert!(env.stack().is_empty());
+        let err = env.pop("the result").unwrap_err().to_string();
+        assert!(err.contains("the result"), "{err}");
+    }
+
+    #[test]
+    fn call_binding() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("Double ← ×2\nAnswer ← 42").unwrap();
+        env.push(21);
+        env.call_binding("Double").unwrap();
+        assert_eq!(env.pop("result").unwrap(), Value::from(42));
+        env.call_binding("Answer").unwrap();
+        assert_eq!(env.take_stack(), [Value::from(42u8)]);
+        let err = env.call_binding("Doubel").unwrap_err().to_string();
+        assert!(err.contains("Did you mean `Double`?"), "{err}");
+        let err = env.call_binding("Nothing").unwrap_err().to_string();
+        assert!(!err.contains("Did you mean"), "{err}");
+    }
+
+    #[test]
+    fn run_isolated() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Uiua>();
+        assert_send::<Value>();
+        let handles: Vec<_> = (0..16)
+            .map(|i| std::thread::spawn(move || Uiua::run_isolated(&format!("/+⇡{i}"))))
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let stack = handle.join().unwrap().unwrap();
+            assert_eq!(stack, [Value::from((i * i.saturating_sub(1) / 2) as f64)]);
+        }
+        assert!(Uiua::run_isolated("&fras \"file.txt\"").is_err());
+    }
+
+    #[test]
+    fn max_call_depth() {
+        let mut env = Uiua::with_native_sys().with_max_call_depth(50);
+        let factorial = |n| format!("!(|1 ×↬>2.-1.) {n}");
+        for _ in 0..2 {
+            let err = env.load_str(&factorial(100)).unwrap_err();
+            assert!(err.is_call_depth_exceeded(), "{err}");
+            assert!(err.to_string().contains("50"), "{err}");
+        }
+        env.load_str(&factorial(10)).unwrap();
+        assert_eq!(env.pop("result").unwrap(), Value::from(3628800));
+
+        // Each level of recursion is one call, plus the call to the function itself
+        let mut env = Uiua::with_native_sys().with_max_call_depth(10);
+        env.load_str(&factorial(11)).unwrap();
+        assert!(env.load_str(&factorial(12)).is_err());
+
+        // Tail calls do not count toward the limit
+        let mut env = Uiua::with_native_sys().with_max_call_depth(10);
+        env.load_str("!(|1 ↬>0.-1) 1000").unwrap();
+    }
+
+    #[test]
+    fn profiling() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("⍥(+1)100 0").unwrap();
+        assert!(env.profile_report().is_empty());
+
+        let mut env = Uiua::with_native_sys().with_profiling(true);
+        env.load_str("⍥(+1)100 0").unwrap();
+        let report = env.profile_report();
+        let count = |prim| {
+            (report.iter())
+                .find(|(p, ..)| *p == prim)
+                .map(|(_, count, _)| *count)
+        };
+        assert_eq!(count(Primitive::Add), Some(100));
+        assert_eq!(count(Primitive::Repeat), Some(1));
+        assert_eq!(count(Primitive::Sub), None);
+        // Repeat's time includes the time of the additions
+        let time = |prim| report.iter().find(|(p, ..)| *p == prim).unwrap().2;
+        assert!(time(Primitive::Repeat) >= time(Primitive::Add));
+    }
+
+    #[test]
+    fn infinity_literals() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("∞ ¯∞ NaN").unwrap();
+        assert_eq!(env.pop(1).unwrap(), Value::from(f64::INFINITY));
+        assert_eq!(env.pop(2).unwrap(), Value::from(f64::NEG_INFINITY));
+        let Value::Num(nan) = env.pop(3).unwrap() else {
+            panic!("expected a number")
+        };
+        assert!(nan.data[0].is_nan());
+    }
+
+    #[test]
+    fn invalid_regex() {
+        let mut env = Uiua::with_native_sys();
+        let err = env.load_str("regexreplace \"(\" \"\" \"abc\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid pattern"), "{err}");
+        assert!(err.to_string().contains("unclosed group"), "{err}");
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut env = Uiua::with_native_sys();
+        let err = env.load_str("⍘utf [104 105 255 104]").unwrap_err();
+        assert!(err.to_string().contains("at byte 2"), "{err}");
+        env.load_str("⍣(⍘utf [255]) (0;)").unwrap();
+        assert_eq!(env.take_stack(), [Value::from(0)]);
+    }
+
+    #[test]
+    fn seeded_shuffle() {
+        let run = |seed| {
+            let mut env = Uiua::with_native_sys().with_seed(seed);
+            env.load_str("shuffle ⇡20").unwrap();
+            env.pop(1).unwrap()
+        };
+        let shuffled = run(5);
+        assert_eq!(shuffled, run(5));
+        assert_ne!(shuffled, run(6));
+        let mut env = Uiua::with_native_sys();
+        env.load_str("⇡20").unwrap();
+        let range = env.pop(1).unwrap();
+        let mut rows: Vec<Value> = shuffled.into_rows().collect();
+        rows.sort();
+        assert_eq!(rows, range.into_rows().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn seeded_sample() {
+        let run = |seed| {
+            let mut env = Uiua::with_native_sys().with_seed(seed);
+            env.load_str("⍥(sample 3 ⇡5)10").unwrap();
+            env.take_stack()
+        };
+        let samples = run(5);
+        assert_eq!(samples, run(5));
+        for sample in samples {
+            let mut rows: Vec<Value> = sample.into_rows().collect();
+            assert_eq!(rows.len(), 3);
+            rows.sort();
+            rows.dedup();
+            assert_eq!(rows.len(), 3);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! A compressed representation for numeric arrays that are mostly zeros
+
+use std::mem::size_of;
+
+use ecow::EcoVec;
+
+use crate::{
+    array::{Array, FormatShape, Shape},
+    value::Value,
+    Uiua, UiuaResult,
+};
+
+/// A numeric array that only stores its nonzero elements
+///
+/// Elements are stored in compressed sparse row form. Each row of the array
+/// is treated as a flat list, and only the nonzero elements of each row are stored,
+/// along with their positions in the row.
+///
+/// Use [`Value::to_sparse`] to create one and [`SparseArray::to_dense`] to convert back.
+/// This is not a [`Value`] variant, so other operations do not accept it directly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseArray {
+    shape: Shape,
+    /// The start of each row's elements in `indices` and `values`,
+    /// followed by the total number of stored elements
+    row_starts: Vec<usize>,
+    /// The flat position within its row of each stored element
+    indices: Vec<usize>,
+    /// The stored elements
+    values: Vec<f64>,
+}
+
+impl SparseArray {
+    /// Compress a dense array
+    ///
+    /// Returns `None` for scalars
+    pub fn from_dense(array: &Array<f64>) -> Option<Self> {
+        Self::from_elems(array.shape.clone(), array.data.iter().copied())
+    }
+    fn from_elems(shape: Shape, elems: impl IntoIterator<Item = f64>) -> Option<Self> {
+        if shape.is_empty() {
+            return None;
+        }
+        let row_len: usize = shape[1..].iter().product();
+        let mut row_starts = Vec::with_capacity(shape[0] + 1);
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+        for (i, elem) in elems.into_iter().enumerate() {
+            if row_len > 0 && i % row_len == 0 {
+                row_starts.push(values.len());
+            }
+            if elem != 0.0 {
+                indices.push(i % row_len);
+                values.push(elem);
+            }
+        }
+        row_starts.resize(shape[0] + 1, values.len());
+        Some(SparseArray {
+            shape,
+            row_starts,
+            indices,
+            values,
+        })
+    }
+    /// Expand into a dense array
+    pub fn to_dense(&self) -> Value {
+        let row_len = self.row_len();
+        let mut data = vec![0.0; self.shape.iter().product()];
+        for row in 0..self.row_count() {
+            let start = row * row_len;
+            for (i, n) in self.row_entries(row) {
+                data[start + i] = n;
+            }
+        }
+        Array::new(self.shape.clone(), EcoVec::from(data)).into()
+    }
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+    pub fn rank(&self) -> usize {
+        self.shape.len()
+    }
+    pub fn row_count(&self) -> usize {
+        self.shape[0]
+    }
+    /// The number of elements in each row
+    pub fn row_len(&self) -> usize {
+        self.shape[1..].iter().product()
+    }
+    /// The number of stored nonzero elements
+    pub fn nonzero_count(&self) -> usize {
+        self.values.len()
+    }
+    /// The approximate number of bytes used by the array's elements
+    pub fn memory_size(&self) -> usize {
+        self.shape.len() * size_of::<usize>()
+            + self.row_starts.len() * size_of::<usize>()
+            + self.indices.len() * size_of::<usize>()
+            + self.values.len() * size_of::<f64>()
+    }
+    /// The positions within the row and values of a row's stored elements
+    fn row_entries(&self, row: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.row_starts[row]..self.row_starts[row + 1];
+        (self.indices[range.clone()].iter().copied()).zip(self.values[range].iter().copied())
+    }
+    /// Get the element at the given index, or `None` if it is out of bounds
+    pub fn get(&self, index: &[usize]) -> Option<f64> {
+        if index.len() != self.rank() || index.iter().zip(&self.shape).any(|(i, s)| i >= s) {
+            return None;
+        }
+        let mut flat = 0;
+        for (i, s) in index[1..].iter().zip(&self.shape[1..]) {
+            flat = flat * s + i;
+        }
+        let range = self.row_starts[index[0]]..self.row_starts[index[0] + 1];
+        Some(match self.indices[range.clone()].binary_search(&flat) {
+            Ok(i) => self.values[range.start + i],
+            Err(_) => 0.0,
+        })
+    }
+    /// Get a row of the array as a dense value
+    ///
+    /// Only the row is expanded. Use [`SparseArray::sparse_row`] to keep it compressed.
+    pub fn row(&self, row: usize, env: &Uiua) -> UiuaResult<Value> {
+        if row >= self.row_count() {
+            return Err(env.error(format!(
+                "Index {row} is out of bounds of length {}",
+                self.row_count()
+            )));
+        }
+        let mut data = vec![0.0; self.row_len()];
+        for (i, n) in self.row_entries(row) {
+            data[i] = n;
+        }
+        Ok(Array::new(&self.shape[1..], EcoVec::from(data)).into())
+    }
+    /// Get a row of an array with rank greater than 1 without expanding it
+    pub fn sparse_row(&self, row: usize) -> Option<SparseArray> {
+        if self.rank() < 2 || row >= self.row_count() {
+            return None;
+        }
+        let row_shape: Shape = self.shape[1..].into();
+        let sub_len: usize = row_shape[1..].iter().product();
+        let mut row_starts = vec![0; row_shape[0] + 1];
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+        for (i, n) in self.row_entries(row) {
+            row_starts[i / sub_len + 1] += 1;
+            indices.push(i % sub_len);
+            values.push(n);
+        }
+        for i in 1..row_starts.len() {
+            row_starts[i] += row_starts[i - 1];
+        }
+        Some(SparseArray {
+            shape: row_shape,
+            row_starts,
+            indices,
+            values,
+        })
+    }
+    /// Apply a function to every element
+    ///
+    /// If the function maps zero to zero, the result stays sparse.
+    /// Otherwise, the result is dense.
+    pub fn map(&self, f: impl Fn(f64) -> f64) -> Result<SparseArray, Value> {
+        if f(0.0) == 0.0 {
+            let mut mapped = self.clone();
+            for n in &mut mapped.values {
+                *n = f(*n);
+            }
+            Ok(mapped)
+        } else {
+            let Value::Num(mut dense) = self.to_dense() else {
+                unreachable!()
+            };
+            for n in dense.data.as_mut_slice() {
+                *n = f(*n);
+            }
+            Err(dense.into())
+        }
+    }
+    /// Multiply this rank-2 array by a dense rank-1 or rank-2 array
+    ///
+    /// Only the stored elements are visited, so the cost is proportional
+    /// to the number of nonzero elements rather than the size of the array.
+    pub fn matmul(&self, other: &Value, env: &Uiua) -> UiuaResult<Value> {
+        let (rows, inner) = match *self.shape() {
+            [rows, inner] => (rows, inner),
+            _ => {
+                return Err(env.error(format!(
+                    "Cannot multiply sparse array of shape {} as a matrix",
+                    FormatShape(self.shape())
+                )))
+            }
+        };
+        let other_nums = num_elems(other).ok_or_else(|| {
+            env.error(format!(
+                "Cannot multiply sparse array by {} array",
+                other.type_name()
+            ))
+        })?;
+        let other_shape = other.shape();
+        if other_shape.is_empty() || other_shape.len() > 2 || other_shape[0] != inner {
+            return Err(env.error(format!(
+                "Cannot multiply sparse array of shape {} by array of shape {}",
+                FormatShape(self.shape()),
+                FormatShape(other_shape)
+            )));
+        }
+        let cols = other_shape.get(1).copied().unwrap_or(1);
+        let mut data = vec![0.0; rows * cols];
+        for row in 0..rows {
+            let out = &mut data[row * cols..][..cols];
+            for (k, n) in self.row_entries(row) {
+                let other_row = &other_nums[k * cols..][..cols];
+                for (o, m) in out.iter_mut().zip(other_row) {
+                    *o += n * m;
+                }
+            }
+        }
+        let mut shape = Shape::from([rows].as_slice());
+        shape.extend(other_shape.get(1).copied());
+        Ok(Array::new(shape, EcoVec::from(data)).into())
+    }
+}
+
+impl Value {
+    /// Compress a numeric array that is mostly zeros
+    ///
+    /// Returns `None` for scalars and non-numeric arrays
+    pub fn to_sparse(&self) -> Option<SparseArray> {
+        match self {
+            Value::Num(arr) => SparseArray::from_dense(arr),
+            Value::Byte(arr) => {
+                SparseArray::from_elems(arr.shape.clone(), arr.data.iter().map(|&b| b as f64))
+            }
+            Value::Int(arr) => {
+                SparseArray::from_elems(arr.shape.clone(), arr.data.iter().map(|&i| i as f64))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Get the elements of a numeric array as numbers
+fn num_elems(value: &Value) -> Option<Vec<f64>> {
+    Some(match value {
+        Value::Num(arr) => arr.data.to_vec(),
+        Value::Byte(arr) => arr.data.iter().map(|&b| b as f64).collect(),
+        Value::Int(arr) => arr.data.iter().map(|&i| i as f64).collect(),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(n: usize) -> Value {
+        let mut data = vec![0.0; n * n];
+        for i in 0..n {
+            data[i * n + i] = 1.0;
+        }
+        Array::new([n, n].as_slice(), EcoVec::from(data)).into()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("[[0 0 3] [0 0 0] [1 0 2]]").unwrap();
+        let value = env.pop(1).unwrap();
+        let sparse = value.to_sparse().unwrap();
+        assert_eq!(sparse.nonzero_count(), 3);
+        assert_eq!(sparse.to_dense(), value);
+        assert_eq!(sparse.get(&[0, 2]), Some(3.0));
+        assert_eq!(sparse.get(&[1, 1]), Some(0.0));
+        assert_eq!(sparse.get(&[3, 0]), None);
+        assert_eq!(sparse.row(2, &env).unwrap(), value.row(2));
+        assert!(sparse.row(3, &env).is_err());
+        assert!(Value::from(5.0).to_sparse().is_none());
+
+        env.load_str("↯2_3_2 [0 1 0 0 0 2 0 0 0 0 3 0]").unwrap();
+        let value = env.pop(1).unwrap();
+        let sparse = value.to_sparse().unwrap();
+        let row = sparse.sparse_row(1).unwrap();
+        assert_eq!(row.shape(), [3, 2]);
+        assert_eq!(row.to_dense(), value.row(1));
+        assert_eq!(row.get(&[2, 0]), Some(3.0));
+
+        let doubled = sparse.map(|n| n * 2.0).unwrap();
+        assert_eq!(doubled.nonzero_count(), 3);
+        let shifted = sparse.map(|n| n + 1.0).unwrap_err();
+        assert_eq!(shifted.shape(), [2, 3, 2]);
+    }
+
+    #[test]
+    fn identity_matmul() {
+        let env = Uiua::with_native_sys();
+        let n = 1000;
+        let dense = identity(n);
+        let sparse = dense.to_sparse().unwrap();
+        let dense_size = n * n * size_of::<f64>();
+        assert!(sparse.memory_size() * 100 < dense_size);
+
+        let other: Value = Array::new(
+            [n, 2].as_slice(),
+            (0..n * 2).map(|i| i as f64).collect::<EcoVec<_>>(),
+        )
+        .into();
+        assert_eq!(sparse.matmul(&other, &env).unwrap(), other);
+        let vector: Value = Array::new([n].as_slice(), EcoVec::from(vec![2.0; n])).into();
+        assert_eq!(sparse.matmul(&vector, &env).unwrap(), vector);
+        assert!(sparse.matmul(&Value::from("abc"), &env).is_err());
+        assert!(sparse.matmul(&identity(3), &env).is_err());
+    }
+}
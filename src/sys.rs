@@ -1,7 +1,8 @@
+#[cfg(feature = "image")]
+use std::collections::{HashMap, HashSet};
 use std::{
     any::Any,
-    collections::{HashMap, HashSet},
-    io::{stderr, stdin, Cursor, Read, Write},
+    io::{stderr, Cursor, Write},
     sync::{Arc, OnceLock},
     time::Duration,
 };
@@ -9,20 +10,19 @@ use std::{
 use ecow::EcoVec;
 use enum_iterator::Sequence;
 use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+#[cfg(feature = "image")]
 use image::{DynamicImage, ImageOutputFormat};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
+#[cfg(feature = "image")]
 use tinyvec::tiny_vec;
 
 use crate::{
-    array::Array,
-    cowslice::{cowslice, CowSlice},
-    function::Function,
-    grid_fmt::GridFmt,
-    primitive::PrimDoc,
-    value::Value,
-    Uiua, UiuaError, UiuaResult,
+    array::Array, cowslice::cowslice, function::Function, grid_fmt::GridFmt, primitive::PrimDoc,
+    value::Value, Uiua, UiuaError, UiuaResult,
 };
+#[cfg(feature = "image")]
+use crate::{cowslice::CowSlice, DiagnosticKind};
 
 pub fn example_ua<T>(f: impl FnOnce(&mut String) -> T) -> T {
     static EXAMPLE_UA: Lazy<Mutex<String>> = Lazy::new(|| {
@@ -106,6 +106,14 @@ sys_op! {
     /// If EOF is reached, the number `0` is returned instead.
     /// Programs that wish to properly handle EOF should check for this.
     (0, ScanLine, "&sc", "scan line"),
+    /// Read a line from stdin, without the trailing newline
+    ///
+    /// The normal output is a string.
+    /// If EOF is reached, a [box]ed empty list is returned instead.
+    /// This distinguishes EOF from an empty line.
+    ///
+    /// See also: [&sc]
+    (0, ReadLine, "&rl", "read line"),
     /// Get the size of the terminal
     ///
     /// The result is a 2-element array of the height and width of the terminal.
@@ -116,7 +124,18 @@ sys_op! {
     /// The first element will always be the name of your script
     (0, Args, "&args", "arguments"),
     /// Get the value of an environment variable
+    ///
+    /// If the variable is not set, a [box]ed empty list is returned.
+    /// This distinguishes an unset variable from one that is set to an empty string.
+    ///
+    /// ex: &var "UIUA_UNSET_VARIABLE"
     (1, Var, "&var", "environment variable"),
+    /// Get all environment variables
+    ///
+    /// The result is a rank `2` array of [box]ed name-value pairs.
+    ///
+    /// See also: [&var]
+    (0, Vars, "&vars", "environment variables"),
     /// Run a command and wait for it to finish
     ///
     /// Standard IO will be inherited. Returns the exit code of the command.
@@ -129,6 +148,16 @@ sys_op! {
     ///
     /// Expects either a string, a rank `2` character array, or a rank `1` array of [box] strings.
     (1(3), RunCapture, "&runc", "run command capture"),
+    /// Run a program and wait for it to finish
+    ///
+    /// The first argument is the name of the program, and the second is a list of [box]ed string arguments.
+    /// Standard IO will be captured.
+    /// The result is a record with the keys `code`, `stdout`, and `stderr`, like the objects returned by [&jsond].
+    /// `stdout` and `stderr` are strings if they are valid UTF-8 and byte arrays otherwise.
+    ///
+    /// If the program cannot be run, an error is thrown.
+    /// It can be caught with [try].
+    (2, RunProcess, "&runp", "run process"),
     /// Change the current directory
     (1(0), ChangeDirectory, "&cd", "change directory"),
     /// Sleep for n seconds
@@ -187,8 +216,58 @@ sys_op! {
     ///
     /// Expects a path and a [rank]`1` array or either numbers or characters.
     (2(0), FWriteAll, "&fwa", "file - write all"),
+    /// Decode a JSON string into an array
+    ///
+    /// Numbers become numbers and strings become character arrays.
+    /// `true` and `false` become `1` and `0`, and `null` becomes `NaN`.
+    /// Arrays whose items all have the same type and shape become normal arrays.
+    /// All other arrays become arrays of [box]es.
+    /// Objects become rank `2` arrays of [box]ed key-value pairs.
+    ///
+    /// ex: &jsond "[1, 2, 3]"
+    /// ex: &jsond "[1, \"two\", [3]]"
+    /// ex: &jsond "{\"name\": \"Uiua\", \"year\": 2023}"
+    ///
+    /// See also: [&jsone]
+    (1, JsonDecode, "&jsond", "json - decode"),
+    /// Encode an array as a JSON string
+    ///
+    /// This is the inverse of [&jsond].
+    /// Rank `2` arrays of [box]es with `2` columns whose first column is all strings are encoded as objects.
+    /// `NaN` is encoded as `null`.
+    ///
+    /// ex: &jsone [1 2 3]
+    /// ex: &jsone {"a" 1 [2 3]}
+    ///
+    /// See also: [&jsond]
+    (1, JsonEncode, "&jsone", "json - encode"),
+    /// Decode a CSV string into a rank `2` array of [box]ed strings
+    ///
+    /// Quoted fields may contain commas, newlines, and quotes escaped as `""`.
+    /// Empty lines are skipped.
+    /// Every record must have the same number of fields.
+    ///
+    /// ex: &csvd "name,age\nAlice,30\nBob,25\n"
+    /// ex: &csvd "a,\"b, c\"\n1,\"\"\"2\"\"\""
+    ///
+    /// See also: [&csve]
+    (1, CsvDecode, "&csvd", "csv - decode"),
+    /// Encode a rank `1` or `2` array as a CSV string
+    ///
+    /// Each cell must be a string or a scalar number, possibly [box]ed.
+    /// Fields that contain commas, quotes, or newlines are quoted.
+    ///
+    /// ex: &csve [1_2 3_4]
+    /// ex: &csve {"a" "b, c" "d"}
+    ///
+    /// See also: [&csvd]
+    (1, CsvEncode, "&csve", "csv - encode"),
     /// Decode an image from a byte array
     ///
+    /// The result is a rank 3 byte array of values between 0 and 255.
+    /// Its shape is `[height width channels]`.
+    /// The number of channels matches the image: 1 for grayscale, 2 for grayscale with alpha, 3 for RGB, and 4 for RGB with alpha.
+    ///
     /// Supported formats are `jpg`, `png`, `bmp`, `gif`, and `ico`.
     ///
     /// See also: [&ime]
@@ -207,6 +286,9 @@ sys_op! {
     /// A length 3 last axis is an RGB image.
     /// A length 4 last axis is an RGB image with an alpha channel.
     ///
+    /// Number values should be between 0 and 1. Values outside this range are clamped, and a warning is emitted.
+    /// Byte values are used as is, like those returned by [&imd], unless they are all 0 or 1, in which case 1 is white.
+    ///
     /// Supported formats are `jpg`, `png`, `bmp`, `gif`, and `ico`.
     ///
     /// See also: [&ims] [&imd]
@@ -232,20 +314,24 @@ sys_op! {
     (1(0), ImShow, "&ims", "image - show"),
     /// Encode a gif into a byte array
     ///
-    /// The first argument is a framerate in seconds.
+    /// The first argument is a framerate in frames per second.
+    /// It can be a single number or a list with one framerate for each frame.
     /// The second argument is the gif data and must be a rank 3 or 4 numeric array.
     /// The rows of the array are the frames of the gif, and their format must conform to that of [&ime].
+    /// The frames may also be a list of [box]ed arrays, but they must all have the same dimensions.
     ///
     /// See also: [&gifs]
-    (1, GifEncode, "&gife", "gif - encode"),
+    (2, GifEncode, "&gife", "gif - encode"),
     /// Show a gif
     ///
-    /// The first argument is a framerate in seconds.
+    /// The first argument is a framerate in frames per second.
+    /// It can be a single number or a list with one framerate for each frame.
     /// The second argument is the gif data and must be a rank 3 or 4 numeric array.
     /// The rows of the array are the frames of the gif, and their format must conform to that of [&ime].
+    /// The frames may also be a list of [box]ed arrays, but they must all have the same dimensions.
     ///
     /// See also: [&gife]
-    (1(0), GifShow, "&gifs", "gif - show"),
+    (2(0), GifShow, "&gifs", "gif - show"),
     /// Decode audio from a byte array
     ///
     /// Only the `wav` format is supported.
@@ -306,7 +392,20 @@ sys_op! {
     /// Set the write timeout of a TCP socket in seconds
     (2(0), TcpSetWriteTimeout, "&tcpswt", "tcp - set write timeout"),
     /// Get the connection address of a TCP socket
+    ///
+    /// For a TCP listener, this is the local address it is bound to.
     (1, TcpAddr, "&tcpaddr", "tcp - address"),
+    /// Send a string or byte array over a TCP socket
+    ///
+    /// The first argument is the data, and the second is the socket handle.
+    /// The data is flushed immediately.
+    (2(0), TcpSend, "&tcps", "tcp - send"),
+    /// Receive at most n bytes from a TCP socket
+    ///
+    /// The first argument is the maximum number of bytes, and the second is the socket handle.
+    /// Unlike [&rb], this returns as soon as any bytes are available.
+    /// If the connection has been closed, an empty byte array is returned.
+    (2, TcpRecv, "&tcpr", "tcp - receive"),
     /// Make an HTTP request
     ///
     /// Takes in an 1.x HTTP request and returns an HTTP response.
@@ -328,6 +427,44 @@ sys_op! {
     /// - The HTTP version
     /// - The `Host` header (if not defined)
     (2, HttpsWrite, "&httpsw", "http - Make an HTTP request"),
+    /// Make an HTTP request
+    ///
+    /// Expects a request record and returns a response record.
+    /// Records are rank `2` arrays of [box]ed key-value pairs, like the objects returned by [&jsond].
+    ///
+    /// The request may have the following keys:
+    /// - `url` - The URL to request. This is required.
+    /// - `method` - The HTTP method. Defaults to `GET`.
+    /// - `headers` - A record of header names and values
+    /// - `body` - The request body as a string or byte array
+    /// - `timeout` - The timeout in seconds. Defaults to `30`.
+    ///
+    /// The response has the keys `status`, `headers`, and `body`.
+    /// The body is a string if it is valid UTF-8 and a byte array otherwise.
+    ///
+    /// ex! &http ↯1_2 {"url" "http://localhost:1/"}
+    (1, HttpRequest, "&http", "http - request"),
+}
+
+/// The default timeout for [`WebRequest`]s
+pub const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An HTTP request made with [`SysBackend::http_request`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub timeout: Duration,
+}
+
+/// A response to a [`WebRequest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
 }
 
 /// A handle to an IO stream
@@ -380,9 +517,29 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn scan_line_stdin(&self) -> Result<Option<String>, String> {
         Err("Reading from stdin is not supported in this environment".into())
     }
+    /// Read up to `count` bytes from stdin
+    ///
+    /// Should return fewer bytes if EOF is reached.
+    fn read_stdin(&self, count: usize) -> Result<Vec<u8>, String> {
+        Err("Reading from stdin is not supported in this environment".into())
+    }
     fn var(&self, name: &str) -> Option<String> {
         None
     }
+    /// Get the current time in seconds since the Unix epoch
+    fn now(&self) -> f64 {
+        instant::now() / 1000.0
+    }
+    /// Get the value of a monotonic clock in seconds
+    ///
+    /// Only differences between values are meaningful.
+    fn monotonic(&self) -> f64 {
+        instant::now() / 1000.0
+    }
+    /// Get all environment variables as name-value pairs
+    fn vars(&self) -> Result<Vec<(String, String)>, String> {
+        Ok(Vec::new())
+    }
     fn term_size(&self) -> Result<(usize, usize), String> {
         Err("Getting the terminal size is not supported in this environment".into())
     }
@@ -436,6 +593,7 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn sleep(&self, seconds: f64) -> Result<(), String> {
         Err("Sleeping is not supported in this environment".into())
     }
+    #[cfg(feature = "image")]
     fn show_image(&self, image: DynamicImage) -> Result<(), String> {
         Err("Showing images not supported in this environment".into())
     }
@@ -466,6 +624,17 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn tcp_set_non_blocking(&self, handle: Handle, non_blocking: bool) -> Result<(), String> {
         Err("TCP sockets are not supported in this environment".into())
     }
+    /// Write all the bytes to a TCP socket and flush it
+    fn tcp_send(&self, handle: Handle, bytes: &[u8]) -> Result<(), String> {
+        Err("TCP sockets are not supported in this environment".into())
+    }
+    /// Read at most `max` bytes from a TCP socket
+    ///
+    /// Should return as soon as any bytes are available,
+    /// and should return an empty buffer if the connection is closed.
+    fn tcp_recv(&self, handle: Handle, max: usize) -> Result<Vec<u8>, String> {
+        Err("TCP sockets are not supported in this environment".into())
+    }
     fn tcp_set_read_timeout(
         &self,
         handle: Handle,
@@ -508,12 +677,22 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     ) -> Result<(i32, String, String), String> {
         Err("Running commands is not supported in this environment".into())
     }
+    /// Run a program and capture its exit code, stdout, and stderr
+    fn run_process(&self, program: &str, args: &[&str]) -> Result<(i32, Vec<u8>, Vec<u8>), String> {
+        Err("Running processes is not supported in this environment".into())
+    }
     fn change_directory(&self, path: &str) -> Result<(), String> {
         Err("Changing directories is not supported in this environment".into())
     }
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         Err("Making HTTPS requests is not supported in this environment".into())
     }
+    /// Make an HTTP request
+    ///
+    /// Backends that should not have network access can leave this unimplemented.
+    fn http_request(&self, request: WebRequest) -> Result<WebResponse, String> {
+        Err("Making HTTP requests is not supported in this environment".into())
+    }
 }
 
 impl SysOp {
@@ -548,6 +727,10 @@ impl SysOp {
                     env.push(0u8);
                 }
             }
+            SysOp::ReadLine => match env.backend.scan_line_stdin().map_err(|e| env.error(e))? {
+                Some(line) => env.push(line),
+                None => env.push(Function::boxed(Array::<f64>::default())),
+            },
             SysOp::TermSize => {
                 let (width, height) = env.backend.term_size().map_err(|e| env.error(e))?;
                 env.push(cowslice![height as f64, width as f64])
@@ -562,8 +745,14 @@ impl SysOp {
                 let key = env
                     .pop(1)?
                     .as_string(env, "Augument to var must be a string")?;
-                let var = env.backend.var(&key).unwrap_or_default();
-                env.push(var);
+                match env.backend.var(&key) {
+                    Some(var) => env.push(var),
+                    None => env.push(Function::boxed(Array::<f64>::default())),
+                }
+            }
+            SysOp::Vars => {
+                let vars = env.backend.vars().map_err(|e| env.error(e))?;
+                env.push(record(vars));
             }
             SysOp::FOpen => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
@@ -584,12 +773,7 @@ impl SysOp {
                 let bytes = match handle {
                     Handle::STDOUT => return Err(env.error("Cannot read from stdout")),
                     Handle::STDERR => return Err(env.error("Cannot read from stderr")),
-                    Handle::STDIN => stdin()
-                        .lock()
-                        .bytes()
-                        .take(count)
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|e| env.error(e))?,
+                    Handle::STDIN => env.backend.read_stdin(count).map_err(|e| env.error(e))?,
                     _ => env.backend.read(handle, count).map_err(|e| env.error(e))?,
                 };
                 let s = String::from_utf8(bytes).map_err(|e| env.error(e))?;
@@ -604,12 +788,7 @@ impl SysOp {
                 let bytes = match handle {
                     Handle::STDOUT => return Err(env.error("Cannot read from stdout")),
                     Handle::STDERR => return Err(env.error("Cannot read from stderr")),
-                    Handle::STDIN => stdin()
-                        .lock()
-                        .bytes()
-                        .take(count)
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|e| env.error(e))?,
+                    Handle::STDIN => env.backend.read_stdin(count).map_err(|e| env.error(e))?,
                     _ => env.backend.read(handle, count).map_err(|e| env.error(e))?,
                 };
                 env.push(Array::from(bytes.as_slice()));
@@ -638,10 +817,12 @@ impl SysOp {
                             _ => return Err(env.error("Delimiter must be a string or byte array")),
                         };
                         let mut buffer = Vec::new();
-                        let stdin = stdin().lock();
-                        for byte in stdin.bytes() {
-                            let byte = byte.map_err(|e| env.error(e))?;
-                            buffer.push(byte);
+                        loop {
+                            let byte = env.backend.read_stdin(1).map_err(|e| env.error(e))?;
+                            if byte.is_empty() {
+                                break;
+                            }
+                            buffer.extend(byte);
                             if buffer.ends_with(&delim_bytes) {
                                 break;
                             }
@@ -691,8 +872,12 @@ impl SysOp {
                     .into();
                 let bytes: Vec<u8> = match data {
                     Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
+                    Value::Int(arr) => arr.data.iter().map(|&x| x as u8).collect(),
                     Value::Byte(arr) => arr.data.into(),
                     Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    Value::Complex(_) => {
+                        return Err(env.error("Cannot write complex array to file"))
+                    }
                     Value::Func(_) => return Err(env.error("Cannot write function array to file")),
                 };
                 match handle {
@@ -748,8 +933,12 @@ impl SysOp {
                 let data = env.pop(2)?;
                 let bytes: Vec<u8> = match data {
                     Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
+                    Value::Int(arr) => arr.data.iter().map(|&x| x as u8).collect(),
                     Value::Byte(arr) => arr.data.into(),
                     Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    Value::Complex(_) => {
+                        return Err(env.error("Cannot write complex array to file"))
+                    }
                     Value::Func(_) => return Err(env.error("Cannot write function array to file")),
                 };
                 env.backend
@@ -801,6 +990,25 @@ impl SysOp {
                 let path = env.pop(1)?.as_string(env, "Invoke path must be a string")?;
                 env.backend.invoke(&path).map_err(|e| env.error(e))?;
             }
+            SysOp::JsonDecode => {
+                let json = env.pop(1)?.as_string(env, "JSON must be a string")?;
+                let value = Value::from_json_string(&json, env)?;
+                env.push(value);
+            }
+            SysOp::JsonEncode => {
+                let json = env.pop(1)?.to_json_string(env)?;
+                env.push(json);
+            }
+            SysOp::CsvDecode => {
+                let csv = env.pop(1)?.as_string(env, "CSV must be a string")?;
+                let value = Value::from_csv_string(&csv, env)?;
+                env.push(value);
+            }
+            SysOp::CsvEncode => {
+                let csv = env.pop(1)?.to_csv_string(env)?;
+                env.push(csv);
+            }
+            #[cfg(feature = "image")]
             SysOp::ImDecode => {
                 let bytes = match env.pop(1)? {
                     Value::Byte(arr) => {
@@ -824,19 +1032,21 @@ impl SysOp {
                     _ => return Err(env.error("Image bytes must be a numeric array")),
                 };
                 let image = image::load_from_memory(&bytes)
-                    .map_err(|e| env.error(format!("Failed to read image: {}", e)))?
-                    .into_rgba8();
-                let shape = tiny_vec![image.height() as usize, image.width() as usize, 4];
-                let array = Array::<f64>::new(
-                    shape,
-                    image
-                        .into_raw()
-                        .into_iter()
-                        .map(|b| b as f64 / 255.0)
-                        .collect::<CowSlice<_>>(),
+                    .map_err(|e| env.error(format!("Failed to read image: {}", e)))?;
+                let (width, height) = (image.width() as usize, image.height() as usize);
+                let (channels, data) = match image.color().channel_count() {
+                    1 => (1, image.into_luma8().into_raw()),
+                    2 => (2, image.into_luma_alpha8().into_raw()),
+                    3 => (3, image.into_rgb8().into_raw()),
+                    _ => (4, image.into_rgba8().into_raw()),
+                };
+                let array = Array::<u8>::new(
+                    tiny_vec![height, width, channels],
+                    data.into_iter().collect::<CowSlice<_>>(),
                 );
                 env.push(array);
             }
+            #[cfg(feature = "image")]
             SysOp::ImEncode => {
                 let format = env
                     .pop(1)?
@@ -850,27 +1060,52 @@ impl SysOp {
                     "ico" => ImageOutputFormat::Ico,
                     format => return Err(env.error(format!("Invalid image format: {}", format))),
                 };
+                warn_image_clamping(&value, env);
                 let bytes =
                     value_to_image_bytes(&value, output_format).map_err(|e| env.error(e))?;
                 env.push(Array::<u8>::from(bytes.as_slice()));
             }
+            #[cfg(feature = "image")]
             SysOp::ImShow => {
                 let value = env.pop(1)?;
+                warn_image_clamping(&value, env);
                 let image = value_to_image(&value).map_err(|e| env.error(e))?;
                 env.backend.show_image(image).map_err(|e| env.error(e))?;
             }
+            #[cfg(feature = "image")]
             SysOp::GifEncode => {
-                let delay = env.pop(1)?.as_num(env, "Delay must be a number")?;
+                let frame_rates = env.pop(1)?.as_number_list(
+                    env,
+                    "Frame rate must be a positive number or list of positive numbers",
+                    |n| n > 0.0,
+                    |n| n,
+                )?;
                 let value = env.pop(2)?;
-                let bytes = value_to_gif_bytes(&value, delay).map_err(|e| env.error(e))?;
+                warn_image_clamping(&value, env);
+                let bytes = value_to_gif_bytes(&value, &frame_rates).map_err(|e| env.error(e))?;
                 env.push(Array::<u8>::from(bytes.as_slice()));
             }
+            #[cfg(feature = "image")]
             SysOp::GifShow => {
-                let delay = env.pop(1)?.as_num(env, "Delay must be a number")?;
+                let frame_rates = env.pop(1)?.as_number_list(
+                    env,
+                    "Frame rate must be a positive number or list of positive numbers",
+                    |n| n > 0.0,
+                    |n| n,
+                )?;
                 let value = env.pop(2)?;
-                let bytes = value_to_gif_bytes(&value, delay).map_err(|e| env.error(e))?;
+                warn_image_clamping(&value, env);
+                let bytes = value_to_gif_bytes(&value, &frame_rates).map_err(|e| env.error(e))?;
                 env.backend.show_gif(bytes).map_err(|e| env.error(e))?;
             }
+            #[cfg(not(feature = "image"))]
+            SysOp::ImDecode
+            | SysOp::ImEncode
+            | SysOp::ImShow
+            | SysOp::GifEncode
+            | SysOp::GifShow => {
+                return Err(env.error("Image support is not enabled in this build of Uiua"))
+            }
             SysOp::AudioDecode => {
                 let bytes = match env.pop(1)? {
                     Value::Byte(arr) => {
@@ -981,6 +1216,32 @@ impl SysOp {
                 let addr = env.backend.tcp_addr(handle).map_err(|e| env.error(e))?;
                 env.push(addr);
             }
+            SysOp::TcpSend => {
+                let data = env.pop(1)?;
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let bytes = match data {
+                    Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    data => data.into_bytes(env, "Data must be a string or byte array")?,
+                };
+                env.backend
+                    .tcp_send(handle, &bytes)
+                    .map_err(|e| env.error(e))?;
+            }
+            SysOp::TcpRecv => {
+                let max = env.pop(1)?.as_nat(env, "Count must be an integer")?;
+                let handle = env
+                    .pop(2)?
+                    .as_nat(env, "Handle must be an natural number")?
+                    .into();
+                let bytes = env
+                    .backend
+                    .tcp_recv(handle, max)
+                    .map_err(|e| env.error(e))?;
+                env.push(Array::<u8>::from(bytes.as_slice()));
+            }
             SysOp::TcpSetNonBlocking => {
                 let handle = env
                     .pop(1)?
@@ -1034,6 +1295,19 @@ impl SysOp {
                     .map_err(|e| env.error(e))?;
                 env.push(res);
             }
+            SysOp::HttpRequest => {
+                let request = env.pop(1)?;
+                let request = value_to_http_request(&request, env)?;
+                let response = env
+                    .backend
+                    .http_request(request)
+                    .map_err(|e| env.error(e))?;
+                env.push(record([
+                    ("status", f64::from(response.status).into()),
+                    ("headers", record(response.headers)),
+                    ("body", bytes_to_value(response.body)),
+                ]));
+            }
             SysOp::Close => {
                 let handle = env
                     .pop(1)?
@@ -1061,6 +1335,25 @@ impl SysOp {
                 env.push(stdout);
                 env.push(code);
             }
+            SysOp::RunProcess => {
+                let command = env.pop(1)?.as_string(env, "Program must be a string")?;
+                let args = env.pop(2)?;
+                let args = if args.row_count() == 0 {
+                    Vec::new()
+                } else {
+                    value_to_strings(&args, env)?
+                };
+                let args: Vec<_> = args.iter().map(|s| s.as_str()).collect();
+                let (code, stdout, stderr) = env
+                    .backend
+                    .run_process(&command, &args)
+                    .map_err(|e| env.error(e))?;
+                env.push(record([
+                    ("code", Value::from(code)),
+                    ("stdout", bytes_to_value(stdout)),
+                    ("stderr", bytes_to_value(stderr)),
+                ]));
+            }
             SysOp::ChangeDirectory => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
                 env.backend
@@ -1072,7 +1365,92 @@ impl SysOp {
     }
 }
 
+/// Build a record from key-value pairs
+///
+/// A record is a rank `2` array of boxed key-value pairs.
+fn record<K: Into<String>, V: Into<Value>>(pairs: impl IntoIterator<Item = (K, V)>) -> Value {
+    let mut data = EcoVec::new();
+    for (key, value) in pairs {
+        data.push(Arc::new(Function::boxed(key.into())));
+        data.push(Arc::new(Function::boxed(value.into())));
+    }
+    Array::new(tinyvec::tiny_vec![data.len() / 2, 2], data).into()
+}
+
+/// Get the key-value pairs of a record
+pub(crate) fn record_fields(value: &Value, env: &Uiua) -> UiuaResult<Vec<(String, Value)>> {
+    let arr = match value {
+        Value::Func(arr) if arr.shape() == [arr.row_count(), 2] => arr,
+        _ => {
+            return Err(env.error(format!(
+                "Record must be a rank 2 array of boxed key-value pairs, \
+                but it is a {} array of shape {}",
+                value.type_name(),
+                value.format_shape()
+            )))
+        }
+    };
+    let mut fields = Vec::with_capacity(arr.row_count());
+    for pair in arr.data.chunks_exact(2) {
+        let (Some(key), Some(value)) = (pair[0].as_boxed(), pair[1].as_boxed()) else {
+            return Err(env.error("Record keys and values must be boxed arrays"));
+        };
+        let key = key.as_string(env, "Record keys must be strings")?;
+        fields.push((key, value.clone()));
+    }
+    Ok(fields)
+}
+
+fn value_to_http_request(value: &Value, env: &Uiua) -> UiuaResult<WebRequest> {
+    let mut request = WebRequest {
+        method: "GET".into(),
+        url: String::new(),
+        headers: Vec::new(),
+        body: Vec::new(),
+        timeout: DEFAULT_HTTP_TIMEOUT,
+    };
+    for (key, value) in record_fields(value, env)? {
+        match key.as_str() {
+            "url" => request.url = value.as_string(env, "HTTP url must be a string")?,
+            "method" => request.method = value.as_string(env, "HTTP method must be a string")?,
+            "headers" => {
+                for (name, value) in record_fields(&value, env)? {
+                    let value = value.as_string(env, "HTTP header values must be strings")?;
+                    request.headers.push((name, value));
+                }
+            }
+            "body" => {
+                request.body = match value {
+                    Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    value => value.into_bytes(env, "HTTP body must be a string or byte array")?,
+                }
+            }
+            "timeout" => {
+                let seconds = value.as_num(env, "HTTP timeout must be a number")?;
+                if !(seconds > 0.0 && seconds.is_finite()) {
+                    return Err(env.error("HTTP timeout must be a positive finite number"));
+                }
+                request.timeout = Duration::from_secs_f64(seconds);
+            }
+            key => return Err(env.error(format!("Unknown HTTP request field {key:?}"))),
+        }
+    }
+    if request.url.is_empty() {
+        return Err(env.error("HTTP request must have a url"));
+    }
+    Ok(request)
+}
+
 fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String>)> {
+    let mut strings = value_to_strings(value, env)?;
+    if strings.is_empty() {
+        return Err(env.error("Command array not be empty"));
+    }
+    let command = strings.remove(0);
+    Ok((command, strings))
+}
+
+fn value_to_strings(value: &Value, env: &Uiua) -> UiuaResult<Vec<String>> {
     let mut strings = Vec::new();
     match value {
         Value::Char(arr) => match arr.rank() {
@@ -1119,24 +1497,42 @@ fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String
                 )))
             }
         },
-        Value::Num(_) | Value::Byte(_) => {
+        Value::Num(_) | Value::Int(_) | Value::Byte(_) | Value::Complex(_) => {
             return Err(env.error(format!(
                 "Command must be a string or function array, but it is {}s",
                 value.type_name()
             )))
         }
     }
-    if strings.is_empty() {
-        return Err(env.error("Command array not be empty"));
+    Ok(strings)
+}
+
+/// Convert bytes to a string if they are valid UTF-8, or a byte array otherwise
+fn bytes_to_value(bytes: Vec<u8>) -> Value {
+    match String::from_utf8(bytes) {
+        Ok(s) => s.into(),
+        Err(e) => Array::<u8>::from(e.as_bytes()).into(),
     }
-    let command = strings.remove(0);
-    Ok((command, strings))
 }
 
+#[cfg(feature = "image")]
+fn warn_image_clamping(value: &Value, env: &Uiua) {
+    if let Value::Num(arr) = value {
+        if arr.data.iter().any(|n| !(0.0..=1.0).contains(n)) {
+            env.runtime_diagnostic(
+                "Image values outside the range 0 to 1 were clamped",
+                DiagnosticKind::Warning,
+            );
+        }
+    }
+}
+
+#[cfg(feature = "image")]
 pub fn value_to_image_bytes(value: &Value, format: ImageOutputFormat) -> Result<Vec<u8>, String> {
     image_to_bytes(&value_to_image(value)?, format)
 }
 
+#[cfg(feature = "image")]
 pub fn image_to_bytes(image: &DynamicImage, format: ImageOutputFormat) -> Result<Vec<u8>, String> {
     let mut bytes = Cursor::new(Vec::new());
     image
@@ -1145,6 +1541,7 @@ pub fn image_to_bytes(image: &DynamicImage, format: ImageOutputFormat) -> Result
     Ok(bytes.into_inner())
 }
 
+#[cfg(feature = "image")]
 pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
     if ![2, 3].contains(&value.rank()) {
         return Err("Image must be a rank 2 or 3 numeric array".into());
@@ -1155,7 +1552,11 @@ pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
             .iter()
             .map(|f| (*f * 255.0).floor() as u8)
             .collect(),
-        Value::Byte(bytes) => bytes.data.iter().map(|&b| (b > 0) as u8 * 255).collect(),
+        // Boolean arrays are black and white
+        Value::Byte(bytes) if bytes.data.iter().all(|&b| b <= 1) => {
+            bytes.data.iter().map(|&b| b * 255).collect()
+        }
+        Value::Byte(bytes) => bytes.data.to_vec(),
         _ => return Err("Image must be a numeric array".into()),
     };
     #[allow(clippy::match_ref_pats)]
@@ -1351,18 +1752,48 @@ fn array_from_wav_bytes_impl<T: hound::Sample>(
     }
 }
 
-pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, String> {
+/// Encode the rows of a value as the frames of a GIF
+///
+/// If `frame_rates` has a single element, it is used for every frame.
+/// Otherwise, it must have one element per frame.
+///
+/// The frames may also be a list of boxed arrays, in which case every frame
+/// must have the same dimensions.
+#[cfg(feature = "image")]
+pub fn value_to_gif_bytes(value: &Value, frame_rates: &[f64]) -> Result<Vec<u8>, String> {
     if value.row_count() == 0 {
         return Err("Cannot convert empty array into GIF".into());
     }
+    if frame_rates.len() != 1 && frame_rates.len() != value.row_count() {
+        return Err(format!(
+            "There must be either 1 frame rate or 1 per frame, \
+            but there are {} frame rates and {} frames",
+            frame_rates.len(),
+            value.row_count()
+        ));
+    }
     let mut frames = Vec::with_capacity(value.row_count());
-    let mut width = 0;
-    let mut height = 0;
     for row in value.rows() {
-        let image = value_to_image(&row)?.into_rgb8();
-        width = image.width();
-        height = image.height();
-        frames.push(image);
+        let row = match row {
+            Value::Func(arr) => arr
+                .into_unboxed()
+                .map_err(|_| "GIF frames must be arrays, not functions")?,
+            row => row,
+        };
+        frames.push(value_to_image(&row)?.into_rgb8());
+    }
+    let (width, height) = frames[0].dimensions();
+    if let Some((i, frame)) =
+        (frames.iter().enumerate()).find(|(_, f)| f.dimensions() != (width, height))
+    {
+        return Err(format!(
+            "All GIF frames must have the same dimensions, but frame 0 is {}x{} and frame {} is {}x{}",
+            width,
+            height,
+            i,
+            frame.width(),
+            frame.height()
+        ));
     }
     if width > u16::MAX as u32 || height > u16::MAX as u32 {
         return Err(format!(
@@ -1403,13 +1834,13 @@ pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, Str
     let mut encoder = gif::Encoder::new(&mut bytes, width as u16, height as u16, &palette)
         .map_err(|e| e.to_string())?;
     const MIN_FRAME_RATE: f64 = 1.0 / 60.0;
-    let delay = ((1.0 / frame_rate.max(MIN_FRAME_RATE)).abs() * 100.0) as u16;
     encoder
         .set_repeat(gif::Repeat::Infinite)
         .map_err(|e| e.to_string())?;
-    for image in frames {
+    for (i, image) in frames.into_iter().enumerate() {
+        let frame_rate = frame_rates[i.min(frame_rates.len() - 1)];
         let mut frame = gif::Frame::from_rgb(width as u16, height as u16, image.as_raw());
-        frame.delay = delay;
+        frame.delay = ((1.0 / frame_rate.max(MIN_FRAME_RATE)).abs() * 100.0) as u16;
         encoder.write_frame(&frame).map_err(|e| e.to_string())?;
     }
     drop(encoder);
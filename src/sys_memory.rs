@@ -0,0 +1,178 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::atomic::{self, AtomicU64},
+};
+
+use parking_lot::Mutex;
+
+use crate::{Handle, SysBackend};
+
+/// A [`SysBackend`] with an in-memory filesystem
+///
+/// Files are stored in a map from paths to their contents.
+/// Directories are implied by the `/`-separated paths of the files.
+/// Stdout and stderr are captured into buffers.
+/// Environment variables come from a fixed map rather than the real environment.
+///
+/// This is useful for deterministic tests and for environments without a filesystem.
+pub struct MemorySys {
+    files: Mutex<HashMap<String, Vec<u8>>>,
+    open_files: Mutex<HashMap<Handle, OpenFile>>,
+    next_handle: AtomicU64,
+    vars: HashMap<String, String>,
+    stdout: Mutex<Vec<u8>>,
+    stderr: Mutex<Vec<u8>>,
+}
+
+struct OpenFile {
+    path: String,
+    position: usize,
+}
+
+impl Default for MemorySys {
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+impl MemorySys {
+    /// Create a new backend with the given files
+    pub fn new(files: HashMap<String, Vec<u8>>) -> Self {
+        Self {
+            files: Mutex::new(files),
+            open_files: Mutex::new(HashMap::new()),
+            next_handle: Handle::FIRST_UNRESERVED.0.into(),
+            vars: HashMap::new(),
+            stdout: Mutex::new(Vec::new()),
+            stderr: Mutex::new(Vec::new()),
+        }
+    }
+    /// Set the environment variables
+    pub fn with_vars(mut self, vars: HashMap<String, String>) -> Self {
+        self.vars = vars;
+        self
+    }
+    /// Get a copy of the contents of a file
+    pub fn file(&self, path: &str) -> Option<Vec<u8>> {
+        self.files.lock().get(path).cloned()
+    }
+    /// Get a copy of all files
+    pub fn files(&self) -> HashMap<String, Vec<u8>> {
+        self.files.lock().clone()
+    }
+    /// Get everything written to stdout so far
+    pub fn stdout(&self) -> Vec<u8> {
+        self.stdout.lock().clone()
+    }
+    /// Get everything written to stderr so far
+    pub fn stderr(&self) -> Vec<u8> {
+        self.stderr.lock().clone()
+    }
+    fn open(&self, path: &str) -> Handle {
+        let handle = Handle(self.next_handle.fetch_add(1, atomic::Ordering::Relaxed));
+        let file = OpenFile {
+            path: path.into(),
+            position: 0,
+        };
+        self.open_files.lock().insert(handle, file);
+        handle
+    }
+}
+
+impl SysBackend for MemorySys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn print_str_stdout(&self, s: &str) -> Result<(), String> {
+        self.stdout.lock().extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+    fn print_str_stderr(&self, s: &str) -> Result<(), String> {
+        self.stderr.lock().extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+    fn print_str_trace(&self, s: &str) {
+        self.stderr.lock().extend_from_slice(s.as_bytes());
+    }
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+    fn vars(&self) -> Result<Vec<(String, String)>, String> {
+        let mut vars: Vec<_> = self.vars.clone().into_iter().collect();
+        vars.sort();
+        Ok(vars)
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let files = self.files.lock();
+        files.contains_key(path) || files.keys().any(|p| p.starts_with(&prefix))
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        if self.files.lock().contains_key(path) {
+            Ok(true)
+        } else if self.file_exists(path) {
+            Ok(false)
+        } else {
+            Err(format!("{path} does not exist"))
+        }
+    }
+    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let mut paths: Vec<String> = (self.files.lock().keys())
+            .filter_map(|p| p.strip_prefix(&prefix))
+            .map(|rest| format!("{prefix}{}", rest.split('/').next().unwrap_or_default()))
+            .collect();
+        if paths.is_empty() {
+            return Err(format!("{path} is not a directory"));
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+    fn open_file(&self, path: &str) -> Result<Handle, String> {
+        if !self.files.lock().contains_key(path) {
+            return Err(format!("{path} does not exist"));
+        }
+        Ok(self.open(path))
+    }
+    fn create_file(&self, path: &str) -> Result<Handle, String> {
+        self.files.lock().insert(path.into(), Vec::new());
+        Ok(self.open(path))
+    }
+    fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
+        let mut open_files = self.open_files.lock();
+        let file = open_files.get_mut(&handle).ok_or("Invalid file handle")?;
+        let files = self.files.lock();
+        let contents = files.get(&file.path).ok_or("File was deleted")?;
+        let start = file.position.min(contents.len());
+        let end = start.saturating_add(count).min(contents.len());
+        file.position = end;
+        Ok(contents[start..end].to_vec())
+    }
+    fn write(&self, handle: Handle, contents: &[u8]) -> Result<(), String> {
+        let mut open_files = self.open_files.lock();
+        let file = open_files.get_mut(&handle).ok_or("Invalid file handle")?;
+        let mut files = self.files.lock();
+        let data = files.entry(file.path.clone()).or_default();
+        let end = file.position + contents.len();
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[file.position..end].copy_from_slice(contents);
+        file.position = end;
+        Ok(())
+    }
+    fn file_read_all(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.file(path)
+            .ok_or_else(|| format!("{path} does not exist"))
+    }
+    fn file_write_all(&self, path: &str, contents: &[u8]) -> Result<(), String> {
+        self.files.lock().insert(path.into(), contents.to_vec());
+        Ok(())
+    }
+    fn close(&self, handle: Handle) -> Result<(), String> {
+        self.open_files.lock().remove(&handle);
+        Ok(())
+    }
+}
@@ -2,18 +2,24 @@ use std::{
     any::Any,
     env,
     fs::{self, File},
-    io::{stderr, stdin, stdout, BufRead, Read, Write},
+    io::{self, stderr, stdin, stdout, BufRead, BufReader, Read, Write},
     net::*,
-    process::Command,
-    sync::atomic::{self, AtomicU64},
+    process::{Command, ExitStatus},
+    sync::{
+        atomic::{self, AtomicU64},
+        Arc,
+    },
     thread::{sleep, spawn, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use crate::{value::Value, Handle, SysBackend, Uiua, UiuaError, UiuaResult};
+use crate::{
+    value::Value, Handle, SysBackend, Uiua, UiuaError, UiuaResult, WebRequest, WebResponse,
+};
 use bufreaderwriter::seq::BufReaderWriterSeq;
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 
 #[derive(Default)]
 pub struct NativeSys;
@@ -71,6 +77,17 @@ impl GlobalNativeSys {
         }
         panic!("Ran out of file handles");
     }
+    fn tcp_socket(
+        &self,
+        handle: Handle,
+    ) -> Result<dashmap::mapref::one::RefMut<'_, Handle, Buffered<TcpStream>>, String> {
+        self.tcp_sockets.get_mut(&handle).ok_or_else(|| {
+            format!(
+                "Handle {} is not an open tcp socket. It may have already been closed.",
+                handle.0
+            )
+        })
+    }
     fn get_stream(&self, handle: Handle) -> Result<SysStream, String> {
         Ok(if let Some(file) = self.files.get_mut(&handle) {
             SysStream::File(file)
@@ -79,7 +96,10 @@ impl GlobalNativeSys {
         } else if let Some(socket) = self.tcp_sockets.get_mut(&handle) {
             SysStream::TcpSocket(socket)
         } else {
-            return Err("Invalid file handle".to_string());
+            return Err(format!(
+                "Handle {} is not open. It may have already been closed.",
+                handle.0
+            ));
         })
     }
 }
@@ -121,6 +141,15 @@ impl SysBackend for NativeSys {
             .transpose()
             .map_err(|e| e.to_string())
     }
+    fn read_stdin(&self, count: usize) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::new();
+        stdin()
+            .lock()
+            .take(count as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| e.to_string())?;
+        Ok(buf)
+    }
     fn save_error_color(&self, error: &UiuaError) {
         NATIVE_SYS
             .colored_errors
@@ -133,6 +162,20 @@ impl SysBackend for NativeSys {
     fn var(&self, name: &str) -> Option<String> {
         env::var(name).ok()
     }
+    fn vars(&self) -> Result<Vec<(String, String)>, String> {
+        let mut vars: Vec<_> = env::vars().collect();
+        vars.sort();
+        Ok(vars)
+    }
+    fn now(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64())
+    }
+    fn monotonic(&self) -> f64 {
+        static START: Lazy<Instant> = Lazy::new(Instant::now);
+        START.elapsed().as_secs_f64()
+    }
     fn file_exists(&self, path: &str) -> bool {
         fs::metadata(path).is_ok()
     }
@@ -345,6 +388,12 @@ impl SysBackend for NativeSys {
         Ok(handle)
     }
     fn tcp_addr(&self, handle: Handle) -> Result<String, String> {
+        if let Some(listener) = NATIVE_SYS.tcp_listeners.get(&handle) {
+            return Ok(listener
+                .local_addr()
+                .map_err(|e| e.to_string())?
+                .to_string());
+        }
         let socket = NATIVE_SYS
             .tcp_sockets
             .get(&handle)
@@ -366,6 +415,21 @@ impl SysBackend for NativeSys {
             .map_err(|e| e.to_string())?;
         Ok(())
     }
+    fn tcp_send(&self, handle: Handle, bytes: &[u8]) -> Result<(), String> {
+        let mut socket = NATIVE_SYS.tcp_socket(handle)?;
+        socket.write_all(bytes).map_err(|e| e.to_string())?;
+        socket.flush().map_err(|e| e.to_string())
+    }
+    fn tcp_recv(&self, handle: Handle, max: usize) -> Result<Vec<u8>, String> {
+        let mut socket = NATIVE_SYS.tcp_socket(handle)?;
+        let mut buf = vec![0; max.min(1 << 16)];
+        match socket.read(&mut buf) {
+            Ok(n) => buf.truncate(n),
+            Err(e) if e.kind() == io::ErrorKind::ConnectionReset => buf.clear(),
+            Err(e) => return Err(e.to_string()),
+        }
+        Ok(buf)
+    }
     fn tcp_set_read_timeout(
         &self,
         handle: Handle,
@@ -397,14 +461,17 @@ impl SysBackend for NativeSys {
         Ok(())
     }
     fn close(&self, handle: Handle) -> Result<(), String> {
+        NATIVE_SYS.hostnames.remove(&handle);
         if NATIVE_SYS.files.remove(&handle).is_some()
             || NATIVE_SYS.tcp_listeners.remove(&handle).is_some()
-            || (NATIVE_SYS.tcp_sockets.remove(&handle).is_some()
-                && NATIVE_SYS.hostnames.remove(&handle).is_some())
+            || NATIVE_SYS.tcp_sockets.remove(&handle).is_some()
         {
             Ok(())
         } else {
-            Err("Invalid stream handle".to_string())
+            Err(format!(
+                "Handle {} is not open. It may have already been closed.",
+                handle.0
+            ))
         }
     }
     #[cfg(feature = "invoke")]
@@ -442,7 +509,7 @@ impl SysBackend for NativeSys {
             .map_err(|e| e.to_string())?
             .wait()
             .map_err(|e| e.to_string())?;
-        Ok(status.code().unwrap_or(0))
+        Ok(exit_code(status))
     }
     fn run_command_capture(
         &self,
@@ -454,14 +521,48 @@ impl SysBackend for NativeSys {
             .output()
             .map_err(|e| e.to_string())?;
         Ok((
-            output.status.code().unwrap_or(0),
+            exit_code(output.status),
             String::from_utf8_lossy(&output.stdout).into(),
             String::from_utf8_lossy(&output.stderr).into(),
         ))
     }
+    fn run_process(&self, program: &str, args: &[&str]) -> Result<(i32, Vec<u8>, Vec<u8>), String> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run {program}: {e}"))?;
+        Ok((exit_code(output.status), output.stdout, output.stderr))
+    }
     fn change_directory(&self, path: &str) -> Result<(), String> {
         env::set_current_dir(path).map_err(|e| e.to_string())
     }
+    #[cfg(feature = "http")]
+    fn http_request(&self, request: WebRequest) -> Result<WebResponse, String> {
+        let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())
+            .map_err(|_| format!("Invalid HTTP method {:?}", request.method))?;
+        let client = reqwest::blocking::Client::builder()
+            .timeout(request.timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let mut builder = client.request(method, &request.url).body(request.body);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+        let response = builder.send().map_err(|e| e.to_string())?;
+        let status = response.status().as_u16();
+        let headers = (response.headers().iter())
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.to_string(), value)
+            })
+            .collect();
+        let body = response.bytes().map_err(|e| e.to_string())?.to_vec();
+        Ok(WebResponse {
+            status,
+            headers,
+            body,
+        })
+    }
     #[cfg(feature = "https")]
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         let host = NATIVE_SYS
@@ -511,6 +612,18 @@ impl SysBackend for NativeSys {
     }
 }
 
+/// Get the exit code of a finished process
+///
+/// Processes killed by a signal have no exit code,
+/// so they are given 128 plus the signal number, as in most shells.
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal;
+    }
+    status.code().unwrap_or(1)
+}
+
 /// Takes an HTTP request, validates it, and fixes it (if possible) by adding
 /// the HTTP version and trailing newlines if they aren't present.
 ///
@@ -598,3 +711,194 @@ fn check_http(mut request: String, hostname: &str) -> Result<String, String> {
 
     Ok(request)
 }
+
+/// A [`SysBackend`] that behaves like [`NativeSys`], except that stdout and
+/// stderr are captured into buffers and stdin is read from a supplied source
+///
+/// Image and audio output are not supported.
+pub struct CapturedSys {
+    stdout: Arc<Mutex<Vec<u8>>>,
+    stderr: Arc<Mutex<Vec<u8>>>,
+    stdin: Mutex<Box<dyn BufRead + Send>>,
+}
+
+impl CapturedSys {
+    /// Create a new backend that writes stdout to the given buffer
+    ///
+    /// Stderr is captured into its own buffer, and stdin is empty.
+    pub fn new(stdout: Arc<Mutex<Vec<u8>>>) -> Self {
+        Self {
+            stdout,
+            stderr: Arc::new(Mutex::new(Vec::new())),
+            stdin: Mutex::new(Box::new(io::empty())),
+        }
+    }
+    /// Write stderr to the given buffer
+    pub fn with_stderr(mut self, stderr: Arc<Mutex<Vec<u8>>>) -> Self {
+        self.stderr = stderr;
+        self
+    }
+    /// Read stdin from the given source
+    pub fn with_stdin(mut self, stdin: impl Read + Send + 'static) -> Self {
+        self.stdin = Mutex::new(Box::new(BufReader::new(stdin)));
+        self
+    }
+    /// Get the buffer that receives stdout
+    pub fn stdout(&self) -> &Arc<Mutex<Vec<u8>>> {
+        &self.stdout
+    }
+    /// Get the buffer that receives stderr
+    pub fn stderr(&self) -> &Arc<Mutex<Vec<u8>>> {
+        &self.stderr
+    }
+}
+
+impl SysBackend for CapturedSys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn print_str_stdout(&self, s: &str) -> Result<(), String> {
+        self.stdout.lock().extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+    fn print_str_stderr(&self, s: &str) -> Result<(), String> {
+        self.stderr.lock().extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+    fn print_str_trace(&self, s: &str) {
+        self.stderr.lock().extend_from_slice(s.as_bytes());
+    }
+    fn scan_line_stdin(&self) -> Result<Option<String>, String> {
+        let mut line = String::new();
+        let read = (self.stdin.lock())
+            .read_line(&mut line)
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+    fn read_stdin(&self, count: usize) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::new();
+        (self.stdin.lock().as_mut())
+            .take(count as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| e.to_string())?;
+        Ok(buf)
+    }
+    fn var(&self, name: &str) -> Option<String> {
+        NativeSys.var(name)
+    }
+    fn vars(&self) -> Result<Vec<(String, String)>, String> {
+        NativeSys.vars()
+    }
+    fn now(&self) -> f64 {
+        NativeSys.now()
+    }
+    fn monotonic(&self) -> f64 {
+        NativeSys.monotonic()
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        NativeSys.file_exists(path)
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        NativeSys.is_file(path)
+    }
+    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        NativeSys.list_dir(path)
+    }
+    fn open_file(&self, path: &str) -> Result<Handle, String> {
+        NativeSys.open_file(path)
+    }
+    fn create_file(&self, path: &str) -> Result<Handle, String> {
+        NativeSys.create_file(path)
+    }
+    fn read(&self, handle: Handle, len: usize) -> Result<Vec<u8>, String> {
+        NativeSys.read(handle, len)
+    }
+    fn write(&self, handle: Handle, conts: &[u8]) -> Result<(), String> {
+        NativeSys.write(handle, conts)
+    }
+    fn sleep(&self, seconds: f64) -> Result<(), String> {
+        NativeSys.sleep(seconds)
+    }
+    fn tcp_listen(&self, addr: &str) -> Result<Handle, String> {
+        NativeSys.tcp_listen(addr)
+    }
+    fn tcp_accept(&self, handle: Handle) -> Result<Handle, String> {
+        NativeSys.tcp_accept(handle)
+    }
+    fn tcp_connect(&self, addr: &str) -> Result<Handle, String> {
+        NativeSys.tcp_connect(addr)
+    }
+    fn tcp_addr(&self, handle: Handle) -> Result<String, String> {
+        NativeSys.tcp_addr(handle)
+    }
+    fn tcp_set_non_blocking(&self, handle: Handle, non_blocking: bool) -> Result<(), String> {
+        NativeSys.tcp_set_non_blocking(handle, non_blocking)
+    }
+    fn tcp_send(&self, handle: Handle, bytes: &[u8]) -> Result<(), String> {
+        NativeSys.tcp_send(handle, bytes)
+    }
+    fn tcp_recv(&self, handle: Handle, max: usize) -> Result<Vec<u8>, String> {
+        NativeSys.tcp_recv(handle, max)
+    }
+    fn tcp_set_read_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        NativeSys.tcp_set_read_timeout(handle, timeout)
+    }
+    fn tcp_set_write_timeout(
+        &self,
+        handle: Handle,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        NativeSys.tcp_set_write_timeout(handle, timeout)
+    }
+    fn close(&self, handle: Handle) -> Result<(), String> {
+        NativeSys.close(handle)
+    }
+    fn spawn(
+        &self,
+        env: Uiua,
+        f: Box<dyn FnOnce(&mut Uiua) -> UiuaResult + Send>,
+    ) -> Result<Handle, String> {
+        NativeSys.spawn(env, f)
+    }
+    fn wait(&self, handle: Handle) -> Result<Vec<Value>, Result<UiuaError, String>> {
+        NativeSys.wait(handle)
+    }
+    fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<i32, String> {
+        let (status, stdout, stderr) = self.run_command_capture(command, args)?;
+        self.print_str_stdout(&stdout)?;
+        self.print_str_stderr(&stderr)?;
+        Ok(status)
+    }
+    fn run_command_capture(
+        &self,
+        command: &str,
+        args: &[&str],
+    ) -> Result<(i32, String, String), String> {
+        NativeSys.run_command_capture(command, args)
+    }
+    fn run_process(&self, program: &str, args: &[&str]) -> Result<(i32, Vec<u8>, Vec<u8>), String> {
+        NativeSys.run_process(program, args)
+    }
+    fn change_directory(&self, path: &str) -> Result<(), String> {
+        NativeSys.change_directory(path)
+    }
+    fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
+        NativeSys.https_get(request, handle)
+    }
+    fn http_request(&self, request: WebRequest) -> Result<WebResponse, String> {
+        NativeSys.http_request(request)
+    }
+}
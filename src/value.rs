@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    cell::Cell,
     cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
@@ -8,21 +9,70 @@ use std::{
 };
 
 use ecow::EcoVec;
+use num_complex::Complex64;
 
 use crate::{
-    algorithm::{pervade::*, FillContext},
+    algorithm::{
+        pervade::*,
+        simd::{bin_pervade_num_mut, SimdOp},
+        FillContext,
+    },
     array::*,
     cowslice::CowSlice,
-    function::{Function, Signature},
+    function::{Function, Instr, Signature},
     grid_fmt::GridFmt,
     primitive::Primitive,
-    Uiua, UiuaResult,
+    DiagnosticKind, Uiua, UiuaResult,
 };
 
+/// Get the shortest source code for a number
+fn num_source(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".into();
+    }
+    let mut source = if n.is_sign_negative() {
+        "¯".to_string()
+    } else {
+        String::new()
+    };
+    let n = n.abs();
+    if n.is_infinite() {
+        source.push('∞');
+    } else {
+        let plain = n.to_string();
+        let scientific = format!("{n:e}").replace('-', "¯");
+        if scientific.len() < plain.len() {
+            source.push_str(&scientific);
+        } else {
+            source.push_str(&plain);
+        }
+    }
+    source
+}
+
+/// Push a character as it would appear in a character or string literal
+fn push_escaped_char(source: &mut String, c: char, delimiter: char) {
+    match c {
+        '\n' => source.push_str("\\n"),
+        '\r' => source.push_str("\\r"),
+        '\t' => source.push_str("\\t"),
+        '\0' => source.push_str("\\0"),
+        '\\' => source.push_str("\\\\"),
+        ' ' if delimiter == '@' => source.push_str("\\s"),
+        '"' if delimiter == '"' => source.push_str("\\\""),
+        c if c.is_control() && (c as u32) < 0x10000 => {
+            source.push_str(&format!("\\u{:04x}", c as u32))
+        }
+        c => source.push(c),
+    }
+}
+
 #[derive(Clone)]
 pub enum Value {
     Num(Array<f64>),
+    Int(Array<i64>),
     Byte(Array<u8>),
+    Complex(Array<Complex64>),
     Char(Array<char>),
     Func(Array<Arc<Function>>),
 }
@@ -37,7 +87,9 @@ impl fmt::Debug for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Num(array) => array.fmt(f),
+            Self::Int(array) => array.fmt(f),
             Self::Byte(array) => array.fmt(f),
+            Self::Complex(array) => array.fmt(f),
             Self::Char(array) => array.fmt(f),
             Self::Func(array) => array.fmt(f),
         }
@@ -67,6 +119,12 @@ impl Value {
             _ => None,
         }
     }
+    pub fn as_complex_array(&self) -> Option<&Array<Complex64>> {
+        match self {
+            Self::Complex(array) => Some(array),
+            _ => None,
+        }
+    }
     pub fn as_char_array(&self) -> Option<&Array<char>> {
         match self {
             Self::Char(array) => Some(array),
@@ -79,6 +137,72 @@ impl Value {
             _ => None,
         }
     }
+    /// Get the shape and elements of a numeric array as `f32`s
+    ///
+    /// Numbers and integers are narrowed to `f32`, which may lose precision.
+    /// Use [`Value::to_f32_vec_with_tolerance`] to be warned about it.
+    pub fn to_f32_vec(&self, env: &Uiua) -> UiuaResult<(Shape, Vec<f32>)> {
+        self.to_f32_vec_impl(None, env)
+    }
+    /// Like [`Value::to_f32_vec`], but emits a warning diagnostic if narrowing
+    /// any number changes it by more than the given relative error
+    pub fn to_f32_vec_with_tolerance(
+        &self,
+        tolerance: f64,
+        env: &Uiua,
+    ) -> UiuaResult<(Shape, Vec<f32>)> {
+        self.to_f32_vec_impl(Some(tolerance), env)
+    }
+    fn to_f32_vec_impl(&self, tolerance: Option<f64>, env: &Uiua) -> UiuaResult<(Shape, Vec<f32>)> {
+        let data: Vec<f32> = match self {
+            Value::Num(nums) => nums.data.iter().map(|&n| n as f32).collect(),
+            Value::Int(ints) => ints.data.iter().map(|&i| i as f32).collect(),
+            Value::Byte(bytes) => bytes.data.iter().map(|&b| b as f32).collect(),
+            value => {
+                return Err(env.error(format!(
+                    "Cannot convert {} array to f32s",
+                    value.type_name()
+                )))
+            }
+        };
+        if let Some(tolerance) = tolerance {
+            fn max_error(original: impl Iterator<Item = f64>, data: &[f32]) -> f64 {
+                (original.zip(data))
+                    .filter(|&(n, _)| n != 0.0 && !n.is_nan())
+                    .map(|(n, &f)| ((f as f64 - n) / n).abs())
+                    .fold(0.0, f64::max)
+            }
+            let max_error = match self {
+                Value::Num(nums) => max_error(nums.data.iter().copied(), &data),
+                Value::Int(ints) => max_error(ints.data.iter().map(|&i| i as f64), &data),
+                // Bytes are always exact
+                _ => 0.0,
+            };
+            if max_error > tolerance {
+                env.runtime_diagnostic(
+                    format!(
+                        "Converting to f32 changed a number by a relative error of {max_error:e}, \
+                        which exceeds the tolerance of {tolerance:e}"
+                    ),
+                    DiagnosticKind::Warning,
+                );
+            }
+        }
+        Ok((self.shape().into(), data))
+    }
+    /// Create a number array from a shape and `f32` elements
+    ///
+    /// # Panics
+    /// Panics if the number of elements does not match the shape
+    pub fn from_f32_slice(shape: &[usize], data: &[f32]) -> Self {
+        assert_eq!(
+            shape.iter().product::<usize>(),
+            data.len(),
+            "Shape does not match the number of elements"
+        );
+        let data: EcoVec<f64> = data.iter().map(|&f| f as f64).collect();
+        Array::new(shape, data).into()
+    }
     #[inline]
     pub fn into_func_array(self) -> Result<Array<Arc<Function>>, Self> {
         match self {
@@ -99,7 +223,9 @@ impl Value {
     pub fn rows(&self) -> Box<dyn ExactSizeIterator<Item = Self> + '_> {
         match self {
             Self::Num(array) => Box::new(array.rows().map(Value::from)),
+            Self::Int(array) => Box::new(array.rows().map(Value::from)),
             Self::Byte(array) => Box::new(array.rows().map(Value::from)),
+            Self::Complex(array) => Box::new(array.rows().map(Value::from)),
             Self::Char(array) => Box::new(array.rows().map(Value::from)),
             Self::Func(array) => Box::new(array.rows().map(Value::from)),
         }
@@ -107,7 +233,9 @@ impl Value {
     pub fn into_rows(self) -> Box<dyn ExactSizeIterator<Item = Self>> {
         match self {
             Self::Num(array) => Box::new(array.into_rows().map(Value::from)),
+            Self::Int(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Byte(array) => Box::new(array.into_rows().map(Value::from)),
+            Self::Complex(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Char(array) => Box::new(array.into_rows().map(Value::from)),
             Self::Func(array) => Box::new(array.into_rows().map(Value::from)),
         }
@@ -115,7 +243,9 @@ impl Value {
     pub fn into_rows_rev(self) -> Box<dyn Iterator<Item = Self>> {
         match self {
             Self::Num(array) => Box::new(array.into_rows_rev().map(Value::from)),
+            Self::Int(array) => Box::new(array.into_rows_rev().map(Value::from)),
             Self::Byte(array) => Box::new(array.into_rows_rev().map(Value::from)),
+            Self::Complex(array) => Box::new(array.into_rows_rev().map(Value::from)),
             Self::Char(array) => Box::new(array.into_rows_rev().map(Value::from)),
             Self::Func(array) => Box::new(array.into_rows_rev().map(Value::from)),
         }
@@ -123,30 +253,72 @@ impl Value {
     pub fn into_flat_values(self) -> Box<dyn Iterator<Item = Self>> {
         match self {
             Self::Num(array) => Box::new(array.data.into_iter().map(Value::from)),
+            Self::Int(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Byte(array) => Box::new(array.data.into_iter().map(Value::from)),
+            Self::Complex(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Char(array) => Box::new(array.data.into_iter().map(Value::from)),
             Self::Func(array) => Box::new(array.data.into_iter().map(Value::from)),
         }
     }
     pub fn type_name(&self) -> &'static str {
         match self {
-            Self::Num(_) | Self::Byte(_) => "number",
+            Self::Num(_) | Self::Int(_) | Self::Byte(_) => "number",
+            Self::Complex(_) => "complex",
             Self::Char(_) => "character",
             Self::Func(_) => "function",
         }
     }
     pub fn shape(&self) -> &[usize] {
-        self.generic_ref_shallow(Array::shape, Array::shape, Array::shape, Array::shape)
+        self.generic_ref_shallow(
+            Array::shape,
+            Array::shape,
+            Array::shape,
+            Array::shape,
+            Array::shape,
+            Array::shape,
+        )
     }
     pub fn shape_prefixes_match(&self, other: &Self) -> bool {
         self.shape().iter().zip(other.shape()).all(|(a, b)| a == b)
     }
+    /// Check if the shapes of two values can be broadcast together
+    ///
+    /// Shapes are aligned at their last axes, and each pair of axes must
+    /// either be equal or contain a `1`. See [`ShapeExt::broadcast_with`].
+    pub fn shape_broadcast_compatible(&self, other: &Self) -> bool {
+        (Shape::from(self.shape()))
+            .broadcast_with(&Shape::from(other.shape()))
+            .is_some()
+    }
+    /// Broadcast two values to a common shape if their shapes do not match by prefix
+    pub(crate) fn broadcast_together(a: &mut Self, b: &mut Self) {
+        if a.shape_prefixes_match(b) {
+            return;
+        }
+        let Some(shape) = Shape::from(a.shape()).broadcast_with(&Shape::from(b.shape())) else {
+            return;
+        };
+        for value in [a, b] {
+            if value.shape() != shape.as_slice() {
+                *value = value.generic_ref_shallow(
+                    |arr| arr.broadcast_to(&shape).into(),
+                    |arr| arr.broadcast_to(&shape).into(),
+                    |arr| arr.broadcast_to(&shape).into(),
+                    |arr| arr.broadcast_to(&shape).into(),
+                    |arr| arr.broadcast_to(&shape).into(),
+                    |arr| arr.broadcast_to(&shape).into(),
+                );
+            }
+        }
+    }
     pub fn row_count(&self) -> usize {
         self.generic_ref_shallow(
             Array::row_count,
             Array::row_count,
             Array::row_count,
             Array::row_count,
+            Array::row_count,
+            Array::row_count,
         )
     }
     pub fn row_len(&self) -> usize {
@@ -155,6 +327,8 @@ impl Value {
             Array::row_len,
             Array::row_len,
             Array::row_len,
+            Array::row_len,
+            Array::row_len,
         )
     }
     pub fn flat_len(&self) -> usize {
@@ -163,12 +337,16 @@ impl Value {
             Array::flat_len,
             Array::flat_len,
             Array::flat_len,
+            Array::flat_len,
+            Array::flat_len,
         )
     }
     pub(crate) fn first_dim_zero(&self) -> Self {
         match self {
             Self::Num(array) => array.first_dim_zero().into(),
+            Self::Int(array) => array.first_dim_zero().into(),
             Self::Byte(array) => array.first_dim_zero().into(),
+            Self::Complex(array) => array.first_dim_zero().into(),
             Self::Char(array) => array.first_dim_zero().into(),
             Self::Func(array) => array.first_dim_zero().into(),
         }
@@ -179,6 +357,8 @@ impl Value {
             Array::format_shape,
             Array::format_shape,
             Array::format_shape,
+            Array::format_shape,
+            Array::format_shape,
         )
     }
     pub fn rank(&self) -> usize {
@@ -187,7 +367,9 @@ impl Value {
     pub fn shape_mut(&mut self) -> &mut Shape {
         match self {
             Self::Num(array) => &mut array.shape,
+            Self::Int(array) => &mut array.shape,
             Self::Byte(array) => &mut array.shape,
+            Self::Complex(array) => &mut array.shape,
             Self::Char(array) => &mut array.shape,
             Self::Func(array) => &mut array.shape,
         }
@@ -198,6 +380,8 @@ impl Value {
             Array::validate_shape,
             Array::validate_shape,
             Array::validate_shape,
+            Array::validate_shape,
+            Array::validate_shape,
         )
     }
     pub fn row(&self, i: usize) -> Self {
@@ -206,18 +390,153 @@ impl Value {
             |arr| arr.row(i).into(),
             |arr| arr.row(i).into(),
             |arr| arr.row(i).into(),
+            |arr| arr.row(i).into(),
+            |arr| arr.row(i).into(),
         )
     }
+    /// Get the sub-array at the given leading indices
+    ///
+    /// An empty list of indices returns the whole value.
+    pub fn rank_slice(&self, indices: &[usize], env: &Uiua) -> UiuaResult<Self> {
+        if indices.len() > self.rank() {
+            return Err(env.error(format!(
+                "Cannot slice rank {} array with {} indices",
+                self.rank(),
+                indices.len()
+            )));
+        }
+        let mut value = self.clone();
+        for (axis, &i) in indices.iter().enumerate() {
+            let len = value.row_count();
+            if i >= len {
+                return Err(env.error(format!(
+                    "Index {i} is out of bounds of length {len} (axis {axis}) in shape {}",
+                    self.format_shape()
+                )));
+            }
+            value = value.row(i);
+        }
+        Ok(value)
+    }
+    /// Apply a function to every non-box value nested in this one, preserving the box structure
+    ///
+    /// Functions that are not boxes are left unchanged.
+    pub fn map_leaves(&self, f: impl Fn(&Value) -> UiuaResult<Value>) -> UiuaResult<Value> {
+        enum Task<'a> {
+            Visit(&'a Value),
+            /// Rebuild a box array, taking the mapped boxes from the results
+            Build(&'a Array<Arc<Function>>, usize),
+        }
+        let mut tasks = vec![Task::Visit(self)];
+        let mut results: Vec<Value> = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(Value::Func(arr)) => {
+                    let boxes: Vec<&Value> = arr.data.iter().filter_map(|f| f.as_boxed()).collect();
+                    tasks.push(Task::Build(arr, boxes.len()));
+                    tasks.extend(boxes.into_iter().rev().map(Task::Visit));
+                }
+                Task::Visit(value) => results.push(f(value)?),
+                Task::Build(arr, count) => {
+                    let mut mapped = results.drain(results.len() - count..);
+                    let data: CowSlice<_> = (arr.data.iter())
+                        .map(|func| {
+                            if func.as_boxed().is_some() {
+                                Arc::new(Function::boxed(mapped.next().unwrap()))
+                            } else {
+                                func.clone()
+                            }
+                        })
+                        .collect();
+                    drop(mapped);
+                    results.push(Array::new(arr.shape.clone(), data).into());
+                }
+            }
+        }
+        Ok(results.pop().unwrap())
+    }
+    /// Combine the rows of this value from first to last, starting with an initial value
+    ///
+    /// `f` is called with the accumulated value and the next row.
+    /// The first error returned by `f` stops the fold.
+    /// If there are no rows, the initial value is returned.
+    pub fn fold_rows(
+        self,
+        init: Value,
+        f: impl FnMut(Value, Value) -> UiuaResult<Value>,
+    ) -> UiuaResult<Value> {
+        self.into_rows().try_fold(init, f)
+    }
+    /// Combine the rows of this value from first to last, keeping every intermediate result
+    ///
+    /// `f` is called with the accumulated value and the next row.
+    /// The first row is the initial accumulated value, so the result has the same number
+    /// of rows as this value. This is like [`scan`](crate::primitive::Primitive::Scan).
+    pub fn scan_rows(
+        self,
+        mut f: impl FnMut(Value, Value) -> UiuaResult<Value>,
+        env: &Uiua,
+    ) -> UiuaResult<Value> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot scan rank 0 array"));
+        }
+        if self.row_count() == 0 {
+            return Ok(self.first_dim_zero());
+        }
+        let mut scanned = Vec::with_capacity(self.row_count());
+        let mut rows = self.into_rows();
+        let mut acc = rows.next().unwrap();
+        for row in rows {
+            let next = f(acc.clone(), row)?;
+            scanned.push(acc);
+            acc = next;
+        }
+        scanned.push(acc);
+        Value::from_row_values(scanned, env)
+    }
+    /// Copy this value so that it shares no data with the original
+    ///
+    /// Cloning a value normally shares its data until one of the clones is modified.
+    /// This copies all data eagerly, including boxed values and values in function bodies.
+    pub fn deep_clone_detached(&self) -> Value {
+        fn detach<T: Clone>(arr: &Array<T>, f: impl Fn(&T) -> T) -> Array<T> {
+            Array::new(
+                arr.shape.clone(),
+                arr.data.iter().map(f).collect::<CowSlice<_>>(),
+            )
+        }
+        match self {
+            Value::Num(arr) => detach(arr, |&n| n).into(),
+            Value::Int(arr) => detach(arr, |&i| i).into(),
+            Value::Byte(arr) => detach(arr, |&b| b).into(),
+            Value::Complex(arr) => detach(arr, |&c| c).into(),
+            Value::Char(arr) => detach(arr, |&c| c).into(),
+            Value::Func(arr) => detach(arr, |func| {
+                let mut func = Function::clone(func);
+                for instr in &mut func.instrs {
+                    if let Instr::Push(val) = instr {
+                        **val = val.deep_clone_detached();
+                    }
+                }
+                Arc::new(func)
+            })
+            .into(),
+        }
+    }
     pub fn generic_into_shallow<T>(
         self,
         n: impl FnOnce(Array<f64>) -> T,
+        i: impl FnOnce(Array<i64>) -> T,
         b: impl FnOnce(Array<u8>) -> T,
+        co: impl FnOnce(Array<Complex64>) -> T,
         c: impl FnOnce(Array<char>) -> T,
         f: impl FnOnce(Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
+            Self::Int(array) => i(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => f(array),
         }
@@ -225,16 +544,20 @@ impl Value {
     pub fn generic_into_deep<T>(
         self,
         n: impl FnOnce(Array<f64>) -> T,
+        i: impl FnOnce(Array<i64>) -> T,
         b: impl FnOnce(Array<u8>) -> T,
+        co: impl FnOnce(Array<Complex64>) -> T,
         c: impl FnOnce(Array<char>) -> T,
         f: impl FnOnce(Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
+            Self::Int(array) => i(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => match array.into_unboxed() {
-                Ok(value) => value.generic_into_deep(n, b, c, f),
+                Ok(value) => value.generic_into_deep(n, i, b, co, c, f),
                 Err(array) => f(array),
             },
         }
@@ -242,13 +565,17 @@ impl Value {
     pub fn generic_ref_shallow<'a, T: 'a>(
         &'a self,
         n: impl FnOnce(&'a Array<f64>) -> T,
+        i: impl FnOnce(&'a Array<i64>) -> T,
         b: impl FnOnce(&'a Array<u8>) -> T,
+        co: impl FnOnce(&'a Array<Complex64>) -> T,
         c: impl FnOnce(&'a Array<char>) -> T,
         f: impl FnOnce(&'a Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
+            Self::Int(array) => i(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => f(array),
         }
@@ -256,53 +583,81 @@ impl Value {
     pub fn generic_ref_deep<'a, T: 'a>(
         &'a self,
         n: impl FnOnce(&'a Array<f64>) -> T,
+        i: impl FnOnce(&'a Array<i64>) -> T,
         b: impl FnOnce(&'a Array<u8>) -> T,
+        co: impl FnOnce(&'a Array<Complex64>) -> T,
         c: impl FnOnce(&'a Array<char>) -> T,
         f: impl FnOnce(&'a Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
+            Self::Int(array) => i(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => {
                 if let Some(value) = array.as_boxed() {
-                    value.generic_ref_deep(n, b, c, f)
+                    value.generic_ref_deep(n, i, b, co, c, f)
                 } else {
                     f(array)
                 }
             }
         }
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn generic_ref_env_shallow<'a, T: 'a>(
         &'a self,
         n: impl FnOnce(&'a Array<f64>, &Uiua) -> UiuaResult<T>,
+        i: impl FnOnce(&'a Array<i64>, &Uiua) -> UiuaResult<T>,
         b: impl FnOnce(&'a Array<u8>, &Uiua) -> UiuaResult<T>,
+        co: impl FnOnce(&'a Array<Complex64>, &Uiua) -> UiuaResult<T>,
         c: impl FnOnce(&'a Array<char>, &Uiua) -> UiuaResult<T>,
         f: impl FnOnce(&'a Array<Arc<Function>>, &Uiua) -> UiuaResult<T>,
         env: &Uiua,
     ) -> UiuaResult<T> {
-        self.generic_ref_shallow(|a| n(a, env), |a| b(a, env), |a| c(a, env), |a| f(a, env))
+        self.generic_ref_shallow(
+            |a| n(a, env),
+            |a| i(a, env),
+            |a| b(a, env),
+            |a| co(a, env),
+            |a| c(a, env),
+            |a| f(a, env),
+        )
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn generic_ref_env_deep<'a, T: 'a>(
         &'a self,
         n: impl FnOnce(&'a Array<f64>, &Uiua) -> UiuaResult<T>,
+        i: impl FnOnce(&'a Array<i64>, &Uiua) -> UiuaResult<T>,
         b: impl FnOnce(&'a Array<u8>, &Uiua) -> UiuaResult<T>,
+        co: impl FnOnce(&'a Array<Complex64>, &Uiua) -> UiuaResult<T>,
         c: impl FnOnce(&'a Array<char>, &Uiua) -> UiuaResult<T>,
         f: impl FnOnce(&'a Array<Arc<Function>>, &Uiua) -> UiuaResult<T>,
         env: &Uiua,
     ) -> UiuaResult<T> {
-        self.generic_ref_deep(|a| n(a, env), |a| b(a, env), |a| c(a, env), |a| f(a, env))
+        self.generic_ref_deep(
+            |a| n(a, env),
+            |a| i(a, env),
+            |a| b(a, env),
+            |a| co(a, env),
+            |a| c(a, env),
+            |a| f(a, env),
+        )
     }
     pub fn generic_mut_shallow<T>(
         &mut self,
         n: impl FnOnce(&mut Array<f64>) -> T,
+        i: impl FnOnce(&mut Array<i64>) -> T,
         b: impl FnOnce(&mut Array<u8>) -> T,
+        co: impl FnOnce(&mut Array<Complex64>) -> T,
         c: impl FnOnce(&mut Array<char>) -> T,
         f: impl FnOnce(&mut Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
+            Self::Int(array) => i(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => f(array),
         }
@@ -310,17 +665,21 @@ impl Value {
     pub fn generic_mut_deep<T>(
         &mut self,
         n: impl FnOnce(&mut Array<f64>) -> T,
+        i: impl FnOnce(&mut Array<i64>) -> T,
         b: impl FnOnce(&mut Array<u8>) -> T,
+        co: impl FnOnce(&mut Array<Complex64>) -> T,
         c: impl FnOnce(&mut Array<char>) -> T,
         f: impl FnOnce(&mut Array<Arc<Function>>) -> T,
     ) -> T {
         match self {
             Self::Num(array) => n(array),
+            Self::Int(array) => i(array),
             Self::Byte(array) => b(array),
+            Self::Complex(array) => co(array),
             Self::Char(array) => c(array),
             Self::Func(array) => {
                 if let Some(value) = array.as_boxed_mut() {
-                    value.generic_mut_deep(n, b, c, f)
+                    value.generic_mut_deep(n, i, b, co, c, f)
                 } else {
                     f(array)
                 }
@@ -331,7 +690,9 @@ impl Value {
     pub(crate) fn reserve_min(&mut self, min: usize) {
         match self {
             Self::Num(arr) => arr.data.reserve_min(min),
+            Self::Int(arr) => arr.data.reserve_min(min),
             Self::Byte(arr) => arr.data.reserve_min(min),
+            Self::Complex(arr) => arr.data.reserve_min(min),
             Self::Char(arr) => arr.data.reserve_min(min),
             Self::Func(arr) => arr.data.reserve_min(min),
         }
@@ -340,11 +701,92 @@ impl Value {
     pub fn show(&self) -> String {
         match self {
             Self::Num(array) => array.grid_string(),
+            Self::Int(array) => array.grid_string(),
             Self::Byte(array) => array.grid_string(),
+            Self::Complex(array) => array.grid_string(),
             Self::Char(array) => array.grid_string(),
             Self::Func(array) => array.grid_string(),
         }
     }
+    /// Get Uiua source code that evaluates to the value
+    ///
+    /// Fails if the value contains complex numbers or functions that are not boxes.
+    pub fn to_source(&self, env: &Uiua) -> UiuaResult<String> {
+        let mut source = String::new();
+        self.write_source(&mut source, env)?;
+        Ok(source)
+    }
+    fn write_source(&self, source: &mut String, env: &Uiua) -> UiuaResult {
+        let shape = self.shape();
+        if shape.contains(&0) {
+            if self.rank() > 1 {
+                source.push_str("↯[");
+                for (i, dim) in shape.iter().enumerate() {
+                    if i > 0 {
+                        source.push(' ');
+                    }
+                    source.push_str(&dim.to_string());
+                }
+                source.push_str("] ");
+            }
+            source.push_str(match self {
+                Value::Char(_) => "\"\"",
+                Value::Func(_) => "{}",
+                _ => "[]",
+            });
+            return Ok(());
+        }
+        match (self, self.rank()) {
+            (Value::Num(arr), 0) => source.push_str(&num_source(arr.data[0])),
+            (Value::Int(arr), 0) => source.push_str(&num_source(arr.data[0] as f64)),
+            (Value::Byte(arr), 0) => source.push_str(&num_source(arr.data[0].into())),
+            (Value::Complex(_), _) => {
+                return Err(env.error("Complex numbers cannot be converted to source"))
+            }
+            (Value::Char(arr), 0) => {
+                source.push('@');
+                push_escaped_char(source, arr.data[0], '@');
+            }
+            (Value::Char(arr), 1) => {
+                source.push('"');
+                for &c in &arr.data {
+                    push_escaped_char(source, c, '"');
+                }
+                source.push('"');
+            }
+            (Value::Func(arr), 0) => {
+                let Some(inner) = arr.data[0].as_boxed() else {
+                    return Err(env.error("Functions cannot be converted to source"));
+                };
+                source.push('□');
+                inner.write_source(source, env)?;
+            }
+            (Value::Func(arr), 1) => {
+                source.push('{');
+                for (i, f) in arr.data.iter().enumerate() {
+                    let Some(inner) = f.as_boxed() else {
+                        return Err(env.error("Functions cannot be converted to source"));
+                    };
+                    if i > 0 {
+                        source.push(' ');
+                    }
+                    inner.write_source(source, env)?;
+                }
+                source.push('}');
+            }
+            _ => {
+                source.push('[');
+                for (i, row) in self.rows().enumerate() {
+                    if i > 0 {
+                        source.push(' ');
+                    }
+                    row.write_source(source, env)?;
+                }
+                source.push(']');
+            }
+        }
+        Ok(())
+    }
     pub fn as_primitive(&self) -> Option<(Primitive, usize)> {
         if let Value::Func(fs) = self {
             if fs.rank() == 0 {
@@ -378,6 +820,14 @@ impl Value {
                 }
                 num != 0.0
             }
+            Value::Int(ints) => {
+                if ints.rank() > 0 {
+                    return Err(
+                        env.error(format!("{requirement}, but its rank is {}", ints.rank()))
+                    );
+                }
+                ints.data[0] != 0
+            }
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
                     return Err(
@@ -408,6 +858,18 @@ impl Value {
                 }
                 num as usize
             }
+            Value::Int(ints) => {
+                if ints.rank() > 0 {
+                    return Err(
+                        env.error(format!("{requirement}, but its rank is {}", ints.rank()))
+                    );
+                }
+                let int = ints.data[0];
+                if int < 0 {
+                    return Err(env.error(format!("{requirement}, but it is negative")));
+                }
+                int as usize
+            }
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
                     return Err(
@@ -435,6 +897,14 @@ impl Value {
                 }
                 num as isize
             }
+            Value::Int(ints) => {
+                if ints.rank() > 0 {
+                    return Err(
+                        env.error(format!("{requirement}, but its rank is {}", ints.rank()))
+                    );
+                }
+                ints.data[0] as isize
+            }
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
                     return Err(
@@ -458,6 +928,14 @@ impl Value {
                 }
                 nums.data[0]
             }
+            Value::Int(ints) => {
+                if ints.rank() > 0 {
+                    return Err(
+                        env.error(format!("{requirement}, but its rank is {}", ints.rank()))
+                    );
+                }
+                ints.data[0] as f64
+            }
             Value::Byte(bytes) => {
                 if bytes.rank() > 0 {
                     return Err(
@@ -479,6 +957,13 @@ impl Value {
             |f| f as usize,
         )
     }
+    /// Interpret the value as a shape
+    ///
+    /// The value must be a scalar or list of natural numbers
+    pub fn try_into_shape(&self, env: &Uiua) -> UiuaResult<Shape> {
+        let dims = self.as_naturals(env, "Shape must be a list of natural numbers")?;
+        Ok(dims.into_iter().collect())
+    }
     pub fn as_bytes(&self, env: &Uiua, requirement: &'static str) -> UiuaResult<Vec<u8>> {
         self.as_number_list(
             env,
@@ -488,6 +973,12 @@ impl Value {
         )
     }
     pub fn as_integers(&self, env: &Uiua, requirement: &'static str) -> UiuaResult<Vec<isize>> {
+        if let Value::Int(ints) = self {
+            if ints.rank() > 1 {
+                return Err(env.error(format!("{requirement}, but its rank is {}", ints.rank())));
+            }
+            return Ok(ints.data.iter().map(|&i| i as isize).collect());
+        }
         self.as_number_list(env, requirement, |f| f.fract() == 0.0, |f| f as isize)
     }
     pub fn as_rank_list(
@@ -534,6 +1025,22 @@ impl Value {
                 }
                 result
             }
+            Value::Int(ints) => {
+                if ints.rank() > 1 {
+                    return Err(
+                        env.error(format!("{requirement}, but its rank is {}", ints.rank()))
+                    );
+                }
+                let mut result = Vec::with_capacity(ints.row_count());
+                for &int in ints.data() {
+                    let num = int as f64;
+                    if !test(num) {
+                        return Err(env.error(requirement));
+                    }
+                    result.push(convert(num));
+                }
+                result
+            }
             Value::Byte(bytes) => {
                 if bytes.rank() > 1 {
                     return Err(
@@ -580,6 +1087,23 @@ impl Value {
                 }
                 Array::new(self.shape(), result)
             }
+            Value::Int(ints) => {
+                if !test_shape(self.shape()) {
+                    return Err(env.error(format!(
+                        "{requirement}, but its shape is {}",
+                        ints.format_shape()
+                    )));
+                }
+                let mut result = EcoVec::with_capacity(ints.flat_len());
+                for &int in ints.data() {
+                    let num = int as f64;
+                    if !test_num(num) {
+                        return Err(env.error(requirement));
+                    }
+                    result.push(convert_num(num));
+                }
+                Array::new(self.shape(), result)
+            }
             Value::Byte(bytes) => {
                 if !test_shape(self.shape()) {
                     return Err(env.error(format!(
@@ -641,6 +1165,12 @@ impl Value {
                 }
                 a.data.into_iter().map(|f| f as u8).collect()
             }
+            Value::Int(a) => {
+                if a.rank() != 1 {
+                    return Err(env.error(format!("{requirement}, but its rank is {}", a.rank())));
+                }
+                a.data.into_iter().map(|i| i as u8).collect()
+            }
             Value::Char(a) => {
                 if a.rank() != 1 {
                     return Err(env.error(format!("{requirement}, but its rank is {}", a.rank())));
@@ -655,13 +1185,14 @@ impl Value {
             }
         })
     }
-    /// Turn a number array into a byte array if no information is lost.
+    /// Turn a number or integer array into a byte array if no information is lost.
     pub fn compress(&mut self) {
-        if let Value::Num(nums) = self {
-            if nums
-                .data
-                .iter()
-                .all(|n| n.fract() == 0.0 && *n <= u8::MAX as f64 && *n >= 0.0)
+        match self {
+            Value::Num(nums)
+                if nums
+                    .data
+                    .iter()
+                    .all(|n| n.fract() == 0.0 && *n <= u8::MAX as f64 && *n >= 0.0) =>
             {
                 let mut bytes = EcoVec::with_capacity(nums.flat_len());
                 for n in take(&mut nums.data) {
@@ -669,12 +1200,68 @@ impl Value {
                 }
                 *self = (take(&mut nums.shape), bytes).into();
             }
+            Value::Int(ints) if ints.data.iter().all(|&i| u8::try_from(i).is_ok()) => {
+                let mut bytes = EcoVec::with_capacity(ints.flat_len());
+                for i in take(&mut ints.data) {
+                    bytes.push(i as u8);
+                }
+                *self = (take(&mut ints.shape), bytes).into();
+            }
+            _ => {}
+        }
+    }
+    /// Turn a byte array into a number array, leaving other arrays unchanged
+    ///
+    /// This is the inverse of [`Value::compress`] for byte arrays.
+    pub fn widen_to_num(&mut self) {
+        if let Value::Byte(bytes) = self {
+            *self = Value::Num(take(bytes).convert());
+        }
+    }
+    /// Convert an integer array to a number array if the other value is not also one
+    pub(crate) fn unify_ints(a: Self, b: Self) -> (Self, Self) {
+        match (a, b) {
+            (Value::Int(a), b) if !matches!(b, Value::Int(_)) => (Value::Num(a.into_nums()), b),
+            (a, Value::Int(b)) if !matches!(a, Value::Int(_)) => (a, Value::Num(b.into_nums())),
+            ab => ab,
+        }
+    }
+    pub(crate) fn unify_ints_mut(&mut self, other: Self) -> Self {
+        let (a, b) = Value::unify_ints(take(self), other);
+        *self = a;
+        b
+    }
+    pub(crate) fn unify_ints_ref<'a>(a: &'a Self, b: &'a Self) -> (Cow<'a, Self>, Cow<'a, Self>) {
+        match (a, b) {
+            (Value::Int(a), b) if !matches!(b, Value::Int(_)) => (
+                Cow::Owned(Value::Num(a.clone().into_nums())),
+                Cow::Borrowed(b),
+            ),
+            (a, Value::Int(b)) if !matches!(a, Value::Int(_)) => (
+                Cow::Borrowed(a),
+                Cow::Owned(Value::Num(b.clone().into_nums())),
+            ),
+            (a, b) => (Cow::Borrowed(a), Cow::Borrowed(b)),
+        }
+    }
+    /// Turn a number array into a complex array if it has negative
+    /// elements and a complex fill is set
+    fn complex_if_negative(self, env: &Uiua) -> Self {
+        match self {
+            Value::Num(nums)
+                if env.complex_fill().is_some() && nums.data.iter().any(|&n| n < 0.0) =>
+            {
+                nums.convert_with(Complex64::from).into()
+            }
+            value => value,
         }
     }
     pub fn coerce_to_function(self) -> Array<Arc<Function>> {
         match self {
             Value::Num(arr) => arr.convert_with(|n| Arc::new(Function::boxed(n))),
+            Value::Int(arr) => arr.convert_with(|n| Arc::new(Function::boxed(n))),
             Value::Byte(arr) => arr.convert_with(|n| Arc::new(Function::boxed(n))),
+            Value::Complex(arr) => arr.convert_with(|n| Arc::new(Function::boxed(n))),
             Value::Char(arr) => arr.convert_with(|n| Arc::new(Function::boxed(n))),
             Value::Func(arr) => arr,
         }
@@ -682,7 +1269,11 @@ impl Value {
     pub fn coerce_as_function(&self) -> Cow<Array<Arc<Function>>> {
         match self {
             Value::Num(arr) => Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::boxed(n)))),
+            Value::Int(arr) => Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::boxed(n)))),
             Value::Byte(arr) => Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::boxed(n)))),
+            Value::Complex(arr) => {
+                Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::boxed(n))))
+            }
             Value::Char(arr) => Cow::Owned(arr.convert_ref_with(|n| Arc::new(Function::boxed(n)))),
             Value::Func(arr) => Cow::Borrowed(arr),
         }
@@ -730,7 +1321,9 @@ macro_rules! value_from {
 }
 
 value_from!(f64, Num);
+value_from!(i64, Int);
 value_from!(u8, Byte);
+value_from!(Complex64, Complex);
 value_from!(char, Char);
 value_from!(Arc<Function>, Func);
 
@@ -780,14 +1373,18 @@ macro_rules! value_un_impl {
     ($name:ident, $(
         $([$in_place:ident, $f:ident])?
         $(($make_new:ident, $f2:ident))?
-    ),* $(,)?) => {
+    ),* $(,)? $(; $pre:ident)?) => {
         impl Value {
             pub fn $name(self, env: &Uiua) -> UiuaResult<Self> {
-                Ok(match self {
+                let value = self;
+                $(let value = value.$pre(env);)?
+                Ok(match value {
                     $($(Self::$in_place(mut array) => {
-                        for val in &mut array.data {
-                            *val = $name::$f(*val);
-                        }
+                        par_for_each(
+                            array.data.as_mut_slice(),
+                            env.parallel_min_len(),
+                            |val| *val = $name::$f(*val),
+                        );
                         array.into()
                     },)*)*
                     $($(Self::$make_new(array) => {
@@ -808,6 +1405,7 @@ macro_rules! value_un_impl {
                         array.data = new_data.into();
                         array.into()
                     }
+                    Value::Int(array) => Value::Num(array.into_nums()).$name(env)?,
                     val => return Err($name::error(val.type_name(), env))
                 })
             }
@@ -819,16 +1417,104 @@ value_un_impl!(neg, [Num, num], (Byte, byte));
 value_un_impl!(not, [Num, num], (Byte, byte));
 value_un_impl!(abs, [Num, num], (Byte, byte));
 value_un_impl!(sign, [Num, num], [Byte, byte]);
-value_un_impl!(sqrt, [Num, num], (Byte, byte));
+value_un_impl!(sqrt, [Num, num], (Byte, byte), [Complex, com]; complex_if_negative);
 value_un_impl!(sin, [Num, num], (Byte, byte));
 value_un_impl!(cos, [Num, num], (Byte, byte));
 value_un_impl!(tan, [Num, num], (Byte, byte));
 value_un_impl!(asin, [Num, num], (Byte, byte));
 value_un_impl!(acos, [Num, num], (Byte, byte));
+value_un_impl!(sinh, [Num, num], (Byte, byte));
+value_un_impl!(cosh, [Num, num], (Byte, byte));
+value_un_impl!(tanh, [Num, num], (Byte, byte));
+value_un_impl!(cbrt, [Num, num], (Byte, byte));
+value_un_impl!(gamma, [Num, num], (Byte, byte));
+value_un_impl!(factorial, [Num, num], (Byte, byte));
+value_un_impl!(log2, [Num, num], (Byte, byte));
+value_un_impl!(log10, [Num, num], (Byte, byte));
 value_un_impl!(floor, [Num, num], [Byte, byte]);
 value_un_impl!(ceil, [Num, num], [Byte, byte]);
 value_un_impl!(round, [Num, num], [Byte, byte]);
 
+/// Apply a checked integer operation, falling back to floating point
+/// arithmetic if any element overflows
+fn int_bin_retry_overflow(
+    a: Array<i64>,
+    b: Array<i64>,
+    env: &Uiua,
+    name: &str,
+    checked: fn(i64, i64) -> Option<i64>,
+    fallback: fn(f64, f64) -> f64,
+) -> UiuaResult<Value> {
+    let overflowed = Cell::new(false);
+    let res = bin_pervade(
+        a.clone(),
+        b.clone(),
+        env,
+        InfalliblePervasiveFn::new(|a, b| {
+            checked(a, b).unwrap_or_else(|| {
+                overflowed.set(true);
+                0
+            })
+        }),
+    )?;
+    if !overflowed.get() {
+        return Ok(res.into());
+    }
+    env.runtime_diagnostic(
+        format!("Integer overflow in {name}, falling back to floating point"),
+        DiagnosticKind::Warning,
+    );
+    Ok(bin_pervade(
+        a.into_nums(),
+        b.into_nums(),
+        env,
+        InfalliblePervasiveFn::new(fallback),
+    )?
+    .into())
+}
+
+/// Ensure that all numbers in both operands are integers
+fn integer_operands(a: &Value, b: &Value, name: &str, env: &Uiua) -> UiuaResult {
+    for value in [a, b] {
+        if let Value::Num(nums) = value {
+            if nums.data.iter().any(|n| n.fract() != 0.0) {
+                return Err(env.error(format!(
+                    "Arguments to {name} must be integers, but it has a fractional part"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Ensure that the shift amount is a non-negative integer and the shifted value is integral
+fn shift_operands(amount: &Value, value: &Value, name: &str, env: &Uiua) -> UiuaResult {
+    integer_operands(amount, value, name, env)?;
+    let negative = match amount {
+        Value::Num(nums) => nums.data.iter().any(|&n| n < 0.0),
+        Value::Int(ints) => ints.data.iter().any(|&i| i < 0),
+        _ => false,
+    };
+    if negative {
+        return Err(env.error(format!(
+            "Shift amount for {name} must be a natural number, but it is negative"
+        )));
+    }
+    Ok(())
+}
+
+/// Ensure that the number of digits to round to is an integer
+fn digit_count_operand(digits: &Value, _: &Value, name: &str, env: &Uiua) -> UiuaResult {
+    if let Value::Num(nums) = digits {
+        if nums.data.iter().any(|n| n.fract() != 0.0) {
+            return Err(env.error(format!(
+                "Digit count for {name} must be an integer, but it has a fractional part"
+            )));
+        }
+    }
+    Ok(())
+}
+
 macro_rules! val_retry {
     (Byte, $env:expr) => {
         $env.num_fill().is_some()
@@ -838,24 +1524,48 @@ macro_rules! val_retry {
     };
 }
 
+/// Use the SIMD kernels for arithmetic on numbers
+macro_rules! pervade_mut {
+    (add, Num, $a:expr, $b:expr, $env:expr, $f:expr) => {
+        bin_pervade_num_mut($a, $b, $env, SimdOp::Add)
+    };
+    (sub, Num, $a:expr, $b:expr, $env:expr, $f:expr) => {
+        bin_pervade_num_mut($a, $b, $env, SimdOp::Sub)
+    };
+    (mul, Num, $a:expr, $b:expr, $env:expr, $f:expr) => {
+        bin_pervade_num_mut($a, $b, $env, SimdOp::Mul)
+    };
+    (div, Num, $a:expr, $b:expr, $env:expr, $f:expr) => {
+        bin_pervade_num_mut($a, $b, $env, SimdOp::Div)
+    };
+    ($module:ident, $variant:ident, $a:expr, $b:expr, $env:expr, $f:expr) => {
+        bin_pervade_mut($a, $b, $env, $f)
+    };
+}
+
 macro_rules! value_bin_impl {
-    ($name:ident, $(
+    ($module:ident as $name:ident, $(
         $(($na:ident, $nb:ident, $f:ident $(, $retry:ident)?))*
         $([$ip:ident, $f2:ident $(, $retry2:ident)?])*
-    ),* ) => {
+        $(<Int, $checked:ident>)*
+    ),* $(; $check:ident)?) => {
         impl Value {
             #[allow(unreachable_patterns)]
-            pub fn $name(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+            pub fn $name(mut self, mut other: Self, env: &Uiua) -> UiuaResult<Self> {
+                $($check(&self, &other, stringify!($module), env)?;)?
+                if env.broadcasting() {
+                    Value::broadcast_together(&mut self, &mut other);
+                }
                 Ok(match (self, other) {
                     $($((Value::$ip(mut a), Value::$ip(b)) => {
                         if val_retry!($ip, env) {
                             let mut a_clone = a.clone();
-                            if let Err(e) = bin_pervade_mut(&mut a_clone, b.clone(), env, $name::$f2) {
+                            if let Err(e) = bin_pervade_mut(&mut a_clone, b.clone(), env, $module::$f2) {
                                 if e.is_fill() {
                                     $(
                                         let mut a = a.convert();
                                         let b = b.convert();
-                                        bin_pervade_mut(&mut a, b, env, $name::$retry2)?;
+                                        bin_pervade_mut(&mut a, b, env, $module::$retry2)?;
                                         return Ok(a.into());
                                     )*
                                 }
@@ -864,24 +1574,27 @@ macro_rules! value_bin_impl {
                                 a_clone.into()
                             }
                         } else {
-                            bin_pervade_mut(&mut a, b, env, $name::$f2)?;
+                            pervade_mut!($module, $ip, &mut a, b, env, $module::$f2)?;
                             a.into()
                         }
                     },)*)*
+                    $($((Value::Int(a), Value::Int(b)) => {
+                        int_bin_retry_overflow(a, b, env, stringify!($module), $module::$checked, $module::num_num)?
+                    },)*)*
                     $($((Value::$na(a), Value::$nb(b)) => {
                         if val_retry!($na, env) || val_retry!($nb, env) {
-                            let res = bin_pervade(a.clone(), b.clone(), env, InfalliblePervasiveFn::new($name::$f));
+                            let res = bin_pervade(a.clone(), b.clone(), env, InfalliblePervasiveFn::new($module::$f));
                             match res {
                                 Ok(arr) => arr.into(),
                                 #[allow(unreachable_code, unused_variables)]
                                 Err(e) if e.is_fill() => {
-                                    $(return bin_pervade(a.convert(), b.convert(), env, InfalliblePervasiveFn::new($name::$retry)).map(Into::into);)?
+                                    $(return bin_pervade(a.convert(), b.convert(), env, InfalliblePervasiveFn::new($module::$retry)).map(Into::into);)?
                                     return Err(e);
                                 }
                                 Err(e) => return Err(e),
                             }
                         } else {
-                            bin_pervade(a, b, env, InfalliblePervasiveFn::new($name::$f))?.into()
+                            bin_pervade(a, b, env, InfalliblePervasiveFn::new($module::$f))?.into()
                         }
                     },)*)*
                     (Value::Func(a), b) => {
@@ -910,16 +1623,27 @@ macro_rules! value_bin_impl {
                             }
                         }
                     },
-                    (a, b) => return Err($name::error(a.type_name(), b.type_name(), env)),
+                    (Value::Int(a), b) => Value::$name(Value::Num(a.into_nums()), b, env)?,
+                    (a, Value::Int(b)) => Value::$name(a, Value::Num(b.into_nums()), env)?,
+                    (a, b) => return Err($module::error(a.type_name(), b.type_name(), env)),
                 })
             }
         }
     };
+    ($name:ident, $($rest:tt)*) => {
+        value_bin_impl!($name as $name, $($rest)*);
+    };
 }
 
 value_bin_impl!(
     add,
     [Num, num_num],
+    <Int, int_int>,
+    [Complex, com_com],
+    (Num, Complex, num_com),
+    (Complex, Num, com_num),
+    (Byte, Complex, byte_com),
+    (Complex, Byte, com_byte),
     (Num, Char, num_char),
     (Char, Num, char_num),
     (Byte, Byte, byte_byte, num_num),
@@ -932,6 +1656,12 @@ value_bin_impl!(
 value_bin_impl!(
     sub,
     [Num, num_num],
+    <Int, int_int>,
+    [Complex, com_com],
+    (Num, Complex, num_com),
+    (Complex, Num, com_num),
+    (Byte, Complex, byte_com),
+    (Complex, Byte, com_byte),
     (Num, Char, num_char),
     (Char, Char, char_char),
     (Byte, Byte, byte_byte, num_num),
@@ -943,6 +1673,12 @@ value_bin_impl!(
 value_bin_impl!(
     mul,
     [Num, num_num],
+    <Int, int_int>,
+    [Complex, com_com],
+    (Num, Complex, num_com),
+    (Complex, Num, com_num),
+    (Byte, Complex, byte_com),
+    (Complex, Byte, com_byte),
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
@@ -950,6 +1686,11 @@ value_bin_impl!(
 value_bin_impl!(
     div,
     [Num, num_num],
+    [Complex, com_com],
+    (Num, Complex, num_com),
+    (Complex, Num, com_num),
+    (Byte, Complex, byte_com),
+    (Complex, Byte, com_byte),
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
@@ -976,6 +1717,166 @@ value_bin_impl!(
     (Num, Byte, num_byte, num_num),
 );
 value_bin_impl!(atan2, [Num, num_num]);
+value_bin_impl!(
+    hypot,
+    [Num, num_num],
+    (Byte, Byte, byte_byte),
+    (Byte, Num, byte_num),
+    (Num, Byte, num_byte),
+);
+value_bin_impl!(
+    roundto as round_to,
+    [Num, num_num],
+    [Byte, byte_byte],
+    (Byte, Num, byte_num, num_num),
+    (Num, Byte, num_byte, num_num);
+    digit_count_operand
+);
+value_bin_impl!(
+    gcd,
+    [Num, num_num],
+    <Int, int_int>,
+    (Byte, Byte, byte_byte, num_num),
+    (Byte, Num, byte_num, num_num),
+    (Num, Byte, num_byte, num_num);
+    integer_operands
+);
+value_bin_impl!(
+    lcm,
+    [Num, num_num],
+    <Int, int_int>,
+    (Byte, Byte, byte_byte, num_num),
+    (Byte, Num, byte_num, num_num),
+    (Num, Byte, num_byte, num_num);
+    integer_operands
+);
+value_bin_impl!(
+    bit_and,
+    [Num, num_num],
+    <Int, int_int>,
+    [Byte, byte_byte],
+    (Byte, Num, byte_num),
+    (Num, Byte, num_byte);
+    integer_operands
+);
+value_bin_impl!(
+    bit_or,
+    [Num, num_num],
+    <Int, int_int>,
+    [Byte, byte_byte],
+    (Byte, Num, byte_num),
+    (Num, Byte, num_byte);
+    integer_operands
+);
+value_bin_impl!(
+    bit_xor,
+    [Num, num_num],
+    <Int, int_int>,
+    [Byte, byte_byte],
+    (Byte, Num, byte_num),
+    (Num, Byte, num_byte);
+    integer_operands
+);
+value_bin_impl!(
+    bit_shift_left as bit_shift_left_impl,
+    [Num, num_num],
+    <Int, int_int>,
+    (Byte, Byte, byte_byte, num_num),
+    (Byte, Num, byte_num),
+    (Num, Byte, num_byte);
+    shift_operands
+);
+value_bin_impl!(
+    bit_shift_right,
+    [Num, num_num],
+    <Int, int_int>,
+    [Byte, byte_byte],
+    (Byte, Num, byte_num),
+    (Num, Byte, num_byte);
+    shift_operands
+);
+
+impl Value {
+    /// Shift the bits of `other` left by `self`
+    ///
+    /// Bytes are widened to numbers if the result does not fit in a byte.
+    pub fn bit_shift_left(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+        let bytes = matches!((&self, &other), (Value::Byte(_), Value::Byte(_)));
+        let mut shifted = self.bit_shift_left_impl(other, env)?;
+        if bytes {
+            shifted.compress();
+        }
+        Ok(shifted)
+    }
+    /// Invert the bits of every number as a two's-complement integer of the given width
+    ///
+    /// If no width is given, the smallest of 8, 16, 32, or 64 bits that
+    /// contains every number is used. Non-negative numbers are inverted
+    /// as unsigned integers and negative ones as signed integers.
+    pub fn bit_not(self, width: Option<u32>, env: &Uiua) -> UiuaResult<Self> {
+        if let Some(width) = width {
+            if !(1..=64).contains(&width) {
+                return Err(env.error(format!(
+                    "Bit width must be between 1 and 64, but it is {width}"
+                )));
+            }
+        }
+        Ok(match self {
+            Value::Byte(bytes) if width.unwrap_or(8) >= 8 && width.unwrap_or(8) < 64 => {
+                let mask = (1u64 << width.unwrap_or(8)) - 1;
+                if mask == u8::MAX as u64 {
+                    bytes.convert_with(|b| !b).into()
+                } else {
+                    bytes.convert_with(|b| (mask - b as u64) as f64).into()
+                }
+            }
+            Value::Byte(bytes) => Value::Num(bytes.convert()).bit_not(width, env)?,
+            Value::Int(ints) => Value::Num(ints.into_nums()).bit_not(width, env)?,
+            Value::Num(nums) => {
+                if nums.data.iter().any(|n| n.fract() != 0.0) {
+                    return Err(env.error(
+                        "Argument to bit not must be integers, but it has a fractional part",
+                    ));
+                }
+                let signed = nums.data.iter().any(|&n| n < 0.0);
+                let fits = |width: u32, n: f64| {
+                    if signed {
+                        let half = 2f64.powi(width as i32 - 1);
+                        -half <= n && n < half
+                    } else {
+                        n < 2f64.powi(width as i32)
+                    }
+                };
+                let width = match width {
+                    Some(width) => {
+                        if let Some(n) = nums.data.iter().find(|&&n| !fits(width, n)) {
+                            return Err(
+                                env.error(format!("{n} does not fit in a {width}-bit integer"))
+                            );
+                        }
+                        width
+                    }
+                    None => [8, 16, 32, 64]
+                        .into_iter()
+                        .find(|&width| nums.data.iter().all(|&n| fits(width, n)))
+                        .unwrap_or(64),
+                };
+                if signed {
+                    nums.convert_with(|n| -n - 1.0).into()
+                } else {
+                    let mask = 2f64.powi(width as i32) - 1.0;
+                    nums.convert_with(|n| mask - n).into()
+                }
+            }
+            value => {
+                return Err(env.error(format!(
+                    "Cannot get the bitwise not of {}",
+                    value.type_name()
+                )))
+            }
+        })
+    }
+}
 
 value_bin_impl!(
     min,
@@ -995,6 +1896,24 @@ value_bin_impl!(
     (Num, Byte, num_byte, num_num),
 );
 
+impl Value {
+    /// Clamp every element between a low and a high bound
+    pub fn clamp(self, low: Self, high: Self, env: &Uiua) -> UiuaResult<Self> {
+        let inverted = match low.clone().is_lt(high.clone(), env)? {
+            Value::Num(nums) => nums.data.iter().any(|&n| n != 0.0),
+            Value::Byte(bytes) => bytes.data.iter().any(|&b| b != 0),
+            _ => false,
+        };
+        if inverted {
+            return Err(env.error(format!(
+                "Cannot clamp with a low bound of {low} and a high bound of {high} \
+                because the low bound is greater than the high bound"
+            )));
+        }
+        low.max(high.min(self, env)?, env)
+    }
+}
+
 macro_rules! cmp_impls {
     ($($name:ident),*) => {
         $(
@@ -1003,6 +1922,7 @@ macro_rules! cmp_impls {
                 // Value comparable
                 [Num, same_type],
                 (Byte, Byte, same_type, num_num),
+                (Int, Int, generic),
                 (Char, Char, generic),
                 (Func, Func, generic),
                 (Num, Byte, num_byte, num_num),
@@ -1028,6 +1948,16 @@ impl PartialEq for Value {
             (Value::Func(a), Value::Func(b)) => a == b,
             (Value::Num(a), Value::Byte(b)) => a == b,
             (Value::Byte(a), Value::Num(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Int(a), Value::Num(b)) => a == b,
+            (Value::Num(a), Value::Int(b)) => a == b,
+            (Value::Int(a), Value::Byte(b)) => a == b,
+            (Value::Byte(a), Value::Int(b)) => a == b,
+            (Value::Complex(a), Value::Complex(b)) => a == b,
+            (Value::Num(a), Value::Complex(b)) => a == b,
+            (Value::Complex(a), Value::Num(b)) => a == b,
+            (Value::Byte(a), Value::Complex(b)) => a == b,
+            (Value::Complex(a), Value::Byte(b)) => a == b,
             _ => false,
         }
     }
@@ -1050,10 +1980,24 @@ impl Ord for Value {
             (Value::Func(a), Value::Func(b)) => a.cmp(b),
             (Value::Num(a), Value::Byte(b)) => a.partial_cmp(b).unwrap(),
             (Value::Byte(a), Value::Num(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Int(a), Value::Num(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Num(a), Value::Int(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Int(a), Value::Byte(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Byte(a), Value::Int(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Complex(a), Value::Complex(b)) => a.cmp(b),
+            (Value::Num(a), Value::Complex(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Complex(a), Value::Num(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Byte(a), Value::Complex(b)) => a.partial_cmp(b).unwrap(),
+            (Value::Complex(a), Value::Byte(b)) => a.partial_cmp(b).unwrap(),
             (Value::Num(_), _) => Ordering::Less,
             (_, Value::Num(_)) => Ordering::Greater,
+            (Value::Int(_), _) => Ordering::Less,
+            (_, Value::Int(_)) => Ordering::Greater,
             (Value::Byte(_), _) => Ordering::Less,
             (_, Value::Byte(_)) => Ordering::Greater,
+            (Value::Complex(_), _) => Ordering::Less,
+            (_, Value::Complex(_)) => Ordering::Greater,
             (Value::Char(_), _) => Ordering::Less,
             (_, Value::Char(_)) => Ordering::Greater,
         }
@@ -1063,13 +2007,11 @@ impl Ord for Value {
 impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            Value::Num(arr) => {
-                0u8.hash(state);
-                arr.hash(state);
-            }
-            Value::Byte(arr) => {
-                1u8.hash(state);
-                arr.hash(state);
+            Value::Num(arr) => hash_numbers(&arr.shape, arr.data.iter().copied(), state),
+            Value::Byte(arr) => hash_numbers(&arr.shape, arr.data.iter().map(|&b| b as f64), state),
+            Value::Int(arr) => hash_numbers(&arr.shape, arr.data.iter().map(|&i| i as f64), state),
+            Value::Complex(arr) if arr.data.iter().all(|c| c.im == 0.0) => {
+                hash_numbers(&arr.shape, arr.data.iter().map(|c| c.re), state)
             }
             Value::Char(arr) => {
                 2u8.hash(state);
@@ -1079,15 +2021,29 @@ impl Hash for Value {
                 3u8.hash(state);
                 arr.hash(state);
             }
+            Value::Complex(arr) => {
+                4u8.hash(state);
+                arr.hash(state);
+            }
         }
     }
 }
 
+/// Hash numbers as `f64`s so that values that compare equal hash the same
+/// regardless of their numeric type
+fn hash_numbers<H: Hasher>(shape: &[usize], nums: impl Iterator<Item = f64>, state: &mut H) {
+    0u8.hash(state);
+    shape.hash(state);
+    nums.for_each(|n| n.array_hash(state));
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Num(n) => n.fmt(f),
+            Value::Int(n) => n.fmt(f),
             Value::Byte(b) => b.fmt(f),
+            Value::Complex(b) => b.fmt(f),
             Value::Char(c) => c.fmt(f),
             Value::Func(func) => {
                 if let Some(val) = func.as_boxed() {
@@ -1124,9 +2080,22 @@ impl ValueBuilder {
     }
     pub fn add_row<C: FillContext>(&mut self, mut row: Value, ctx: C) -> Result<(), C::Error> {
         if let Some(value) = &mut self.value {
-            value.append(row, ctx)?;
+            // Rows of the same type and shape are written directly into the buffer
+            if value.shape()[1..] == *row.shape() {
+                match (&mut *value, row) {
+                    (Value::Num(a), Value::Num(b)) => push_row(a, b),
+                    (Value::Int(a), Value::Int(b)) => push_row(a, b),
+                    (Value::Byte(a), Value::Byte(b)) => push_row(a, b),
+                    (Value::Complex(a), Value::Complex(b)) => push_row(a, b),
+                    (Value::Char(a), Value::Char(b)) => push_row(a, b),
+                    (Value::Func(a), Value::Func(b)) => push_row(a, b),
+                    (_, row) => value.append(row, ctx)?,
+                }
+            } else {
+                value.append(row, ctx)?;
+            }
         } else {
-            row.reserve_min(self.capacity);
+            row.reserve_min(self.capacity.saturating_mul(row.flat_len()));
             row.shape_mut().insert(0, 1);
             self.value = Some(row);
         }
@@ -1137,3 +2106,1151 @@ impl ValueBuilder {
         self.value.unwrap_or_default()
     }
 }
+
+fn push_row<T: ArrayValue>(array: &mut Array<T>, row: Array<T>) {
+    array.data.extend_from_slice(&row.data);
+    array.shape[0] += 1;
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    /// Serializes byte data with `serialize_bytes` rather than as a list of numbers
+    struct Bytes<'a>(&'a [u8]);
+
+    impl<'a> Serialize for Bytes<'a> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    struct ByteBuf(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for ByteBuf {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct Visitor;
+            impl<'de> de::Visitor<'de> for Visitor {
+                type Value = ByteBuf;
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "a byte sequence")
+                }
+                fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                    Ok(ByteBuf(v.to_vec()))
+                }
+                fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                    Ok(ByteBuf(v))
+                }
+                fn visit_seq<A: de::SeqAccess<'de>>(
+                    self,
+                    mut seq: A,
+                ) -> Result<Self::Value, A::Error> {
+                    let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                    while let Some(b) = seq.next_element()? {
+                        bytes.push(b);
+                    }
+                    Ok(ByteBuf(bytes))
+                }
+            }
+            deserializer.deserialize_byte_buf(Visitor)
+        }
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename = "Array")]
+    struct ByteArrayRef<'a> {
+        shape: &'a Shape,
+        data: Bytes<'a>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename = "Array")]
+    struct ByteArray {
+        shape: Shape,
+        data: ByteBuf,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename = "Array")]
+    struct BoxArrayRef<'a> {
+        shape: &'a Shape,
+        data: Vec<&'a Value>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename = "Array")]
+    struct BoxArray {
+        shape: Shape,
+        data: Vec<Value>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename = "Value")]
+    enum ValueRep {
+        Num(Array<f64>),
+        Int(Array<i64>),
+        Byte(ByteArray),
+        Complex(Array<Complex64>),
+        Char(Array<char>),
+        Func(BoxArray),
+    }
+
+    impl Serialize for Value {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Value::Num(array) => serializer.serialize_newtype_variant("Value", 0, "Num", array),
+                Value::Int(array) => serializer.serialize_newtype_variant("Value", 1, "Int", array),
+                Value::Byte(array) => serializer.serialize_newtype_variant(
+                    "Value",
+                    2,
+                    "Byte",
+                    &ByteArrayRef {
+                        shape: &array.shape,
+                        data: Bytes(&array.data),
+                    },
+                ),
+                Value::Complex(array) => {
+                    serializer.serialize_newtype_variant("Value", 3, "Complex", array)
+                }
+                Value::Char(array) => {
+                    serializer.serialize_newtype_variant("Value", 4, "Char", array)
+                }
+                Value::Func(array) => {
+                    let data = (array.data.iter())
+                        .map(|f| {
+                            f.as_boxed().ok_or_else(|| {
+                                ser::Error::custom(
+                                    "Cannot serialize functions that are not boxed values",
+                                )
+                            })
+                        })
+                        .collect::<Result<Vec<_>, S::Error>>()?;
+                    let rep = BoxArrayRef {
+                        shape: &array.shape,
+                        data,
+                    };
+                    serializer.serialize_newtype_variant("Value", 5, "Func", &rep)
+                }
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Value {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(match ValueRep::deserialize(deserializer)? {
+                ValueRep::Num(array) => array.into(),
+                ValueRep::Int(array) => array.into(),
+                ValueRep::Byte(ByteArray { shape, data }) => array_from_parts(shape, data.0)
+                    .map_err(de::Error::custom)?
+                    .into(),
+                ValueRep::Complex(array) => array.into(),
+                ValueRep::Char(array) => array.into(),
+                ValueRep::Func(BoxArray { shape, data }) => {
+                    let data = data.into_iter().map(|v| Arc::new(Function::boxed(v)));
+                    array_from_parts(shape, data.collect())
+                        .map_err(de::Error::custom)?
+                        .into()
+                }
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nums(value: Value) -> CowSlice<f64> {
+        match value {
+            Value::Num(arr) => arr.data,
+            v => panic!("expected numbers, got {}", v.type_name()),
+        }
+    }
+
+    fn bytes_value(bytes: &[u8]) -> Value {
+        Array::<u8>::from(bytes).into()
+    }
+
+    #[test]
+    fn complex_arith() {
+        let mut env = Uiua::with_native_sys();
+        let a: Value = [Complex64::new(1.0, 2.0), Complex64::new(0.0, -1.0)]
+            .into_iter()
+            .collect();
+        let sum = a.clone().add(Value::from(1.0), &env).unwrap();
+        assert_eq!(sum.type_name(), "complex");
+        assert_eq!(
+            *sum.as_complex_array().unwrap().data,
+            [Complex64::new(2.0, 2.0), Complex64::new(1.0, -1.0)]
+        );
+        let prod = a.clone().mul(a, &env).unwrap();
+        assert_eq!(
+            *prod.as_complex_array().unwrap().data,
+            [Complex64::new(-3.0, 4.0), Complex64::new(-1.0, 0.0)]
+        );
+        let root = Value::from(-4.0).sqrt(&env).unwrap();
+        assert_eq!(root.type_name(), "number");
+        env.with_fill(Complex64::new(0.0, 0.0).into(), |env| {
+            let root = Value::from(-4.0).sqrt(env)?;
+            assert_eq!(
+                *root.as_complex_array().unwrap().data,
+                [Complex64::new(0.0, 2.0)]
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn int_arith() {
+        let mut env = Uiua::with_native_sys();
+        let big = (1i64 << 53) + 1;
+        let value = Value::from(big);
+        assert_eq!(value.as_int(&env, "").unwrap(), big as isize);
+        let ints: Value = [big, 2].into_iter().collect();
+        assert_eq!(ints.as_integers(&env, "").unwrap(), [big as isize, 2]);
+
+        let sum = ints.add(Value::from(1i64), &env).unwrap();
+        assert!(matches!(&sum, Value::Int(arr) if *arr.data == [big + 1, 3]));
+        assert!(env.take_diagnostics().is_empty());
+
+        let overflow = Value::from(i64::MAX).mul(Value::from(2i64), &env).unwrap();
+        assert_eq!(overflow, Value::from(i64::MAX as f64 * 2.0));
+        assert_eq!(env.take_diagnostics().len(), 1);
+
+        let mut small: Value = [1i64, 255].into_iter().collect();
+        small.compress();
+        assert!(matches!(small, Value::Byte(_)));
+    }
+
+    #[test]
+    fn reshape() {
+        let env = Uiua::with_native_sys();
+        let mut value = Value::from_iter([1.0, 2.0, 3.0, 4.0]);
+        value.reshape(&[2, 2], &env).unwrap();
+        assert_eq!(value.shape(), [2, 2]);
+        value.reshape(&[-1, 1], &env).unwrap();
+        assert_eq!(value.shape(), [4, 1]);
+        assert!(value.reshape(&[-1, -1], &env).is_err());
+    }
+
+    #[test]
+    fn transpose_axes() {
+        let env = Uiua::with_native_sys();
+        let mut value: Value = (0..24).map(|i| i as f64).collect();
+        value.reshape(&[2, 3, 4], &env).unwrap();
+        let original = value.clone();
+        value.transpose_axes(&[2, 0, 1], &env).unwrap();
+        assert_eq!(value.shape(), [4, 2, 3]);
+        let Value::Num(arr) = &value else {
+            panic!("expected numbers");
+        };
+        for i in 0..4 {
+            for j in 0..2 {
+                for k in 0..3 {
+                    assert_eq!(arr.data[i * 6 + j * 3 + k], (j * 12 + k * 4 + i) as f64);
+                }
+            }
+        }
+        value.transpose_axes(&[1, 2, 0], &env).unwrap();
+        assert_eq!(value, original);
+
+        // Rank 2 matches the cycling transpose
+        let mut matrix: Value = (0..70 * 40).map(|i| i as f64).collect();
+        matrix.reshape(&[70, 40], &env).unwrap();
+        let mut expected = matrix.clone();
+        expected.transpose();
+        matrix.transpose_axes(&[1, 0], &env).unwrap();
+        assert_eq!(matrix, expected);
+
+        assert!(value.transpose_axes(&[0, 1], &env).is_err());
+        assert!(value.transpose_axes(&[0, 0, 1], &env).is_err());
+        assert!(value.transpose_axes(&[0, 1, 3], &env).is_err());
+    }
+
+    #[test]
+    fn matmul() {
+        let env = Uiua::with_native_sys();
+        let naive = |a: &[f64], b: &[f64], m: usize, n: usize, p: usize| {
+            let mut result = vec![0.0; m * p];
+            for i in 0..m {
+                for j in 0..p {
+                    for k in 0..n {
+                        result[i * p + j] += a[i * n + k] * b[k * p + j];
+                    }
+                }
+            }
+            result
+        };
+        let matrix = |data: &[f64], rows: usize| {
+            let mut value: Value = data.iter().copied().collect();
+            value.reshape(&[rows as isize, -1], &env).unwrap();
+            value
+        };
+        let size = 512;
+        let a: Vec<f64> = (0..size * size).map(|i| (i % 7) as f64 - 3.0).collect();
+        let b: Vec<f64> = (0..size * size).map(|i| (i % 5) as f64 * 0.5).collect();
+        let product = matrix(&a, size).matmul(matrix(&b, size), &env).unwrap();
+        assert_eq!(product, matrix(&naive(&a, &b, size, size, size), size));
+
+        // Bytes accumulate without overflow
+        let mut bytes = Value::from(Array::<u8>::from_iter([200, 200, 200, 200]));
+        bytes.reshape(&[2, 2], &env).unwrap();
+        let product = bytes.clone().matmul(bytes, &env).unwrap();
+        assert_eq!(product, matrix(&[80000.0; 4], 2));
+
+        // Generic path
+        let mut a: Value = [1i64, 2, 3, 4, 5, 6].into_iter().collect();
+        a.reshape(&[2, 3], &env).unwrap();
+        let b: Value = [1.0, 0.0, 1.0].into_iter().collect();
+        let product = a.clone().matmul(b, &env).unwrap();
+        assert_eq!(product.shape(), [2]);
+        assert_eq!(product, Value::from_iter([4.0, 10.0]));
+
+        let err = a.clone().matmul(a, &env).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("3 columns but the second has 2 rows"));
+    }
+
+    #[test]
+    fn shapes() {
+        use crate::array::ShapeExt;
+
+        let env = Uiua::with_native_sys();
+        let shape = Value::from_iter([2.0, 3.0]).try_into_shape(&env).unwrap();
+        assert_eq!(shape, Shape::from([2, 3].as_slice()));
+        assert_eq!(shape.product(), 6);
+        assert!(Value::from_iter([2.0, -3.0]).try_into_shape(&env).is_err());
+        let mut matrix = Value::from_iter([1.0, 2.0, 3.0, 4.0]);
+        matrix.reshape(&[2, 2], &env).unwrap();
+        assert!(matrix.try_into_shape(&env).is_err());
+
+        let shape = |dims: &[usize]| Shape::from(dims);
+        assert_eq!(
+            shape(&[1, 3]).broadcast_with(&shape(&[2, 1])),
+            Some(shape(&[2, 3]))
+        );
+        assert_eq!(
+            shape(&[4, 1, 5]).broadcast_with(&shape(&[3, 1])),
+            Some(shape(&[4, 3, 5]))
+        );
+        assert_eq!(shape(&[]).broadcast_with(&shape(&[2])), Some(shape(&[2])));
+        assert_eq!(shape(&[2, 3]).broadcast_with(&shape(&[3, 3])), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str(
+            r#"
+            [1.5 2 ¯3]
+            [1 2 3]
+            ↯2_2 "abcd"
+            {1 "hi" [2 3]}
+            "#,
+        )
+        .unwrap();
+        let mut values = env.take_stack();
+        values.push(Value::from(Array::<u8>::new(
+            tinyvec::tiny_vec![2, 2],
+            [1u8, 2, 3, 4],
+        )));
+        values.push(Value::from(Array::<i64>::new(
+            tinyvec::tiny_vec![3],
+            [1i64, -2, 3],
+        )));
+        values.push([Complex64::new(2.0, 1.0)].into_iter().collect());
+        for value in values {
+            let json = serde_json::to_string(&value).unwrap();
+            let back: Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(value, back, "{json}");
+        }
+        env.load_str("(+1)").unwrap();
+        let func = env.pop(1).unwrap();
+        assert!(serde_json::to_string(&func).is_err());
+    }
+
+    #[test]
+    fn hyperbolic_identities() {
+        let env = Uiua::with_native_sys();
+        let xs: Value = (-500..=500).map(|i| i as f64 / 250.0).collect();
+        let sinh = xs.clone().sinh(&env).unwrap();
+        let cosh = xs.clone().cosh(&env).unwrap();
+        let tanh = xs.clone().tanh(&env).unwrap();
+        let neg_sinh = xs.clone().neg(&env).unwrap().sinh(&env).unwrap();
+        let neg_cosh = xs.neg(&env).unwrap().cosh(&env).unwrap();
+        let [sinh, cosh, tanh, neg_sinh, neg_cosh] =
+            [sinh, cosh, tanh, neg_sinh, neg_cosh].map(nums);
+        for i in 0..sinh.len() {
+            let (s, c, t) = (sinh[i], cosh[i], tanh[i]);
+            // Squaring doubles the rounding error of each term
+            assert!((c * c - s * s - 1.0).abs() <= 4.0 * f64::EPSILON * c * c);
+            assert!((t - s / c).abs() <= f64::EPSILON);
+            assert_eq!(neg_sinh[i], -s);
+            assert_eq!(neg_cosh[i], c);
+        }
+        let bytes = bytes_value(&[0, 1, 2]);
+        assert_eq!(bytes.tanh(&env).unwrap().type_name(), "number");
+    }
+
+    #[test]
+    fn bitwise() {
+        let env = Uiua::with_native_sys();
+        let a = bytes_value(&[1, 200, 255]);
+        let key = bytes_value(&[7, 7, 7]);
+        let xored = key.clone().bit_xor(a.clone(), &env).unwrap();
+        assert!(matches!(xored, Value::Byte(_)));
+        assert_eq!(key.bit_xor(xored, &env).unwrap(), a);
+
+        let shifted = Value::from(1u8).bit_shift_left(a.clone(), &env).unwrap();
+        assert_eq!(shifted.type_name(), "number");
+        assert_eq!(shifted, [2.0, 400.0, 510.0].into_iter().collect());
+
+        let inverted = Value::from(5.0).bit_not(Some(4), &env).unwrap();
+        assert_eq!(inverted, Value::from(10.0));
+        assert!(Value::from(16.0).bit_not(Some(4), &env).is_err());
+        assert_eq!(a.bit_not(None, &env).unwrap(), bytes_value(&[254, 55, 0]));
+    }
+
+    #[test]
+    fn grade() {
+        let env = Uiua::with_native_sys();
+        let value: Value = [3.0, 1.0, 2.0].into_iter().collect();
+        assert_eq!(*value.grade(&env).unwrap().data, [1, 2, 0]);
+
+        let value: Value = [2.0, 1.0, 2.0, 1.0, 0.0].into_iter().collect();
+        let grade = value.grade(&env).unwrap();
+        assert_eq!(*grade.data, [4, 1, 3, 0, 2]);
+        let sorted = Value::from(grade).select(&value, &env).unwrap();
+        assert_eq!(sorted, [0.0, 1.0, 1.0, 2.0, 2.0].into_iter().collect());
+
+        let rows = Value::from(Array::<f64>::new(
+            tinyvec::tiny_vec![3, 2],
+            [2.0, 1.0, 1.0, 5.0, 1.0, 2.0],
+        ));
+        assert_eq!(*rows.grade(&env).unwrap().data, [2, 1, 0]);
+    }
+
+    #[test]
+    fn unique_counts() {
+        let env = Uiua::with_native_sys();
+        let value: Value = [3.0, 1.0, 3.0, 2.0, 1.0, 3.0].into_iter().collect();
+        let (unique, counts) = value.unique_counts(&env).unwrap();
+        assert_eq!(unique, [3.0, 1.0, 2.0].into_iter().collect());
+        assert_eq!(counts, [3usize, 2, 1].into_iter().collect());
+
+        let boxes: Value = ["a", "b", "a"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Array<_>>()
+            .into();
+        let (unique, counts) = boxes.unique_counts(&env).unwrap();
+        assert_eq!(unique.row_count(), 2);
+        assert_eq!(counts, [2usize, 1].into_iter().collect());
+
+        let empty = Value::from(Array::<f64>::new(tinyvec::tiny_vec![0, 3], []));
+        let (unique, counts) = empty.unique_counts(&env).unwrap();
+        assert_eq!(unique.shape(), [0, 3]);
+        assert_eq!(counts.shape(), [0]);
+
+        assert!(Value::from(5.0).unique_counts(&env).is_err());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let env = Uiua::with_native_sys();
+        let json = r#"{"name":"Uiua","tags":["array","stack"],"mixed":[1,"two",[3,4]],"nested":{"ok":1,"empty":[],"none":null}}"#;
+        let value = Value::from_json_string(json, &env).unwrap();
+        assert_eq!(value.shape(), [4, 2]);
+        let encoded = value.to_json_string(&env).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+        let actual: serde_json::Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(actual, expected);
+
+        let mixed = Value::from_json_string(r#"[1,"two",[3,4]]"#, &env).unwrap();
+        assert_eq!(mixed.type_name(), "function");
+        assert_eq!(mixed.shape(), [3]);
+        let numbers = Value::from_json_string("[[1,2],[3,4]]", &env).unwrap();
+        assert_eq!(numbers.type_name(), "number");
+        assert_eq!(numbers.shape(), [2, 2]);
+
+        let err = Value::from_json_string("[1, 2, x]", &env).unwrap_err();
+        assert!(err.to_string().contains("byte 7"), "{err}");
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let env = Uiua::with_native_sys();
+        let csv = "Item,Price,Notes\r\n\
+            Apples,1.5,\"red, green\"\r\n\
+            \r\n\
+            Pears,2,\"says \"\"hi\"\"\nthen leaves\"\r\n";
+        let value = Value::from_csv_string(csv, &env).unwrap();
+        assert_eq!(value.shape(), [3, 3]);
+        let notes = value.row(2).row(2);
+        let notes = notes.as_func_array().unwrap().as_boxed().unwrap();
+        assert_eq!(
+            notes.as_string(&env, "").unwrap(),
+            "says \"hi\"\nthen leaves"
+        );
+        let encoded = value.to_csv_string(&env).unwrap();
+        assert_eq!(Value::from_csv_string(&encoded, &env).unwrap(), value);
+
+        let empty = Value::from_csv_string("", &env).unwrap();
+        assert_eq!(empty.shape(), [0, 0]);
+
+        let err = Value::from_csv_string("a,b\n1,\"2\n", &env).unwrap_err();
+        assert!(err.to_string().contains("line 2, column 3"), "{err}");
+        let err = Value::from_csv_string("a,b\n1,2,3", &env).unwrap_err();
+        assert!(err.to_string().contains("record 2"), "{err}");
+    }
+
+    #[test]
+    fn simd_arithmetic() {
+        use crate::algorithm::simd::{SimdOp, SIMD_MIN_LEN};
+
+        let env = Uiua::with_native_sys();
+        let check = |op: SimdOp, a: &[f64], b: &[f64], result: &[f64]| {
+            assert_eq!(result.len(), a.len());
+            for ((&a, &b), &r) in a.iter().zip(b).zip(result) {
+                let expected = op.scalar(a, b);
+                if expected.is_nan() {
+                    assert!(r.is_nan(), "{op:?} {a} {b} gave {r}");
+                } else {
+                    assert_eq!(r.to_bits(), expected.to_bits(), "{op:?} {a} {b}");
+                }
+            }
+        };
+
+        // Several parallel chunks plus a partial one
+        let len = 3 * env.parallel_min_len() + SIMD_MIN_LEN + 1;
+        let a: Vec<f64> = (0..len).map(|i| (i % 1000) as f64 * 0.1 - 17.3).collect();
+        let b: Vec<f64> = (0..len).map(|i| (i % 777) as f64 / 3.0).collect();
+        let sum = Value::from_iter(a.iter().copied())
+            .add(Value::from_iter(b.iter().copied()), &env)
+            .unwrap();
+        check(SimdOp::Add, &a, &b, &nums(sum));
+
+        // Odd lengths exercise the scalar tail, and special values propagate
+        let specials = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 0.0, -0.0];
+        let a: Vec<f64> = (0..100_003)
+            .map(|i| match i % 11 {
+                0..=4 => specials[i % 5],
+                _ => i as f64 * 1.25 - 500.0,
+            })
+            .collect();
+        let b: Vec<f64> = (0..100_003)
+            .map(|i| match i % 13 {
+                0..=4 => specials[(i + 2) % 5],
+                _ => (i % 97) as f64 - 48.5,
+            })
+            .collect();
+        for (op, f) in [
+            (
+                SimdOp::Add,
+                Value::add as fn(Value, Value, &Uiua) -> UiuaResult<Value>,
+            ),
+            (SimdOp::Sub, Value::sub),
+            (SimdOp::Mul, Value::mul),
+            (SimdOp::Div, Value::div),
+        ] {
+            let av = Value::from_iter(a.iter().copied());
+            let bv = Value::from_iter(b.iter().copied());
+            // The second operand is unique
+            check(op, &a, &b, &nums(f(av.clone(), bv.clone(), &env).unwrap()));
+            // The second operand is shared
+            let result = f(av.clone(), bv.clone(), &env).unwrap();
+            drop(bv);
+            check(op, &a, &b, &nums(result));
+            // Both operands are the same array
+            check(op, &a, &a, &nums(f(av.clone(), av, &env).unwrap()));
+        }
+    }
+
+    #[test]
+    fn rows_share_data() {
+        let env = Uiua::with_native_sys();
+        let mut value: Value = (0..800_000).map(|i| i as f64).collect();
+        value.reshape(&[100_000, 8], &env).unwrap();
+        let Value::Num(array) = &value else {
+            unreachable!()
+        };
+        let parent = array.data.as_ptr();
+        let row_ptr = |row: &Value| match row {
+            Value::Num(row) => row.data.as_ptr(),
+            _ => unreachable!(),
+        };
+        // Rows are views into the same buffer
+        for (i, row) in value.rows().enumerate() {
+            assert_eq!(row_ptr(&row), parent.wrapping_add(i * 8));
+        }
+        assert_eq!(row_ptr(&value.row(99_999)), parent.wrapping_add(99_999 * 8));
+        for (i, row) in value.clone().into_rows().enumerate() {
+            assert_eq!(row_ptr(&row), parent.wrapping_add(i * 8));
+        }
+        let last = value.clone().into_rows_rev().next().unwrap();
+        assert_eq!(last, value.row(99_999));
+
+        // Mutating a row copies it
+        let Value::Num(mut row) = value.row(1) else {
+            unreachable!()
+        };
+        row.data.as_mut_slice()[0] = -1.0;
+        assert_ne!(row.data.as_ptr(), parent.wrapping_add(8));
+        assert_eq!(row.data[0], -1.0);
+        assert_eq!(value.row(1).row(0), Value::from(8.0));
+    }
+
+    #[test]
+    fn builder() {
+        let env = Uiua::with_native_sys();
+        let data_ptr = |builder: &ValueBuilder| match &builder.value {
+            Some(Value::Byte(arr)) => arr.data.as_ptr(),
+            _ => unreachable!(),
+        };
+        let rows = 1_000_000;
+        let mut builder = Value::builder(rows);
+        builder
+            .add_row(Value::from(Array::<u8>::from_iter([0, 1, 2])), &env)
+            .unwrap();
+        // The buffer is allocated for every row up front
+        let ptr = data_ptr(&builder);
+        for i in 1..rows {
+            let row = Array::<u8>::from_iter([i as u8, 1, 2]);
+            builder.add_row(Value::from(row), &env).unwrap();
+        }
+        assert_eq!(data_ptr(&builder), ptr);
+        let value = builder.finish();
+        assert_eq!(value.shape(), [rows, 3]);
+        assert_eq!(
+            value.row(rows - 1),
+            Value::from(Array::<u8>::from_iter([63, 1, 2]))
+        );
+
+        // Rows of different types are still unified
+        let mut builder = Value::builder(2);
+        builder.add_row(Value::from(1u8), &env).unwrap();
+        builder.add_row(Value::from(2.5), &env).unwrap();
+        assert_eq!(builder.finish(), Value::from_iter([1.0, 2.5]));
+
+        // Rows must have the same shape
+        let mut builder = Value::builder(2);
+        builder.add_row(Value::from_iter([1.0, 2.0]), &env).unwrap();
+        let err = builder.add_row(Value::from_iter([1.0]), &env).unwrap_err();
+        assert!(err.to_string().contains("Cannot append"), "{err}");
+    }
+
+    #[test]
+    fn append_shape_mismatch() {
+        let mut env = Uiua::with_native_sys();
+        let mut matrix = Value::from_iter([1.0, 2.0, 3.0, 4.0]);
+        matrix.reshape(&[2, 2], &env).unwrap();
+        let err = matrix
+            .append(Value::from_iter([5.0, 6.0, 7.0]), &env)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Cannot append row of shape [3] to array with row shape [2]"),
+            "{err}"
+        );
+
+        // Fills still pad mismatched rows
+        env.load_str("⬚0⊂ ↯2_2 1 [5 6 7]").unwrap();
+        assert_eq!(env.take_stack()[0].shape(), [3, 3]);
+    }
+
+    #[test]
+    fn widen_to_num() {
+        let env = Uiua::with_native_sys();
+        let mut value = bytes_value(&[1, 2, 3]);
+        value.widen_to_num();
+        assert!(matches!(value, Value::Num(_)));
+        assert_eq!(value, Value::from_iter([1.0, 2.0, 3.0]));
+        let halves = Value::from(2.0).div(value.clone(), &env).unwrap();
+        assert_eq!(halves, Value::from_iter([0.5, 1.0, 1.5]));
+
+        // Numbers are unchanged
+        let copy = value.clone();
+        value.widen_to_num();
+        assert_eq!(value, copy);
+        let mut chars = Value::from("ab");
+        chars.widen_to_num();
+        assert_eq!(chars, Value::from("ab"));
+    }
+
+    #[test]
+    fn rank_slice() {
+        let env = Uiua::with_native_sys();
+        let mut value: Value = (0..24).map(|i| i as f64).collect();
+        value.reshape(&[2, 3, 4], &env).unwrap();
+        let vector = value.rank_slice(&[1, 2], &env).unwrap();
+        assert_eq!(vector, Value::from_iter([20.0, 21.0, 22.0, 23.0]));
+        assert_eq!(
+            value.rank_slice(&[1, 2, 3], &env).unwrap(),
+            Value::from(23.0)
+        );
+        assert_eq!(value.rank_slice(&[], &env).unwrap(), value);
+
+        let err = value.rank_slice(&[1, 3], &env).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Index 3 is out of bounds of length 3 (axis 1)"),
+            "{err}"
+        );
+        assert!(value.rank_slice(&[0, 0, 0, 0], &env).is_err());
+    }
+
+    #[test]
+    fn map_leaves() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("{1 {2_3 \"a\"} [4 5]}").unwrap();
+        let value = env.pop(1).unwrap();
+        let doubled = value
+            .map_leaves(|leaf| match leaf {
+                Value::Num(_) | Value::Byte(_) => Value::from(2.0).mul(leaf.clone(), &env),
+                _ => Ok(leaf.clone()),
+            })
+            .unwrap();
+        env.load_str("{2 {4_6 \"a\"} [8 10]}").unwrap();
+        assert_eq!(doubled, env.pop(1).unwrap());
+
+        // Deep nesting does not overflow the stack
+        let mut deep = Value::from(1.0);
+        for _ in 0..100_000 {
+            deep = Value::from(Function::boxed(deep));
+        }
+        let mapped = deep.map_leaves(|_| Ok(Value::from(2.0))).unwrap();
+        let mut inner = &mapped;
+        let mut depth = 0;
+        while let Value::Func(arr) = inner {
+            inner = arr.as_boxed().unwrap();
+            depth += 1;
+        }
+        assert_eq!(depth, 100_000);
+        assert_eq!(*inner, Value::from(2.0));
+    }
+
+    #[test]
+    fn shape_broadcast_compatible() {
+        let env = Uiua::with_native_sys();
+        let shaped = |shape: &[isize]| {
+            let mut value: Value = (0..shape.iter().product::<isize>())
+                .map(|i| i as f64)
+                .collect();
+            value.reshape(shape, &env).unwrap();
+            value
+        };
+        assert!(shaped(&[3, 1]).shape_broadcast_compatible(&shaped(&[1, 4])));
+        assert!(shaped(&[2, 3, 4]).shape_broadcast_compatible(&shaped(&[4])));
+        assert!(Value::from(1.0).shape_broadcast_compatible(&shaped(&[2, 2])));
+        assert!(!shaped(&[3, 2]).shape_broadcast_compatible(&shaped(&[4, 2])));
+        assert!(!shaped(&[3]).shape_broadcast_compatible(&shaped(&[3, 2])));
+        // The prefix check is unchanged
+        assert!(shaped(&[3]).shape_prefixes_match(&shaped(&[3, 2])));
+        assert!(!shaped(&[3, 1]).shape_prefixes_match(&shaped(&[1, 4])));
+    }
+
+    #[test]
+    fn to_source() {
+        let mut env = Uiua::with_native_sys();
+        let round_trip = |value: &Value| {
+            let mut env = Uiua::with_native_sys();
+            let source = value.to_source(&env).unwrap();
+            env.load_str(&source).unwrap();
+            let parsed = env.pop(1).unwrap();
+            assert_eq!(&parsed, value, "{source}");
+            source
+        };
+        let mut matrix = Value::from_iter([1.0, -2.5, 3.0, 4.0]);
+        matrix.reshape(&[2, 2], &env).unwrap();
+        assert_eq!(round_trip(&matrix), "[[1 ¯2.5] [3 4]]");
+        assert_eq!(round_trip(&Value::from(1e300)), "1e300");
+        assert_eq!(round_trip(&Value::from(-2.5e-7)), "¯2.5e¯7");
+        round_trip(&Value::from_iter([
+            0.1,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            1.0 / 3.0,
+        ]));
+        round_trip(&Value::from("a \"b\"\\c\n\t\0_"));
+        round_trip(&Value::from(' '));
+        round_trip(&Value::from('\n'));
+        round_trip(&Array::<char>::new(tinyvec::tiny_vec![2, 2], ['a', 'b', 'c', 'd']).into());
+        round_trip(&Array::<f64>::new(tinyvec::tiny_vec![0, 3], []).into());
+        round_trip(&Array::<char>::new(tinyvec::tiny_vec![0], []).into());
+        round_trip(&bytes_value(&[0, 1, 255]));
+
+        let source = Value::from(f64::NAN).to_source(&env).unwrap();
+        env.load_str(&source).unwrap();
+        let Value::Num(nan) = env.pop(1).unwrap() else {
+            panic!("expected a number")
+        };
+        assert!(nan.data[0].is_nan());
+
+        env.load_str("{1 {2_3 \"a\"} [4 5]}").unwrap();
+        let boxes = env.pop(1).unwrap();
+        let source = boxes.to_source(&env).unwrap();
+        env.load_str(&source).unwrap();
+        assert_eq!(env.pop(1).unwrap(), boxes, "{source}");
+
+        env.load_str("(+1)").unwrap();
+        let func = env.pop(1).unwrap();
+        assert!(func.to_source(&env).is_err());
+    }
+
+    #[test]
+    fn deep_clone_detached() {
+        let original = Value::from_row_values_infallible([
+            Value::from(Function::boxed(Value::from_iter([1.0, 2.0, 3.0]))),
+            Value::from(Function::boxed(Value::from("abc"))),
+        ]);
+        let shared = original.clone();
+        let Value::Func(shared_arr) = &shared else {
+            unreachable!()
+        };
+        let mut detached = shared.deep_clone_detached();
+        assert_eq!(detached, original);
+        let Value::Func(detached_arr) = &mut detached else {
+            unreachable!()
+        };
+        assert!(!detached_arr.data.is_copy_of(&shared_arr.data));
+        let inner = Arc::make_mut(&mut detached_arr.data.as_mut_slice()[0])
+            .as_boxed_mut()
+            .unwrap();
+        let Value::Num(inner) = inner else {
+            unreachable!()
+        };
+        inner.data.as_mut_slice()[0] = 10.0;
+        assert_ne!(detached, original);
+        assert_eq!(shared, original);
+        let Value::Func(original_arr) = &original else {
+            unreachable!()
+        };
+        let original_inner = original_arr.data[0].as_boxed().unwrap();
+        assert_eq!(*original_inner, Value::from_iter([1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn fold_rows() {
+        let env = Uiua::with_native_sys();
+        let sum = |value: Value| value.fold_rows(Value::from(0.0), |acc, row| row.add(acc, &env));
+        let value = Value::from_iter([1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(sum(value).unwrap(), Value::from(10.0));
+        let empty = Value::from_iter(Vec::<f64>::new());
+        assert_eq!(sum(empty).unwrap(), Value::from(0.0));
+        let calls = calls_until_error(&env, |f| {
+            Value::from_iter([1.0, 2.0, 3.0]).fold_rows(Value::from(0.0), f)
+        });
+        assert_eq!(calls, 2);
+    }
+
+    /// Run a row combinator with a function that fails on its second call,
+    /// returning how many times the function was called
+    fn calls_until_error(
+        env: &Uiua,
+        run: impl FnOnce(&mut dyn FnMut(Value, Value) -> UiuaResult<Value>) -> UiuaResult<Value>,
+    ) -> usize {
+        let mut calls = 0;
+        let res = run(&mut |a, b| {
+            calls += 1;
+            if calls == 2 {
+                Err(env.error("Stop"))
+            } else {
+                a.add(b, env)
+            }
+        });
+        assert!(res.is_err());
+        calls
+    }
+
+    #[test]
+    fn window() {
+        let env = Uiua::with_native_sys();
+        let run = |code: &str| {
+            let mut env = Uiua::with_native_sys();
+            env.load_str(code).unwrap();
+            env.pop(1).unwrap()
+        };
+        let value = run("[1 2 3 4]");
+        assert_eq!(value.window(2, &env).unwrap(), run("[[1 2] [2 3] [3 4]]"));
+        assert_eq!(value.window(4, &env).unwrap(), run("[[1 2 3 4]]"));
+        let matrix = run("↯3_2 ⇡6");
+        assert_eq!(matrix.window(2, &env).unwrap().shape(), [2, 2, 2]);
+        assert!(value.window(0, &env).is_err());
+        assert!(value.window(5, &env).is_err());
+        assert!(Value::from(1.0).window(1, &env).is_err());
+    }
+
+    #[test]
+    fn partition_by() {
+        let env = Uiua::with_native_sys();
+        let value = Value::from_iter([1.0, 1.0, 2.0, 2.0, 2.0, 1.0]);
+        let groups = value.partition_by(|row| Ok(row.clone())).unwrap();
+        assert_eq!(
+            groups,
+            [
+                Value::from_iter([1.0, 1.0]),
+                Value::from_iter([2.0, 2.0, 2.0]),
+                Value::from_iter([1.0]),
+            ]
+        );
+        let words = Value::from_row_values_infallible(["ab", "ac", "bd", "ae"].map(Value::from));
+        let by_first = words.partition_by(|row| Ok(row.row(0))).unwrap();
+        assert_eq!(by_first.len(), 3);
+        assert_eq!(by_first[0].shape(), [2, 2]);
+        let empty = Value::from_iter(Vec::<f64>::new());
+        assert!(empty
+            .partition_by(|row| Ok(row.clone()))
+            .unwrap()
+            .is_empty());
+        assert!(value.partition_by(|_| Err(env.error("oops"))).is_err());
+    }
+
+    #[test]
+    fn reverse_axis() {
+        let env = Uiua::with_native_sys();
+        let mut value = Value::from_iter([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        value.reshape(&[2, 3], &env).unwrap();
+        let mut rows_flipped = value.clone();
+        rows_flipped.reverse_axis(1, &env).unwrap();
+        assert_eq!(
+            rows_flipped.as_num_array().unwrap().data,
+            [3.0, 2.0, 1.0, 6.0, 5.0, 4.0]
+        );
+        let mut leading = value.clone();
+        leading.reverse_axis(0, &env).unwrap();
+        let mut reversed = value.clone();
+        reversed.reverse();
+        assert_eq!(leading, reversed);
+
+        let mut cube: Value = Array::<u8>::new(
+            tinyvec::tiny_vec![2, 3, 2],
+            (0..12).collect::<Vec<_>>().as_slice(),
+        )
+        .into();
+        cube.reverse_axis(1, &env).unwrap();
+        assert_eq!(
+            cube.as_byte_array().unwrap().data,
+            [4, 5, 2, 3, 0, 1, 10, 11, 8, 9, 6, 7]
+        );
+
+        let err = value.reverse_axis(2, &env).unwrap_err();
+        assert!(err.to_string().contains("rank 2"), "{err}");
+        assert!(Value::from(1.0).reverse_axis(0, &env).is_err());
+    }
+
+    #[test]
+    fn rotate_axis() {
+        let env = Uiua::with_native_sys();
+        let vector = Value::from_iter([1.0, 2.0, 3.0, 4.0, 5.0]);
+        let mut rotated = vector.clone();
+        rotated.rotate_axis(2, 0, &env).unwrap();
+        assert_eq!(rotated, Value::from_iter([3.0, 4.0, 5.0, 1.0, 2.0]));
+        assert_eq!(
+            rotated,
+            Value::from(2).rotate(vector.clone(), &env).unwrap()
+        );
+        rotated.rotate_axis(-2, 0, &env).unwrap();
+        assert_eq!(rotated, vector);
+        rotated.rotate_axis(10, 0, &env).unwrap();
+        assert_eq!(rotated, vector);
+
+        let mut matrix = Value::from_iter([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        matrix.reshape(&[2, 3], &env).unwrap();
+        matrix.rotate_axis(-1, 1, &env).unwrap();
+        assert_eq!(
+            matrix.as_num_array().unwrap().data,
+            [3.0, 1.0, 2.0, 6.0, 4.0, 5.0]
+        );
+        let err = matrix.rotate_axis(1, 2, &env).unwrap_err();
+        assert!(err.to_string().contains("rank 2"), "{err}");
+    }
+
+    #[test]
+    fn cross() {
+        let mut env = Uiua::with_native_sys();
+        let a = Value::from_iter([1.0, 2.0]);
+        let b = Value::from_iter([3.0, 4.0]);
+        let crossed = a.cross(&b);
+        assert_eq!(crossed.shape(), [4, 2]);
+        env.load_str("{1 3 1 4 2 3 2 4}").unwrap();
+        let mut expected = env.pop(1).unwrap();
+        expected.reshape(&[4, 2], &env).unwrap();
+        assert_eq!(crossed, expected);
+
+        let empty = Value::from_iter(Vec::<f64>::new());
+        assert_eq!(empty.cross(&b).shape(), [0, 2]);
+        assert_eq!(b.cross(&empty).shape(), [0, 2]);
+    }
+
+    #[test]
+    fn chunks() {
+        let mut env = Uiua::with_native_sys();
+        let value = Value::from_iter([1.0, 2.0, 3.0, 4.0, 5.0]);
+        env.load_str("{[1 2] [3 4] [5]}").unwrap();
+        assert_eq!(value.chunks(2, true, &env).unwrap(), env.pop(1).unwrap());
+        env.load_str("{[1 2] [3 4]}").unwrap();
+        assert_eq!(value.chunks(2, false, &env).unwrap(), env.pop(1).unwrap());
+        assert_eq!(value.chunks(5, false, &env).unwrap().row_count(), 1);
+        assert_eq!(value.chunks(6, false, &env).unwrap().row_count(), 0);
+        assert!(value.chunks(0, true, &env).is_err());
+        assert!(Value::from(1.0).chunks(1, true, &env).is_err());
+    }
+
+    #[test]
+    fn mask_select() {
+        let mut env = Uiua::with_native_sys();
+        let value = Value::from_iter([10.0, 20.0, 30.0]);
+        let mask = Value::from_iter([1u8, 0, 1]);
+        assert_eq!(
+            value.mask_select(&mask, &env).unwrap(),
+            Value::from_iter([10.0, 30.0])
+        );
+        env.load_str("[1_2 3_4 5_6]").unwrap();
+        let matrix = env.pop(1).unwrap();
+        let selected = matrix.mask_select(&Value::from_iter([0.0, 1.0, 0.0]), &env);
+        assert_eq!(selected.unwrap().shape(), [1, 2]);
+        let err = value
+            .mask_select(&Value::from_iter([1u8, 0]), &env)
+            .unwrap_err();
+        assert!(err.to_string().contains("length 2"), "{err}");
+        assert!(value
+            .mask_select(&Value::from_iter([1u8, 2, 0]), &env)
+            .is_err());
+        assert!(value
+            .mask_select(&Value::from_iter([1.0, 0.5, 0.0]), &env)
+            .is_err());
+    }
+
+    #[test]
+    fn scan_rows() {
+        let env = Uiua::with_native_sys();
+        let sum = |a: Value, b: Value| a.add(b, &env);
+        let value = Value::from_iter([1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            value.scan_rows(sum, &env).unwrap(),
+            Value::from_iter([1.0, 3.0, 6.0, 10.0])
+        );
+        let empty = Value::from_iter(Vec::<f64>::new());
+        assert_eq!(empty.clone().scan_rows(sum, &env).unwrap(), empty);
+        assert!(Value::from(1.0).scan_rows(sum, &env).is_err());
+        let calls = calls_until_error(&env, |f| {
+            Value::from_iter([1.0, 2.0, 3.0]).scan_rows(f, &env)
+        });
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn outer() {
+        let mut env = Uiua::with_native_sys();
+        let add = |a: Value, b: Value| a.add(b, &env);
+        let xs = Value::from_iter([1.0, 2.0]);
+        let ys = Value::from_iter([10.0, 20.0, 30.0]);
+        let sums = xs.outer(&ys, add, &env).unwrap();
+        assert_eq!(sums.shape(), [2, 3]);
+        assert_eq!(sums.row(0), Value::from_iter([11.0, 21.0, 31.0]));
+        assert_eq!(sums.row(1), Value::from_iter([12.0, 22.0, 32.0]));
+        let empty = Value::from_iter(Vec::<f64>::new());
+        assert_eq!(xs.outer(&empty, add, &env).unwrap().shape(), [2, 0]);
+        env.load_str("[1_2 3_4]").unwrap();
+        let matrix = env.pop(1).unwrap();
+        let pairs = xs.outer(&matrix, |a, b| a.join(b, &env), &env).unwrap();
+        assert_eq!(pairs.shape(), [2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn f32_round_trip() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("[1_2_3 4.5_6_¯7]").unwrap();
+        let value = env.pop(1).unwrap();
+        let (shape, data) = value.to_f32_vec(&env).unwrap();
+        assert_eq!(shape.as_slice(), [2, 3]);
+        assert_eq!(data, [1.0, 2.0, 3.0, 4.5, 6.0, -7.0]);
+        assert_eq!(Value::from_f32_slice(&shape, &data), value);
+        let (_, bytes) = Value::from_iter([1u8, 255]).to_f32_vec(&env).unwrap();
+        assert_eq!(bytes, [1.0, 255.0]);
+        let err = Value::from("abc").to_f32_vec(&env).unwrap_err();
+        assert!(err.to_string().contains("character"), "{err}");
+
+        let precise = Value::from_iter([0.1, 1e300]);
+        precise.to_f32_vec_with_tolerance(1e-3, &env).unwrap();
+        assert_eq!(env.take_diagnostics().len(), 1);
+        let (_, data) = Value::from_iter([0.1])
+            .to_f32_vec_with_tolerance(1e-3, &env)
+            .unwrap();
+        assert_eq!(data, [0.1]);
+        assert!(env.take_diagnostics().is_empty());
+        // Integers above 2^24 lose precision too
+        Value::from(Array::<i64>::from_iter([1, 16_777_217]))
+            .to_f32_vec_with_tolerance(1e-9, &env)
+            .unwrap();
+        assert_eq!(env.take_diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn msgpack() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str(r#"[{"name" "uiua"} {"tags" {"arrays" "stack"}} {"data" [1.5 ¯2 300]} {"bytes" utf "hi"}]"#)
+            .unwrap();
+        let value = env.pop(1).unwrap();
+        let encoded = value.to_msgpack(&env).unwrap();
+        assert_eq!(encoded[0], 0x84);
+        assert_eq!(Value::from_msgpack(&encoded, &env).unwrap(), value);
+
+        let bytes = Value::from_iter([1u8, 2, 3]).to_msgpack(&env).unwrap();
+        assert_eq!(bytes, [0xc4, 3, 1, 2, 3]);
+        let nums = Value::from_iter([1.0, -1.0, 0.5]).to_msgpack(&env).unwrap();
+        assert_eq!(&nums[..3], [0x93, 0x01, 0xff]);
+
+        let err = Value::from_msgpack(&[0xd4, 1, 0], &env).unwrap_err();
+        assert!(err.to_string().contains("extension"), "{err}");
+        assert!(Value::from_msgpack(&[0x92, 1], &env).is_err());
+        assert!(Value::from_msgpack(&[1, 2], &env).is_err());
+    }
+
+    #[test]
+    fn pack_bits() {
+        let env = Uiua::with_native_sys();
+        let mask = Value::from_iter([1u8, 0, 1, 1, 0, 0, 0, 1, 1, 1, 0, 0, 1]);
+        let packed = mask.pack_bits(&env).unwrap();
+        assert_eq!(packed, Value::from_iter([0b1011_0001u8, 0b1100_1000]));
+        assert_eq!(packed.unpack_bits(13, &env).unwrap(), mask);
+        assert!(packed.unpack_bits(17, &env).is_err());
+
+        let matrix: Value = Array::new(
+            [2, 3].as_slice(),
+            EcoVec::from([1.0, 0.0, 1.0, 0.0, 1.0, 1.0]),
+        )
+        .into();
+        let packed = matrix.pack_bits(&env).unwrap();
+        assert_eq!(packed.shape(), [2, 1]);
+        assert_eq!(packed.unpack_bits(3, &env).unwrap(), matrix);
+
+        assert!(Value::from_iter([1.0, 2.0]).pack_bits(&env).is_err());
+        assert!(Value::from(1.0).pack_bits(&env).is_err());
+    }
+
+    #[test]
+    fn numeric_hash_consistency() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |value: Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        let num = Value::from(Array::<f64>::from([2.0, 0.0, 255.0].as_slice()));
+        let int = Value::from(Array::<i64>::from([2, 0, 255].as_slice()));
+        let byte = Value::from(Array::<u8>::from([2, 0, 255].as_slice()));
+        let complex = Value::from(Array::<Complex64>::from(
+            [2.0, -0.0, 255.0].map(Complex64::from).as_slice(),
+        ));
+        for value in [int, byte, complex] {
+            assert_eq!(value, num);
+            assert_eq!(hash(value), hash(num.clone()));
+        }
+    }
+}